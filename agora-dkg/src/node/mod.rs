@@ -40,9 +40,7 @@ impl<P> Node<P> {
 
     pub fn collect_participant(&mut self, pubkey: G2Affine) {
         let address = Address::from(&pubkey);
-        if self.participants.get(&address).is_none() {
-            self.participants.insert(address, pubkey);
-        }
+        self.participants.entry(address).or_insert(pubkey);
     }
 }
 
@@ -132,7 +130,7 @@ impl Node<ShareCollection> {
         shares: Vec<PublicShare>,
     ) -> Result<(), anyhow::Error> {
         ensure!(
-            self.participants.get(&address).is_some(),
+            self.participants.contains_key(&address),
             "no such participant registered"
         );
         self.phase.shares_map.insert(address, shares)?;
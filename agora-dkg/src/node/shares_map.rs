@@ -69,11 +69,11 @@ impl SharesMap {
             return Err(SharesMapError::SharesMapFull);
         }
 
-        if self.map.get(&address).is_some() {
-            Err(SharesMapError::SharesAlreadyProvided(address))
-        } else {
-            self.map.insert(address, share_vec);
+        if let std::collections::btree_map::Entry::Vacant(entry) = self.map.entry(address) {
+            entry.insert(share_vec);
             Ok(())
+        } else {
+            Err(SharesMapError::SharesAlreadyProvided(address))
         }
     }
 
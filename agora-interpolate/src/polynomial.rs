@@ -4,6 +4,7 @@ use std::ops::{AddAssign, Mul, MulAssign, Neg, SubAssign};
 use zeroize::Zeroize;
 
 #[cfg_attr(feature = "zeroize-poly", derive(Zeroize))]
+#[cfg_attr(feature = "zeroize-poly", zeroize(bound = "Y: Zeroize"))]
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Polynomial<Y> {
     coeffs: Vec<Y>,
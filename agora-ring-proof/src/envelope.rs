@@ -0,0 +1,231 @@
+use crate::{Error, RingProof, Scheme, Statement, TriptychSignature, ZkAttest};
+
+use rand_core::{CryptoRng, RngCore};
+
+/// Serialized proof of any scheme, tagged with its scheme.
+///
+/// The binary encoding is the id of the scheme followed by the encoding of
+/// the proof in the scheme.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProofEnvelope {
+    scheme: Scheme,
+    payload: Vec<u8>,
+}
+
+/// A ring parsed for every scheme, so it is parsed once for any number of
+/// enveloped proofs.
+pub struct AnyRing {
+    triptych: <TriptychSignature as RingProof>::Ring,
+    zk_attest: <ZkAttest as RingProof>::Ring,
+}
+
+impl AnyRing {
+    pub fn parse<S: AsRef<str>>(keys: &[S]) -> Result<Self, Error> {
+        Ok(Self {
+            triptych: TriptychSignature::parse_ring(keys)?,
+            zk_attest: ZkAttest::parse_ring(keys)?,
+        })
+    }
+}
+
+impl ProofEnvelope {
+    pub fn seal<P: RingProof>(proof: &P) -> Self {
+        Self {
+            scheme: P::SCHEME,
+            payload: proof.to_bytes(),
+        }
+    }
+
+    pub fn scheme(&self) -> Scheme {
+        self.scheme
+    }
+
+    /// Decodes the proof if it is a proof of `P`.
+    pub fn open<P: RingProof>(&self) -> Result<P, Error> {
+        if self.scheme != P::SCHEME {
+            return Err(Error::SchemeMismatch {
+                expected: P::SCHEME,
+                found: self.scheme,
+            });
+        }
+        P::from_bytes(&self.payload)
+    }
+
+    /// Verifies the proof with the verifier of its scheme.
+    pub fn verify<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        ring: &AnyRing,
+        statement: &Statement,
+    ) -> Result<(), Error> {
+        // NOTE both proof types have inherent `verify` methods
+        match self.scheme {
+            Scheme::Triptych => {
+                let proof = self.open::<TriptychSignature>()?;
+                RingProof::verify(&proof, rng, &ring.triptych, statement)
+            }
+            Scheme::ZkAttest => {
+                let proof = self.open::<ZkAttest>()?;
+                RingProof::verify(&proof, rng, &ring.zk_attest, statement)
+            }
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        [&[self.scheme.id()], self.payload.as_slice()].concat()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let (id, payload) = bytes.split_first().ok_or(Error::EmptyEnvelope)?;
+        Ok(Self {
+            scheme: Scheme::from_id(*id)?,
+            payload: payload.to_vec(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{TriptychWitness, ZkAttestWitness};
+    use agora_zkp_ecdsa::arithmetic::{Modular, Point, Scalar};
+    use agora_zkp_ecdsa::curve::Secp256k1;
+    use agora_zkp_ecdsa::error::{Error as ZkAttestError, StatementError};
+    use agora_zkp_ecdsa::parse::{to_hex, Signature};
+    use agora_zkp_ecdsa::proofs::ProofMode;
+    use agora_zkp_ecdsa::statement::StatementMode;
+    use agora_zkp_triptych::SigningKey;
+    use bigint::Encoding;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const GUILD_ID: &str = "almafa";
+
+    struct Member {
+        secret: Scalar<Secp256k1>,
+        pubkey: String,
+    }
+
+    impl Member {
+        fn random(rng: &mut StdRng) -> Self {
+            let secret = Scalar::<Secp256k1>::random(rng);
+            let pubkey = (Point::<Secp256k1>::GENERATOR * secret).to_affine();
+            let encoded = [
+                &[0x04][..],
+                &pubkey.x().inner().to_be_bytes(),
+                &pubkey.y().inner().to_be_bytes(),
+            ]
+            .concat();
+            Self {
+                secret,
+                pubkey: to_hex(&encoded),
+            }
+        }
+
+        fn triptych_witness(&self) -> TriptychWitness {
+            TriptychWitness {
+                signing_key: SigningKey::from_bytes(&self.secret.inner().to_be_bytes()).unwrap(),
+                base: 3,
+            }
+        }
+
+        fn zk_attest_witness(&self, rng: &mut StdRng, statement: &Statement) -> ZkAttestWitness {
+            let msg_hash = statement
+                .mode
+                .message_hash::<Secp256k1>(&statement.guild_id);
+            let nonce = Scalar::<Secp256k1>::random(rng);
+            let r = Scalar::new(
+                *(Point::<Secp256k1>::GENERATOR * nonce)
+                    .to_affine()
+                    .x()
+                    .inner(),
+            );
+            let s = nonce.inverse() * (msg_hash + r * self.secret);
+            ZkAttestWitness {
+                pubkey: (Point::<Secp256k1>::GENERATOR * self.secret).to_affine(),
+                signature: Signature {
+                    r,
+                    s,
+                    recovery_id: None,
+                },
+                mode: ProofMode::Exp,
+            }
+        }
+    }
+
+    #[test]
+    fn envelope_dispatch() {
+        let mut rng = StdRng::from_seed([50; 32]);
+        let members = (0..5).map(|_| Member::random(&mut rng)).collect::<Vec<_>>();
+        let keys = members
+            .iter()
+            .map(|member| member.pubkey.clone())
+            .collect::<Vec<_>>();
+        let ring = AnyRing::parse(&keys).unwrap();
+        let statement = Statement::new(GUILD_ID.to_string(), StatementMode::PersonalSign);
+        let other_statement = Statement::new("other".to_string(), StatementMode::PersonalSign);
+
+        let triptych = TriptychSignature::prove(
+            &mut rng,
+            members[3].triptych_witness(),
+            &ring.triptych,
+            &statement,
+        )
+        .unwrap();
+        let witness = members[1].zk_attest_witness(&mut rng, &statement);
+        let zk_attest = ZkAttest::prove(&mut rng, witness, &ring.zk_attest, &statement).unwrap();
+
+        let envelopes = [
+            ProofEnvelope::seal(&triptych),
+            ProofEnvelope::seal(&zk_attest),
+        ];
+        assert_eq!(envelopes[0].scheme(), Scheme::Triptych);
+        assert_eq!(envelopes[1].scheme(), Scheme::ZkAttest);
+        for envelope in &envelopes {
+            let decoded = ProofEnvelope::from_bytes(&envelope.to_bytes()).unwrap();
+            assert_eq!(&decoded, envelope);
+            assert!(decoded.verify(&mut rng, &ring, &statement).is_ok());
+            let error = decoded
+                .verify(&mut rng, &ring, &other_statement)
+                .unwrap_err();
+            assert_eq!(error.scheme(), Some(envelope.scheme()));
+        }
+        assert_eq!(
+            envelopes[1].verify(&mut rng, &ring, &other_statement),
+            Err(ZkAttestError::from(StatementError::GuildIdMismatch).into())
+        );
+
+        assert_eq!(
+            envelopes[0].open::<ZkAttest>().err(),
+            Some(Error::SchemeMismatch {
+                expected: Scheme::ZkAttest,
+                found: Scheme::Triptych,
+            })
+        );
+        assert_eq!(ProofEnvelope::from_bytes(&[]), Err(Error::EmptyEnvelope));
+        let mut unknown = envelopes[0].to_bytes();
+        unknown[0] = 0;
+        assert_eq!(
+            ProofEnvelope::from_bytes(&unknown),
+            Err(Error::UnknownScheme(0))
+        );
+
+        // neither scheme proves for a key outside of the ring
+        let outsider = Member::random(&mut rng);
+        assert_eq!(
+            TriptychSignature::prove(
+                &mut rng,
+                outsider.triptych_witness(),
+                &ring.triptych,
+                &statement
+            )
+            .err(),
+            Some(Error::SignerNotInRing)
+        );
+        let witness = outsider.zk_attest_witness(&mut rng, &statement);
+        assert_eq!(
+            ZkAttest::prove(&mut rng, witness, &ring.zk_attest, &statement).err(),
+            Some(Error::SignerNotInRing)
+        );
+    }
+}
@@ -0,0 +1,29 @@
+use crate::Scheme;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum Error {
+    #[error("unknown scheme {0}")]
+    UnknownScheme(u8),
+    #[error("expected a {expected} proof, found a {found} proof")]
+    SchemeMismatch { expected: Scheme, found: Scheme },
+    #[error("empty envelope")]
+    EmptyEnvelope,
+    #[error("signer not in ring")]
+    SignerNotInRing,
+    #[error("{0}")]
+    Triptych(String),
+    #[error(transparent)]
+    ZkAttest(#[from] agora_zkp_ecdsa::error::Error),
+}
+
+impl Error {
+    /// The scheme that raised the error, `None` for errors of the envelope.
+    pub fn scheme(&self) -> Option<Scheme> {
+        match self {
+            Self::Triptych(_) => Some(Scheme::Triptych),
+            Self::ZkAttest(_) => Some(Scheme::ZkAttest),
+            _ => None,
+        }
+    }
+}
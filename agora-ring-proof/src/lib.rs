@@ -0,0 +1,113 @@
+//! Common interface of the ring membership proofs of `agora-zkp-triptych` and
+//! `agora-zkp-ecdsa`.
+//!
+//! Both schemes prove that a member of a ring of secp256k1 public keys joins
+//! a guild, so they share the [`Statement`] and parse their rings from the
+//! same hex encoded keys. [`ProofEnvelope`] tags a serialized proof with its
+//! [`Scheme`], letting verifiers handle proofs of either scheme in a single
+//! code path.
+#![deny(clippy::dbg_macro)]
+#![deny(clippy::all)]
+
+mod envelope;
+mod error;
+mod triptych;
+mod zk_attest;
+
+pub use envelope::{AnyRing, ProofEnvelope};
+pub use error::Error;
+pub use triptych::{TriptychSignature, TriptychWitness};
+pub use zk_attest::{ZkAttest, ZkAttestWitness};
+
+use agora_zkp_ecdsa::arithmetic::Modular;
+use agora_zkp_ecdsa::curve::Secp256k1;
+use agora_zkp_ecdsa::statement::StatementMode;
+use bigint::Encoding;
+use rand_core::{CryptoRng, RngCore};
+
+use std::fmt;
+
+/// Identifier of a proof scheme, the first byte of a [`ProofEnvelope`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[repr(u8)]
+pub enum Scheme {
+    Triptych = 1,
+    ZkAttest = 2,
+}
+
+impl Scheme {
+    pub fn id(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_id(id: u8) -> Result<Self, Error> {
+        match id {
+            1 => Ok(Self::Triptych),
+            2 => Ok(Self::ZkAttest),
+            _ => Err(Error::UnknownScheme(id)),
+        }
+    }
+}
+
+impl fmt::Display for Scheme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Triptych => write!(f, "triptych"),
+            Self::ZkAttest => write!(f, "zk-attest"),
+        }
+    }
+}
+
+/// A member of the ring joins `guild_id` by signing its join message in
+/// `mode`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Statement {
+    pub guild_id: String,
+    pub mode: StatementMode,
+}
+
+impl Statement {
+    pub fn new(guild_id: String, mode: StatementMode) -> Self {
+        Self { guild_id, mode }
+    }
+
+    /// Hash of the signed join message.
+    pub fn message_hash(&self) -> [u8; 32] {
+        self.mode
+            .message_hash::<Secp256k1>(&self.guild_id)
+            .inner()
+            .to_be_bytes()
+    }
+}
+
+/// Proof that a member of a ring, whose identity is not revealed, proves a
+/// [`Statement`].
+pub trait RingProof: Sized {
+    const SCHEME: Scheme;
+
+    /// Public keys of the ring in the representation of the scheme.
+    type Ring;
+    /// Secret inputs of the prover.
+    type Witness;
+
+    /// Parses hex encoded uncompressed public keys.
+    fn parse_ring<S: AsRef<str>>(keys: &[S]) -> Result<Self::Ring, Error>;
+
+    fn prove<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        witness: Self::Witness,
+        ring: &Self::Ring,
+        statement: &Statement,
+    ) -> Result<Self, Error>;
+
+    fn verify<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        ring: &Self::Ring,
+        statement: &Statement,
+    ) -> Result<(), Error>;
+
+    fn to_bytes(&self) -> Vec<u8>;
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error>;
+}
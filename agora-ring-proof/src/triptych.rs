@@ -0,0 +1,81 @@
+use crate::{Error, RingProof, Scheme, Statement};
+
+use agora_zkp_triptych::ring::{check_base, digit_count};
+use agora_zkp_triptych::{Parameters, Ring, Signature, SigningKey};
+use rand_core::{CryptoRng, RngCore};
+
+pub type TriptychSignature = Signature;
+
+pub struct TriptychWitness {
+    pub signing_key: SigningKey,
+    /// Base of the decomposition of the signer's index.
+    pub base: usize,
+}
+
+// Derives as many rows of generators as the ring needs in `base`, the same
+// way the wasm bindings of the crate do.
+fn derive_parameters(base: usize, ring: &Ring) -> Result<Parameters, String> {
+    check_base(base)?;
+    Parameters::new(base, digit_count(ring.len(), base))
+}
+
+/// The guild id is the scope of the linking tag, so a member has a single tag
+/// per guild.
+impl RingProof for Signature {
+    const SCHEME: Scheme = Scheme::Triptych;
+
+    type Ring = Ring;
+    type Witness = TriptychWitness;
+
+    fn parse_ring<S: AsRef<str>>(keys: &[S]) -> Result<Ring, Error> {
+        Ring::parse(keys).map_err(Error::Triptych)
+    }
+
+    fn prove<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        witness: TriptychWitness,
+        ring: &Ring,
+        statement: &Statement,
+    ) -> Result<Self, Error> {
+        let index = ring
+            .position(&witness.signing_key.public_key())
+            .ok_or(Error::SignerNotInRing)?;
+        let parameters = derive_parameters(witness.base, ring).map_err(Error::Triptych)?;
+        Signature::new(
+            rng,
+            index,
+            ring,
+            &statement.message_hash(),
+            statement.guild_id.as_bytes(),
+            &witness.signing_key,
+            &parameters,
+        )
+        .map_err(Error::Triptych)
+    }
+
+    fn verify<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        ring: &Ring,
+        statement: &Statement,
+    ) -> Result<(), Error> {
+        let parameters = derive_parameters(self.base(), ring).map_err(Error::Triptych)?;
+        let message_hash = statement.message_hash();
+        Signature::verify_batch(
+            rng,
+            &[(self, &message_hash)],
+            ring,
+            statement.guild_id.as_bytes(),
+            &parameters,
+        )
+        .map_err(Error::Triptych)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        Signature::to_bytes(self)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Signature::from_bytes(bytes).map_err(Error::Triptych)
+    }
+}
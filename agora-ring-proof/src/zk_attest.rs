@@ -0,0 +1,87 @@
+use crate::{Error, RingProof, Scheme, Statement};
+
+use agora_zkp_ecdsa::arithmetic::AffinePoint;
+use agora_zkp_ecdsa::curve::{Secp256k1, Tom256k1};
+use agora_zkp_ecdsa::error::{Error as ZkAttestError, ParseError, StatementError};
+use agora_zkp_ecdsa::parse::{parse_ring, ParsedProofInput, ParsedRing, Signature};
+use agora_zkp_ecdsa::pedersen::PedersenCycle;
+use agora_zkp_ecdsa::proofs::{ProofMode, ZkAttestProof};
+use borsh::{BorshDeserialize, BorshSerialize};
+use rand_core::{CryptoRng, RngCore};
+
+pub type ZkAttest = ZkAttestProof<Secp256k1, Tom256k1>;
+
+pub struct ZkAttestWitness {
+    pub pubkey: AffinePoint<Secp256k1>,
+    /// Signature of the join message of the statement.
+    pub signature: Signature<Secp256k1>,
+    pub mode: ProofMode,
+}
+
+/// The ring holds the x coordinates of the keys, and the proof is generated
+/// with fresh Pedersen parameters.
+impl RingProof for ZkAttest {
+    const SCHEME: Scheme = Scheme::ZkAttest;
+
+    type Ring = ParsedRing<Tom256k1>;
+    type Witness = ZkAttestWitness;
+
+    fn parse_ring<S: AsRef<str>>(keys: &[S]) -> Result<Self::Ring, Error> {
+        let keys = keys.iter().map(|key| key.as_ref().to_owned()).collect();
+        Ok(parse_ring(keys).map_err(ZkAttestError::from)?)
+    }
+
+    fn prove<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        witness: ZkAttestWitness,
+        ring: &Self::Ring,
+        statement: &Statement,
+    ) -> Result<Self, Error> {
+        let key = witness.pubkey.x().to_cycle_scalar::<Tom256k1>();
+        let index = ring
+            .iter()
+            .position(|k| *k == key)
+            .ok_or(Error::SignerNotInRing)?;
+        let input = ParsedProofInput {
+            msg_hash: statement.mode.message_hash(&statement.guild_id),
+            pubkey: witness.pubkey,
+            signature: witness.signature,
+            index,
+            guild_id: statement.guild_id.clone(),
+            statement: statement.mode.clone(),
+        };
+        let pedersen = PedersenCycle::new(rng);
+        Ok(ZkAttestProof::construct_with_mode(
+            rng,
+            pedersen,
+            input,
+            ring,
+            witness.mode,
+        )?)
+    }
+
+    fn verify<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        ring: &Self::Ring,
+        statement: &Statement,
+    ) -> Result<(), Error> {
+        if self.guild_id != statement.guild_id {
+            return Err(ZkAttestError::from(StatementError::GuildIdMismatch).into());
+        }
+        if self.statement != statement.mode {
+            return Err(ZkAttestError::from(StatementError::MessageHashMismatch).into());
+        }
+        Ok(ZkAttestProof::verify(self, rng, ring)?)
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        // NOTE serializing into a vector never fails
+        self.try_to_vec().unwrap()
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        ZkAttestProof::try_from_slice(bytes)
+            .map_err(|e| ZkAttestError::from(ParseError::InvalidEncoding(e.to_string())).into())
+    }
+}
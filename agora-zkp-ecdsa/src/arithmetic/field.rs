@@ -22,6 +22,63 @@ impl<C: Curve> FieldElement<C> {
     pub fn to_cycle_scalar<CC: Cycle<C>>(self) -> Scalar<CC> {
         Scalar::<CC>::new(self.0)
     }
+
+    /// Returns `true` if the element is a square (zero included).
+    pub fn is_square(&self) -> bool {
+        // Euler's criterion
+        let exponent = Self(C::PRIME_MODULUS.wrapping_sub(&U256::ONE) >> 1, PhantomData);
+        self.pow(&exponent) != -Self::ONE
+    }
+
+    /// Computes a square root of the element using the Tonelli-Shanks
+    /// algorithm. Returns `None` if the element is not a square.
+    pub fn sqrt(&self) -> Option<Self> {
+        if !self.is_square() {
+            return None;
+        }
+        if self == &Self::ZERO {
+            return Some(Self::ZERO);
+        }
+        if C::PRIME_MODULUS.bit_vartime(1) == 1 {
+            // p = 3 mod 4
+            let exponent = C::PRIME_MODULUS.wrapping_add(&U256::ONE) >> 2;
+            return Some(self.pow(&Self(exponent, PhantomData)));
+        }
+        // p - 1 = q * 2^s with q odd
+        let mut q = C::PRIME_MODULUS.wrapping_sub(&U256::ONE);
+        let mut s = 0;
+        while q.bit_vartime(0) == 0 {
+            q >>= 1;
+            s += 1;
+        }
+        // find a non-square
+        let mut z = Self::new(U256::from_u8(2));
+        while z.is_square() {
+            z += Self::ONE;
+        }
+        let mut c = z.pow(&Self(q, PhantomData));
+        let mut t = self.pow(&Self(q, PhantomData));
+        let mut r = self.pow(&Self((q >> 1).wrapping_add(&U256::ONE), PhantomData));
+        let mut m = s;
+        while t != Self::ONE {
+            // find the least i such that t^(2^i) = 1
+            let mut i = 0;
+            let mut t_pow = t;
+            while t_pow != Self::ONE {
+                t_pow *= t_pow;
+                i += 1;
+            }
+            let mut b = c;
+            for _ in 0..m - i - 1 {
+                b *= b;
+            }
+            m = i;
+            c = b * b;
+            t *= c;
+            r *= b;
+        }
+        Some(r)
+    }
 }
 
 impl<C: Curve> Modular for FieldElement<C> {
@@ -72,7 +129,7 @@ impl<C> BorshDeserialize for FieldElement<C> {
     }
 }
 
-impl<'a, 'b, C: Curve> std::ops::Add<&'b FieldElement<C>> for &'a FieldElement<C> {
+impl<'b, C: Curve> std::ops::Add<&'b FieldElement<C>> for &FieldElement<C> {
     type Output = FieldElement<C>;
     fn add(self, rhs: &'b FieldElement<C>) -> Self::Output {
         Modular::add(self, rhs)
@@ -105,7 +162,7 @@ impl<C: Curve> std::ops::Sub for FieldElement<C> {
     }
 }
 
-impl<'a, 'b, C: Curve> std::ops::Sub<&'b FieldElement<C>> for &'a FieldElement<C> {
+impl<C: Curve> std::ops::Sub<&FieldElement<C>> for &FieldElement<C> {
     type Output = FieldElement<C>;
     fn sub(self, rhs: &FieldElement<C>) -> Self::Output {
         Modular::sub(self, rhs)
@@ -139,7 +196,7 @@ impl<C: Curve> std::ops::Mul<&FieldElement<C>> for FieldElement<C> {
     }
 }
 
-impl<'a, 'b, C: Curve> std::ops::Mul<&'b FieldElement<C>> for &'a FieldElement<C> {
+impl<C: Curve> std::ops::Mul<&FieldElement<C>> for &FieldElement<C> {
     type Output = FieldElement<C>;
     fn mul(self, rhs: &FieldElement<C>) -> Self::Output {
         Modular::mul(self, rhs)
@@ -272,6 +329,34 @@ mod test {
         assert_eq!(a_min_b, -b_min_a);
     }
 
+    #[test]
+    fn square_root() {
+        let two = FeSmall::new(U256::from_u32(2));
+        assert!(two.is_square());
+        let root = two.sqrt().unwrap();
+        assert_eq!(root * root, two);
+        let three = FeSmall::new(U256::from_u32(3));
+        assert!(!three.is_square());
+        assert!(three.sqrt().is_none());
+        assert_eq!(FeSmall::ZERO.sqrt(), Some(FeSmall::ZERO));
+
+        // p = 3 mod 4 for secp256k1 and p = 1 mod 4 for Tom256k1
+        let a = FeLarge::new(Secp256k1::GENERATOR_X);
+        let a2 = a * a;
+        let root = a2.sqrt().unwrap();
+        assert!(root == a || root == -a);
+        assert!(!(-a2).is_square());
+
+        let b = FieldElement::<Tom256k1>::new(Tom256k1::GENERATOR_Y);
+        let b2 = b * b;
+        let root = b2.sqrt().unwrap();
+        assert!(root == b || root == -b);
+        let y2 = FieldElement::<Tom256k1>::new(Tom256k1::GENERATOR_X)
+            .pow(&FieldElement::new(U256::from_u8(3)))
+            + FieldElement::new(Tom256k1::COEFF_B);
+        assert_eq!(y2, b2);
+    }
+
     #[test]
     fn serde_round() {
         let le_hex = "ce7c73f82cc708b9080499663f89fda1fa7bb76d78b72b4042554f33e418b94f";
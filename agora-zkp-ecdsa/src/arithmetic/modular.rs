@@ -78,14 +78,11 @@ pub fn mul_mod_u256(lhs: &U256, rhs: &U256, modulus: &U256) -> U256 {
 fn exp_mod_u256(base: &U256, exponent: &U256, modulus: &U256) -> U256 {
     let mut r = U256::ONE;
     let mut q = *base;
-    let mut k = *exponent;
-    while k > U256::ZERO {
-        if mod_u256(&k, &TWO) != U256::ZERO {
-            // k is odd
+    for i in 0..exponent.bits_vartime() {
+        if exponent.bit_vartime(i) == 1 {
             r = mul_mod_u256(&r, &q, modulus);
         }
         q = mul_mod_u256(&q, &q, modulus);
-        k >>= 1; // division by 2
     }
     r
 }
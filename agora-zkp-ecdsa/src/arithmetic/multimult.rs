@@ -1,6 +1,8 @@
-use super::{AffinePoint, Point, Scalar};
+use super::{AffinePoint, Modular, Point, Scalar};
 use crate::curve::Curve;
 
+use bigint::NonZero;
+
 use rand_core::{CryptoRng, RngCore};
 
 use std::cmp::{Eq, Ord, Ordering, PartialEq, PartialOrd};
@@ -87,13 +89,31 @@ impl<C: Curve> MultiMult<C> {
                     return a.point.scalar_mul(&a.scalar);
                 }
 
-                c = Pair {
-                    point: a.point,
-                    scalar: a.scalar - b.scalar,
-                };
-                let d = Pair {
-                    point: a.point + b.point,
-                    scalar: b.scalar,
+                let difference = a.scalar - b.scalar;
+                let d = if difference < b.scalar {
+                    c = Pair {
+                        point: a.point,
+                        scalar: difference,
+                    };
+                    Pair {
+                        point: a.point + b.point,
+                        scalar: b.scalar,
+                    }
+                } else {
+                    // a*P + b*Q = (a - q*b)*P + b*(q*P + Q) with q = a / b,
+                    // otherwise a large scalar next to a small one would be
+                    // reduced by repeated subtraction
+                    // NOTE unwrap is fine because b is nonzero
+                    let quotient =
+                        Scalar::new(*a.scalar.inner() / NonZero::new(*b.scalar.inner()).unwrap());
+                    c = Pair {
+                        point: a.point,
+                        scalar: a.scalar - quotient * b.scalar,
+                    };
+                    Pair {
+                        point: a.point.scalar_mul(&quotient) + b.point,
+                        scalar: b.scalar,
+                    }
                 };
 
                 *b = d;
@@ -172,7 +192,7 @@ impl<C: Curve> Eq for Pair<C> {}
 
 impl<C: Curve> PartialOrd for Pair<C> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.scalar.partial_cmp(&other.scalar)
+        Some(self.cmp(other))
     }
 }
 
@@ -268,6 +288,27 @@ mod test {
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn multimult_skewed_scalars() {
+        let mut rng = get_test_rng();
+        let mut multimult = MultiMult::<Tom256k1>::new();
+        let mut expected = TomPoint::IDENTITY;
+        let scalars = [
+            TomScalar::random(&mut rng),
+            TomScalar::ONE,
+            TomScalar::new(U256::from_u8(3)),
+            TomScalar::random(&mut rng),
+            TomScalar::ONE,
+        ];
+        let mut pt = TomPoint::GENERATOR;
+        for scalar in scalars {
+            pt = pt.double();
+            expected += &(pt * scalar);
+            multimult.insert(pt, scalar);
+        }
+        assert_eq!(multimult.evaluate(), expected);
+    }
+
     #[test]
     fn secp_relations() {
         let mut rng = get_test_rng();
@@ -3,8 +3,12 @@ mod impl_macro;
 use super::{FieldElement, Modular};
 use crate::curve::Curve;
 
+use crate::U256;
+
+use bigint::Encoding;
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::Serialize;
+use sha3::{Digest, Keccak256};
 
 #[derive(Debug, Clone, Copy, BorshSerialize, BorshDeserialize, Serialize)]
 pub struct Point<C: Curve> {
@@ -29,6 +33,28 @@ impl<C: Curve> Point<C> {
         Self { x, y, z }
     }
 
+    /// Deterministically maps `label` to a curve point whose discrete
+    /// logarithm is unknown, using try-and-increment.
+    pub fn hash_to_curve(label: &[u8]) -> Self {
+        let a = FieldElement::new(C::COEFF_A);
+        let b = FieldElement::new(C::COEFF_B);
+        let mut counter = 0u32;
+        loop {
+            let mut hasher = Keccak256::new();
+            hasher.update(label);
+            hasher.update(counter.to_be_bytes());
+            let digest: [u8; 32] = hasher.finalize().into();
+            let x = FieldElement::new(U256::from_be_bytes(digest));
+            let y2 = x * x * x + a * x + b;
+            if let Some(y) = y2.sqrt() {
+                // always pick the even root
+                let y = if y.inner().bit_vartime(0) == 0 { y } else { -y };
+                return Self::new(x, y, FieldElement::ONE);
+            }
+            counter += 1;
+        }
+    }
+
     pub fn to_affine(&self) -> AffinePoint<C> {
         let z_inv = self.z.inverse();
         AffinePoint {
@@ -57,14 +83,14 @@ impl<C: Curve> std::ops::Add<AffinePoint<C>> for Point<C> {
     }
 }
 
-impl<'a, 'b, C: Curve> std::ops::Add<&'b AffinePoint<C>> for &'a Point<C> {
+impl<'b, C: Curve> std::ops::Add<&'b AffinePoint<C>> for &Point<C> {
     type Output = Point<C>;
     fn add(self, rhs: &'b AffinePoint<C>) -> Self::Output {
         self.geometric_add(&rhs.into())
     }
 }
 
-impl<'a, 'b, C: Curve> std::ops::Sub<&'b AffinePoint<C>> for &'a Point<C> {
+impl<'b, C: Curve> std::ops::Sub<&'b AffinePoint<C>> for &Point<C> {
     type Output = Point<C>;
     fn sub(self, rhs: &'b AffinePoint<C>) -> Self::Output {
         self + &(-rhs)
@@ -232,6 +258,17 @@ mod test {
         assert_eq!(g5, (g4 + SecAffine::GENERATOR).into());
     }
 
+    #[test]
+    fn hash_to_curve() {
+        let p = SecPoint::hash_to_curve(b"test-label");
+        let q = TomPoint::hash_to_curve(b"test-label");
+        assert!(p.is_on_curve());
+        assert!(q.is_on_curve());
+        assert_eq!(p, SecPoint::hash_to_curve(b"test-label"));
+        assert_ne!(p, SecPoint::hash_to_curve(b"other-label"));
+        assert_eq!(q.y().inner().bit_vartime(0), 0);
+    }
+
     #[test]
     fn scalar_multiplication() {
         let d = TomScalar::new(U256::from_be_hex(
@@ -17,13 +17,13 @@ pub struct Scalar<C>(U256, PhantomData<C>);
 
 impl<C: Curve> PartialOrd for Scalar<C> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        // NOTE: Constant time comparation could be used for further security
-        Some(self.0.cmp(&other.0))
+        Some(self.cmp(other))
     }
 }
 
 impl<C: Curve> Ord for Scalar<C> {
     fn cmp(&self, other: &Self) -> Ordering {
+        // NOTE: Constant time comparation could be used for further security
         self.0.cmp(&other.0)
     }
 }
@@ -163,7 +163,7 @@ impl<C: Curve> std::ops::Mul for Scalar<C> {
     }
 }
 
-impl<'a, 'b, C: Curve> std::ops::Mul<&'b Scalar<C>> for &'a Scalar<C> {
+impl<'b, C: Curve> std::ops::Mul<&'b Scalar<C>> for &Scalar<C> {
     type Output = Scalar<C>;
     fn mul(self, rhs: &'b Scalar<C>) -> Self::Output {
         Modular::mul(self, rhs)
@@ -0,0 +1,494 @@
+use agora_zkp_ecdsa::arithmetic::{Point, Scalar};
+use agora_zkp_ecdsa::curve::{Curve, Secp256k1, Tom256k1};
+use agora_zkp_ecdsa::ecdsa;
+use agora_zkp_ecdsa::error::{ParameterError, StatementError};
+use agora_zkp_ecdsa::parse::*;
+use agora_zkp_ecdsa::pedersen::PedersenCycle;
+use agora_zkp_ecdsa::proofs::{
+    optimal_base, pad_ring_to_nm, Optimize, ProofMode, SignatureProof, ZkAttestProof,
+};
+use agora_zkp_ecdsa::ring::{canonical_ring, RingDigest};
+use agora_zkp_ecdsa::statement::StatementMode;
+use agora_zkp_ecdsa::vectors;
+use agora_zkp_ecdsa::verifier::RingDirectory;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use borsh::{BorshDeserialize, BorshSerialize};
+use rand_core::OsRng;
+use serde::{Deserialize, Serialize};
+use structopt::StructOpt;
+
+use std::error::Error;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+type Proof = ZkAttestProof<Secp256k1, Tom256k1>;
+
+#[derive(StructOpt)]
+#[structopt(name = "agora-zkp", about = "zero-knowledge proofs of ring membership")]
+enum Command {
+    /// Generates and inspects rings of public keys.
+    Ring(RingCommand),
+    /// Generates a proof from a signature.
+    Prove {
+        #[structopt(long, help = "JSON array of public keys")]
+        ring: PathBuf,
+        #[structopt(
+            long,
+            help = "JSON object with msgHash, signature, guildId and optionally pubkey, index, the EIP-712 domain and normalizeS"
+        )]
+        signature: PathBuf,
+        #[structopt(long, default_value = "json", help = "binary, hex, base64 or json")]
+        encoding: Encoding,
+        #[structopt(long, default_value = "exp", help = "signature proof, exp or circuit")]
+        mode: ProofMode,
+        #[structopt(long, help = "output file, stdout by default")]
+        out: Option<PathBuf>,
+    },
+    /// Verifies a proof against a ring.
+    Verify {
+        #[structopt(long, help = "encoded proof, `-` reads stdin")]
+        proof: PathBuf,
+        #[structopt(long, help = "JSON array of public keys")]
+        ring: Option<PathBuf>,
+        #[structopt(
+            long,
+            help = "directory of rings stored as <ring digest>.json and the ring of each guild in guilds.json"
+        )]
+        rings: Option<PathBuf>,
+        #[structopt(long, help = "expected guild id")]
+        guild_id: Option<String>,
+        #[structopt(long, help = "binary, hex, base64 or json, detected by default")]
+        encoding: Option<Encoding>,
+    },
+    /// Decodes proofs.
+    Proof(ProofCommand),
+    /// Writes the known-answer test vectors of the deterministic prover.
+    Vectors {
+        #[structopt(long, help = "output file, stdout by default")]
+        out: Option<PathBuf>,
+    },
+}
+
+#[derive(StructOpt)]
+enum RingCommand {
+    /// Generates a ring of random public keys.
+    Generate {
+        #[structopt(long, help = "number of random public keys")]
+        size: usize,
+        #[structopt(long, help = "public keys to include in the ring")]
+        include: Vec<String>,
+        #[structopt(
+            long,
+            help = "output file, or directory to write <ring digest>.json into, stdout by default"
+        )]
+        out: Option<PathBuf>,
+    },
+    /// Prints the digest and proof parameters of a ring.
+    Inspect {
+        #[structopt(help = "JSON array of public keys, `-` reads stdin")]
+        ring: PathBuf,
+    },
+}
+
+#[derive(StructOpt)]
+enum ProofCommand {
+    /// Pretty-prints the public parts of a proof.
+    Inspect {
+        #[structopt(help = "encoded proof, `-` reads stdin")]
+        proof: PathBuf,
+        #[structopt(long, help = "binary, hex, base64 or json, detected by default")]
+        encoding: Option<Encoding>,
+    },
+}
+
+/// Encodings of the Borsh serialized proof.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Encoding {
+    Binary,
+    Hex,
+    Base64,
+    /// The request body accepted by `zkp-verifier`.
+    Json,
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "binary" => Ok(Self::Binary),
+            "hex" => Ok(Self::Hex),
+            "base64" => Ok(Self::Base64),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("unknown encoding {}", s)),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignatureInput {
+    msg_hash: String,
+    pubkey: Option<String>,
+    signature: String,
+    guild_id: String,
+    index: Option<usize>,
+    #[serde(default)]
+    domain: Option<TypedDataDomain>,
+    #[serde(default)]
+    normalize_s: bool,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ProofBytes {
+    Hex(String),
+    Bytes(Vec<u8>),
+}
+
+#[derive(Deserialize)]
+struct JsonProofInput {
+    // NOTE the wasm prover returns the proof as `proofBinary`
+    #[serde(alias = "proofBinary")]
+    proof: ProofBytes,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JsonProof {
+    proof: String,
+    guild_id: String,
+    ring_digest: String,
+}
+
+impl Encoding {
+    fn encode(self, proof: &Proof) -> Result<Vec<u8>, Box<dyn Error>> {
+        let bytes = proof.try_to_vec()?;
+        Ok(match self {
+            Self::Binary => bytes,
+            Self::Hex => format!("0x{}", to_hex(&bytes)).into_bytes(),
+            Self::Base64 => BASE64.encode(bytes).into_bytes(),
+            Self::Json => serde_json::to_vec_pretty(&JsonProof {
+                proof: format!("0x{}", to_hex(&bytes)),
+                guild_id: proof.guild_id.clone(),
+                ring_digest: proof.ring_digest.to_hex(),
+            })?,
+        })
+    }
+
+    fn decode(self, data: &[u8]) -> Result<Proof, Box<dyn Error>> {
+        let bytes = match self {
+            Self::Binary => data.to_vec(),
+            Self::Hex => parse_hex(std::str::from_utf8(data)?.trim())?,
+            Self::Base64 => BASE64.decode(std::str::from_utf8(data)?.trim())?,
+            Self::Json => match serde_json::from_slice::<JsonProofInput>(data)?.proof {
+                ProofBytes::Hex(hex) => parse_hex(&hex)?,
+                ProofBytes::Bytes(bytes) => bytes,
+            },
+        };
+        Ok(Proof::try_from_slice(&bytes)?)
+    }
+
+    /// Guesses the encoding of `data`, hex is preferred over base64 as every
+    /// hex string is valid base64.
+    fn detect(data: &[u8]) -> Self {
+        let text = match std::str::from_utf8(data) {
+            Ok(text) => text.trim(),
+            Err(_) => return Self::Binary,
+        };
+        let is_base64 = |c: char| c.is_ascii_alphanumeric() || c == '+' || c == '/' || c == '=';
+        if text.starts_with('{') {
+            Self::Json
+        } else if text
+            .trim_start_matches("0x")
+            .chars()
+            .all(|c| c.is_ascii_hexdigit())
+        {
+            Self::Hex
+        } else if text.chars().all(is_base64) {
+            Self::Base64
+        } else {
+            Self::Binary
+        }
+    }
+}
+
+fn read_input(path: &Path) -> std::io::Result<Vec<u8>> {
+    if path == Path::new("-") {
+        let mut data = Vec::new();
+        std::io::stdin().read_to_end(&mut data)?;
+        Ok(data)
+    } else {
+        std::fs::read(path)
+    }
+}
+
+fn write_output(path: Option<&Path>, data: &[u8]) -> std::io::Result<()> {
+    match path {
+        Some(path) => std::fs::write(path, data),
+        None => {
+            let mut stdout = std::io::stdout();
+            stdout.write_all(data)?;
+            stdout.write_all(b"\n")
+        }
+    }
+}
+
+fn read_ring(path: &Path) -> Result<(Ring, ParsedRing<Tom256k1>), Box<dyn Error>> {
+    let ring: Ring = serde_json::from_slice(&read_input(path)?)?;
+    let parsed = parse_ring(ring.clone())?;
+    Ok((ring, parsed))
+}
+
+fn read_proof(path: &Path, encoding: Option<Encoding>) -> Result<Proof, Box<dyn Error>> {
+    let data = read_input(path)?;
+    encoding
+        .unwrap_or_else(|| Encoding::detect(&data))
+        .decode(&data)
+}
+
+fn scalar_hex<C: Curve>(scalar: &Scalar<C>) -> String {
+    format!("0x{}", scalar).to_lowercase()
+}
+
+fn point_hex<C: Curve>(point: &Point<C>) -> String {
+    let affine = point.to_affine();
+    format!("04{}{}", affine.x(), affine.y()).to_lowercase()
+}
+
+fn random_pubkey(rng: &mut OsRng) -> String {
+    point_hex(&(Point::<Secp256k1>::GENERATOR * Scalar::random(rng)))
+}
+
+fn generate_ring(
+    size: usize,
+    include: Vec<String>,
+    out: Option<PathBuf>,
+) -> Result<(), Box<dyn Error>> {
+    let mut rng = OsRng;
+    for pubkey in &include {
+        parse_pubkey::<Secp256k1>(pubkey)?;
+    }
+    let mut ring = include;
+    ring.extend((0..size).map(|_| random_pubkey(&mut rng)));
+    let digest = RingDigest::new(&parse_ring::<Tom256k1>(ring.clone())?);
+
+    let out = match out {
+        Some(dir) if dir.is_dir() => Some(dir.join(format!("{}.json", digest.to_hex()))),
+        out => out,
+    };
+    write_output(out.as_deref(), &serde_json::to_vec(&ring)?)?;
+    eprintln!("ring digest: {}", digest.to_hex());
+    Ok(())
+}
+
+fn inspect_ring(path: &Path) -> Result<(), Box<dyn Error>> {
+    let (ring, parsed) = read_ring(path)?;
+    let keys = canonical_ring(&parsed);
+    let base = optimal_base(keys.len(), Optimize::ProverTime);
+    let mut padded = keys.clone();
+    pad_ring_to_nm(&mut padded, base)?;
+    let summary = serde_json::json!({
+        "ringDigest": RingDigest::of_canonical(&keys).to_hex(),
+        "keys": ring.len(),
+        "distinctKeys": keys.len(),
+        "base": base,
+        "paddedSize": padded.len(),
+    });
+    println!("{}", serde_json::to_string_pretty(&summary)?);
+    Ok(())
+}
+
+fn prove(
+    ring: &Path,
+    signature: &Path,
+    encoding: Encoding,
+    mode: ProofMode,
+    out: Option<PathBuf>,
+) -> Result<(), Box<dyn Error>> {
+    let mut rng = OsRng;
+    let (_, ring) = read_ring(ring)?;
+    let input: SignatureInput = serde_json::from_slice(&read_input(signature)?)?;
+
+    let msg_hash = parse_msg_hash(&input.msg_hash)?;
+    let mut signature = parse_signature(&input.signature)?;
+    if input.normalize_s {
+        signature.normalize_s();
+    }
+    let pubkey = match &input.pubkey {
+        Some(pubkey) => parse_pubkey::<Secp256k1>(pubkey)?,
+        None => ecdsa::recover(msg_hash, &signature)?,
+    };
+    let index = match input.index {
+        Some(index) => index,
+        None => {
+            let key = pubkey.x().to_cycle_scalar::<Tom256k1>();
+            ring.iter()
+                .position(|k| *k == key)
+                .ok_or(ParameterError::InvalidIndex)?
+        }
+    };
+    let input = ParsedProofInput {
+        msg_hash,
+        pubkey,
+        signature,
+        index,
+        guild_id: input.guild_id,
+        statement: parse_statement(input.domain)?,
+    };
+
+    let pedersen = PedersenCycle::new(&mut rng);
+    let proof = Proof::construct_with_mode(&mut rng, pedersen, input, &ring, mode)?;
+    write_output(out.as_deref(), &encoding.encode(&proof)?)?;
+    Ok(())
+}
+
+fn verify(
+    proof: &Path,
+    ring: Option<PathBuf>,
+    rings: Option<PathBuf>,
+    guild_id: Option<String>,
+    encoding: Option<Encoding>,
+) -> Result<(), Box<dyn Error>> {
+    let proof = read_proof(proof, encoding)?;
+    let guild_id = guild_id.unwrap_or_else(|| proof.guild_id.clone());
+    let result = match (ring, rings) {
+        (Some(ring), None) => {
+            let (_, ring) = read_ring(&ring)?;
+            if proof.guild_id != guild_id {
+                Err(StatementError::GuildIdMismatch.into())
+            } else {
+                proof.verify(&mut OsRng, &ring)
+            }
+        }
+        (None, Some(rings)) => RingDirectory::new(rings).verify(&proof, &guild_id),
+        _ => return Err("exactly one of --ring and --rings is required".into()),
+    };
+    match result {
+        Ok(()) => {
+            println!("Proof OK");
+            Ok(())
+        }
+        Err(error) => {
+            eprintln!("Proof invalid: {} ({})", error, error.code());
+            std::process::exit(1);
+        }
+    }
+}
+
+fn inspect_proof(proof: &Path, encoding: Option<Encoding>) -> Result<(), Box<dyn Error>> {
+    let proof = read_proof(proof, encoding)?;
+    let base = proof.membership_proof.base();
+    let digits = proof.membership_proof.digits();
+    let statement = match &proof.statement {
+        StatementMode::PersonalSign => serde_json::json!("personalSign"),
+        StatementMode::TypedData(domain) => serde_json::json!({
+            "name": domain.name,
+            "version": domain.version,
+            "chainId": domain.chain_id,
+            "verifyingContract": format!("0x{}", to_hex(&domain.verifying_contract)),
+        }),
+    };
+    let signature_proof = match &proof.signature_proof {
+        SignatureProof::Exp(exp) => serde_json::json!({ "mode": "exp", "rounds": exp.rounds() }),
+        SignatureProof::Circuit(_) => serde_json::json!({ "mode": "circuit" }),
+    };
+    let summary = serde_json::json!({
+        "guildId": proof.guild_id,
+        "statement": statement,
+        "msgHash": scalar_hex(&proof.msg_hash),
+        "rPoint": point_hex(&proof.r_point),
+        "ringDigest": proof.ring_digest.to_hex(),
+        "membership": {
+            "base": base,
+            "digits": digits,
+            "maxRingSize": base.checked_pow(digits as u32),
+        },
+        "signatureProof": signature_proof,
+        "size": proof.try_to_vec()?.len(),
+    });
+    println!("{}", serde_json::to_string_pretty(&summary)?);
+    Ok(())
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    match Command::from_args() {
+        Command::Ring(RingCommand::Generate { size, include, out }) => {
+            generate_ring(size, include, out)
+        }
+        Command::Ring(RingCommand::Inspect { ring }) => inspect_ring(&ring),
+        Command::Prove {
+            ring,
+            signature,
+            encoding,
+            mode,
+            out,
+        } => prove(&ring, &signature, encoding, mode, out),
+        Command::Verify {
+            proof,
+            ring,
+            rings,
+            guild_id,
+            encoding,
+        } => verify(&proof, ring, rings, guild_id, encoding),
+        Command::Proof(ProofCommand::Inspect { proof, encoding }) => {
+            inspect_proof(&proof, encoding)
+        }
+        Command::Vectors { out } => {
+            let vectors = serde_json::to_vec_pretty(&vectors::standard()?)?;
+            write_output(out.as_deref(), &vectors)?;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn proof_encodings() {
+        let mut rng = StdRng::from_seed([34; 32]);
+        let ring = vec![
+            "c2ef144b59081382387f0ebf5d96b3a194f8c28961fa443000ea793ce534dac2".to_string(),
+            "54e32170dd5a0b7b641aa77daa1f3f31b8df17e51aaba6cfcb310848d2635118".to_string(),
+        ];
+        let input: ParsedProofInput<Secp256k1> = ProofInput {
+            msg_hash: "0x9788117298a1450f6002d25f0c21d83bc6001681a2e5e31c748c0f55504b11e9".to_string(),
+            pubkey: Some("0454e32170dd5a0b7b641aa77daa1f3f31b8df17e51aaba6cfcb310848d26351180b6ac0399d21460443d10072700b64b454d70bfba5e93601536c740bbd099682".to_string()),
+            signature: "0xd2943d5fa0ba2733bcbbd58853c6c1be65388d9198dcb5228e117f49409612a46394afb97a7610d16e7bea0062e71afc2a3039324c80df8ef38d3668164fad2c1c".to_string(),
+            index: 1,
+            guild_id: "almafa".to_string(),
+            domain: None,
+            normalize_s: false,
+        }
+        .try_into()
+        .unwrap();
+        let ring = parse_ring(ring).unwrap();
+        let pedersen = PedersenCycle::new(&mut rng);
+        let proof = Proof::construct(&mut rng, pedersen, input, &ring).unwrap();
+        let bytes = proof.try_to_vec().unwrap();
+
+        for encoding in [
+            Encoding::Binary,
+            Encoding::Hex,
+            Encoding::Base64,
+            Encoding::Json,
+        ] {
+            let encoded = encoding.encode(&proof).unwrap();
+            assert_eq!(Encoding::detect(&encoded), encoding);
+            let decoded = encoding.decode(&encoded).unwrap();
+            assert_eq!(decoded.try_to_vec().unwrap(), bytes);
+        }
+
+        let wasm_output = serde_json::json!({ "guildId": "almafa", "proofBinary": bytes });
+        let decoded = Encoding::Json
+            .decode(wasm_output.to_string().as_bytes())
+            .unwrap();
+        assert!(decoded.verify(&mut rng, &ring).is_ok());
+        assert!(Encoding::Hex.decode(b"0x00").is_err());
+    }
+}
@@ -0,0 +1,38 @@
+use agora_zkp_ecdsa::verifier::RingDirectory;
+use structopt::StructOpt;
+use tiny_http::Server;
+
+use std::error::Error;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[derive(StructOpt)]
+struct Opt {
+    #[structopt(
+        long,
+        help = "directory of rings stored as <ring digest>.json and the ring of each guild in guilds.json"
+    )]
+    rings: PathBuf,
+    #[structopt(long, default_value = "127.0.0.1:8080", help = "address to listen on")]
+    addr: String,
+    #[structopt(long, default_value = "4", help = "number of worker threads")]
+    threads: usize,
+}
+
+fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+    let opt = Opt::from_args();
+    let rings = Arc::new(RingDirectory::new(opt.rings));
+    let server = Arc::new(Server::http(&opt.addr)?);
+    println!("Verifying proofs on http://{}/verify", server.server_addr());
+
+    let workers = (0..opt.threads.max(1))
+        .map(|_| {
+            let (rings, server) = (Arc::clone(&rings), Arc::clone(&server));
+            std::thread::spawn(move || rings.serve(&server))
+        })
+        .collect::<Vec<_>>();
+    for worker in workers {
+        worker.join().expect("worker panicked");
+    }
+    Ok(())
+}
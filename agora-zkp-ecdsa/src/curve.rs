@@ -9,7 +9,7 @@ pub trait Cycle<C: Curve>: Curve {
     }
 }
 
-pub trait Curve: Clone + Copy + std::fmt::Debug + PartialEq + Eq + Send + Sync {
+pub trait Curve: Clone + Copy + std::fmt::Debug + PartialEq + Eq + Send + Sync + 'static {
     const PRIME_MODULUS: U256;
     const ORDER: U256;
     const GENERATOR_X: U256;
@@ -52,6 +52,7 @@ impl Curve for Tom256k1 {
 }
 
 impl Cycle<Secp256k1> for Tom256k1 {}
+impl Cycle<Tom256k1> for Secp256k1 {}
 
 #[cfg(test)]
 mod test {
@@ -76,6 +77,7 @@ mod test {
     #[test]
     fn cycle_check() {
         assert!(<Tom256k1 as Cycle<Secp256k1>>::is_cycle());
+        assert!(<Secp256k1 as Cycle<Tom256k1>>::is_cycle());
         assert!(!<Tom256k1 as Cycle<TestCurve>>::is_cycle());
     }
 }
@@ -0,0 +1,124 @@
+//! Plain ECDSA over the signing curve, used to check the inputs of the prover
+//! before the expensive zero-knowledge proofs are generated.
+use crate::arithmetic::{AffinePoint, FieldElement, Modular, Point, Scalar};
+use crate::curve::Curve;
+use crate::error::SignatureError;
+use crate::parse::Signature;
+
+/// Checks that `signature` is a valid signature of `msg_hash` by `pubkey`.
+pub fn verify<C: Curve>(
+    msg_hash: Scalar<C>,
+    signature: &Signature<C>,
+    pubkey: &AffinePoint<C>,
+) -> Result<(), SignatureError> {
+    // NOTE `parse_pubkey` rejects keys that are not on the curve, keys built
+    // otherwise cannot have valid signatures
+    if signature.r == Scalar::ZERO
+        || signature.s == Scalar::ZERO
+        || pubkey.is_identity()
+        || !pubkey.is_on_curve()
+    {
+        return Err(SignatureError::InvalidSignature);
+    }
+    let s_inv = signature.s.inverse();
+    let r_point = Point::<C>::GENERATOR.double_mul(
+        &(s_inv * msg_hash),
+        &pubkey.into(),
+        &(s_inv * signature.r),
+    );
+    if r_point.is_identity() || Scalar::<C>::new(*r_point.to_affine().x().inner()) != signature.r {
+        return Err(SignatureError::InvalidSignature);
+    }
+    Ok(())
+}
+
+/// Recovers the public key that signed `msg_hash` from the signature and its
+/// recovery id (`ecrecover`).
+pub fn recover<C: Curve>(
+    msg_hash: Scalar<C>,
+    signature: &Signature<C>,
+) -> Result<AffinePoint<C>, SignatureError> {
+    let recovery_id = signature
+        .recovery_id
+        .ok_or(SignatureError::MissingRecoveryId)?;
+    if signature.r == Scalar::ZERO || signature.s == Scalar::ZERO {
+        return Err(SignatureError::InvalidSignature);
+    }
+    // NOTE r < n < p, so it is a valid field element. Recovery ids 2 and 3,
+    // where the x coordinate of R is r + n, are practically never produced
+    // and are not supported.
+    let x = FieldElement::<C>::new(*signature.r.inner());
+    let y2 = x * x * x + FieldElement::new(C::COEFF_A) * x + FieldElement::new(C::COEFF_B);
+    let y = y2.sqrt().ok_or(SignatureError::RecoveryFailed)?;
+    // the parity of the y coordinate is the last bit of the recovery id
+    let y = if (y.inner().bit_vartime(0) == 1) == (recovery_id & 1 == 1) {
+        y
+    } else {
+        -y
+    };
+    let r_point = Point::from(AffinePoint::new(x, y));
+
+    // Q = r^-1 (s R - z G)
+    let r_inv = signature.r.inverse();
+    let pubkey =
+        Point::<C>::GENERATOR.double_mul(&(-(r_inv * msg_hash)), &r_point, &(r_inv * signature.s));
+    if pubkey.is_identity() {
+        return Err(SignatureError::RecoveryFailed);
+    }
+    Ok(pubkey.to_affine())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::curve::Secp256k1;
+    use crate::parse::{parse_msg_hash, parse_pubkey, parse_signature};
+
+    #[test]
+    fn recover_and_verify() {
+        let msg_hash = parse_msg_hash::<Secp256k1>(
+            "0x9788117298a1450f6002d25f0c21d83bc6001681a2e5e31c748c0f55504b11e9",
+        )
+        .unwrap();
+        let signature = parse_signature("0xd2943d5fa0ba2733bcbbd58853c6c1be65388d9198dcb5228e117f49409612a46394afb97a7610d16e7bea0062e71afc2a3039324c80df8ef38d3668164fad2c1c").unwrap();
+        let pubkey = parse_pubkey("0x0454e32170dd5a0b7b641aa77daa1f3f31b8df17e51aaba6cfcb310848d26351180b6ac0399d21460443d10072700b64b454d70bfba5e93601536c740bbd099682").unwrap();
+
+        assert_eq!(recover(msg_hash, &signature).unwrap(), pubkey);
+        assert!(verify(msg_hash, &signature, &pubkey).is_ok());
+
+        // the other recovery id yields a different key
+        let flipped = Signature {
+            recovery_id: Some(0),
+            ..signature
+        };
+        assert_ne!(recover(msg_hash, &flipped).unwrap(), pubkey);
+
+        // the high-s form of the signature is valid for the same key
+        let mut high = Signature {
+            s: -signature.s,
+            recovery_id: Some(0),
+            ..signature
+        };
+        assert!(high.is_high_s());
+        assert!(verify(msg_hash, &high, &pubkey).is_ok());
+        assert_eq!(recover(msg_hash, &high).unwrap(), pubkey);
+        high.normalize_s();
+        assert_eq!(recover(msg_hash, &high).unwrap(), pubkey);
+
+        let other_hash = msg_hash + Scalar::ONE;
+        assert_eq!(
+            verify(other_hash, &signature, &pubkey),
+            Err(SignatureError::InvalidSignature)
+        );
+        assert_ne!(recover(other_hash, &signature).unwrap(), pubkey);
+
+        let missing = Signature {
+            recovery_id: None,
+            ..signature
+        };
+        assert_eq!(
+            recover(msg_hash, &missing),
+            Err(SignatureError::MissingRecoveryId)
+        );
+    }
+}
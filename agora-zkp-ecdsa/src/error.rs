@@ -0,0 +1,249 @@
+use agora_interpolate::InterpolationError;
+use thiserror::Error;
+
+/// Errors of parsing proof inputs and serialized data.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ParseError {
+    #[error("invalid hash length")]
+    InvalidHashLength,
+    #[error("invalid pubkey")]
+    InvalidPubkey,
+    #[error("invalid bytes")]
+    InvalidBytes,
+    #[error("invalid ring digest")]
+    InvalidRingDigest,
+    #[error("invalid address")]
+    InvalidAddress,
+    #[error("invalid recovery id")]
+    InvalidRecoveryId,
+    #[error("invalid proof mode")]
+    InvalidProofMode,
+    #[error("invalid encoding: {0}")]
+    InvalidEncoding(String),
+}
+
+/// Errors caused by parameters the proofs cannot be generated or verified
+/// with.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ParameterError {
+    #[error("invalid base")]
+    InvalidBase,
+    #[error("invalid branching factor")]
+    InvalidBranchingFactor,
+    #[error("empty ring")]
+    EmptyRing,
+    #[error("invalid index")]
+    InvalidIndex,
+    #[error("invalid index count")]
+    InvalidIndexCount,
+    #[error("invalid ring reference")]
+    InvalidRingReference,
+    #[error("empty formula")]
+    EmptyFormula,
+    #[error("not enough generators")]
+    NotEnoughGenerators,
+}
+
+/// Errors of the membership proofs.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum MembershipError {
+    #[error("invalid commitment lengths")]
+    InvalidCommitmentLengths,
+    #[error("invalid proof lengths")]
+    InvalidProofLengths,
+    #[error("failed to verify membership")]
+    VerificationFailed,
+    #[error("formula not satisfied")]
+    FormulaNotSatisfied,
+    #[error("proof does not match formula")]
+    FormulaMismatch,
+    #[error("ring digest mismatch")]
+    RingDigestMismatch,
+    #[error("unknown ring")]
+    UnknownRing,
+    #[error("invalid root")]
+    InvalidRoot,
+    #[error("public key does not match the ring at the index")]
+    KeyMismatch,
+    #[error("interpolation error: {0}")]
+    Interpolation(#[from] InterpolationError),
+}
+
+/// Errors of the arithmetic circuit proofs.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum CircuitError {
+    #[error("vector input gates must be allocated first")]
+    InvalidInputGate,
+    #[error("missing assignment")]
+    MissingAssignment,
+    #[error("child is not permissible")]
+    NotPermissible,
+    #[error("degenerate rerandomization")]
+    DegenerateRerandomization,
+    #[error("invalid proof lengths")]
+    InvalidProofLengths,
+    #[error("invalid circuit proof")]
+    VerificationFailed,
+    #[error("commitment is not blinded by the circuit generator")]
+    InvalidGenerator,
+}
+
+/// Errors of the proofs of exponentiation.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum ExpError {
+    #[error("security level not achieved")]
+    SecurityLevelNotAchieved,
+    #[error("intermediate value is identity")]
+    IdentityIntermediate,
+    #[error("challenge hash mismatch")]
+    ChallengeMismatch,
+    #[error("proof is invalid")]
+    VerificationFailed,
+    #[error("proof generation cancelled")]
+    Cancelled,
+}
+
+/// Errors of the public part of the proven statement.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum StatementError {
+    #[error("R is at infinity")]
+    RAtInfinity,
+    #[error("Signed message hash mismatch")]
+    MessageHashMismatch,
+    #[error("guild id mismatch")]
+    GuildIdMismatch,
+    #[error("unknown guild")]
+    UnknownGuild,
+}
+
+/// Errors of the plain ECDSA signature checked before proving.
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum SignatureError {
+    #[error("signature is not valid for the public key")]
+    InvalidSignature,
+    #[error("missing recovery id")]
+    MissingRecoveryId,
+    #[error("failed to recover pubkey")]
+    RecoveryFailed,
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum Error {
+    #[error(transparent)]
+    Parse(#[from] ParseError),
+    #[error(transparent)]
+    Parameter(#[from] ParameterError),
+    #[error(transparent)]
+    Membership(#[from] MembershipError),
+    #[error(transparent)]
+    Circuit(#[from] CircuitError),
+    #[error(transparent)]
+    Exp(#[from] ExpError),
+    #[error(transparent)]
+    Statement(#[from] StatementError),
+    #[error(transparent)]
+    Signature(#[from] SignatureError),
+}
+
+impl Error {
+    pub fn category(&self) -> &'static str {
+        match self {
+            Self::Parse(_) => "parse",
+            Self::Parameter(_) => "parameter",
+            Self::Membership(_) => "membership",
+            Self::Circuit(_) => "circuit",
+            Self::Exp(_) => "exp",
+            Self::Statement(_) => "statement",
+            Self::Signature(_) => "signature",
+        }
+    }
+
+    /// Stable identifier of the error that clients can match on instead of
+    /// the message.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::Parse(error) => match error {
+                ParseError::InvalidHashLength => "PARSE_INVALID_HASH_LENGTH",
+                ParseError::InvalidPubkey => "PARSE_INVALID_PUBKEY",
+                ParseError::InvalidBytes => "PARSE_INVALID_BYTES",
+                ParseError::InvalidRingDigest => "PARSE_INVALID_RING_DIGEST",
+                ParseError::InvalidAddress => "PARSE_INVALID_ADDRESS",
+                ParseError::InvalidRecoveryId => "PARSE_INVALID_RECOVERY_ID",
+                ParseError::InvalidProofMode => "PARSE_INVALID_PROOF_MODE",
+                ParseError::InvalidEncoding(_) => "PARSE_INVALID_ENCODING",
+            },
+            Self::Parameter(error) => match error {
+                ParameterError::InvalidBase => "PARAMETER_INVALID_BASE",
+                ParameterError::InvalidBranchingFactor => "PARAMETER_INVALID_BRANCHING_FACTOR",
+                ParameterError::EmptyRing => "PARAMETER_EMPTY_RING",
+                ParameterError::InvalidIndex => "PARAMETER_INVALID_INDEX",
+                ParameterError::InvalidIndexCount => "PARAMETER_INVALID_INDEX_COUNT",
+                ParameterError::InvalidRingReference => "PARAMETER_INVALID_RING_REFERENCE",
+                ParameterError::EmptyFormula => "PARAMETER_EMPTY_FORMULA",
+                ParameterError::NotEnoughGenerators => "PARAMETER_NOT_ENOUGH_GENERATORS",
+            },
+            Self::Membership(error) => match error {
+                MembershipError::InvalidCommitmentLengths => {
+                    "MEMBERSHIP_INVALID_COMMITMENT_LENGTHS"
+                }
+                MembershipError::InvalidProofLengths => "MEMBERSHIP_INVALID_PROOF_LENGTHS",
+                MembershipError::VerificationFailed => "MEMBERSHIP_VERIFICATION_FAILED",
+                MembershipError::FormulaNotSatisfied => "MEMBERSHIP_FORMULA_NOT_SATISFIED",
+                MembershipError::FormulaMismatch => "MEMBERSHIP_FORMULA_MISMATCH",
+                MembershipError::RingDigestMismatch => "MEMBERSHIP_RING_DIGEST_MISMATCH",
+                MembershipError::UnknownRing => "MEMBERSHIP_UNKNOWN_RING",
+                MembershipError::InvalidRoot => "MEMBERSHIP_INVALID_ROOT",
+                MembershipError::KeyMismatch => "MEMBERSHIP_KEY_MISMATCH",
+                MembershipError::Interpolation(_) => "MEMBERSHIP_INTERPOLATION",
+            },
+            Self::Circuit(error) => match error {
+                CircuitError::InvalidInputGate => "CIRCUIT_INVALID_INPUT_GATE",
+                CircuitError::MissingAssignment => "CIRCUIT_MISSING_ASSIGNMENT",
+                CircuitError::NotPermissible => "CIRCUIT_NOT_PERMISSIBLE",
+                CircuitError::DegenerateRerandomization => "CIRCUIT_DEGENERATE_RERANDOMIZATION",
+                CircuitError::InvalidProofLengths => "CIRCUIT_INVALID_PROOF_LENGTHS",
+                CircuitError::VerificationFailed => "CIRCUIT_VERIFICATION_FAILED",
+                CircuitError::InvalidGenerator => "CIRCUIT_INVALID_GENERATOR",
+            },
+            Self::Exp(error) => match error {
+                ExpError::SecurityLevelNotAchieved => "EXP_SECURITY_LEVEL_NOT_ACHIEVED",
+                ExpError::IdentityIntermediate => "EXP_IDENTITY_INTERMEDIATE",
+                ExpError::ChallengeMismatch => "EXP_CHALLENGE_MISMATCH",
+                ExpError::VerificationFailed => "EXP_VERIFICATION_FAILED",
+                ExpError::Cancelled => "EXP_CANCELLED",
+            },
+            Self::Statement(error) => match error {
+                StatementError::RAtInfinity => "STATEMENT_R_AT_INFINITY",
+                StatementError::MessageHashMismatch => "STATEMENT_MESSAGE_HASH_MISMATCH",
+                StatementError::GuildIdMismatch => "STATEMENT_GUILD_ID_MISMATCH",
+                StatementError::UnknownGuild => "STATEMENT_UNKNOWN_GUILD",
+            },
+            Self::Signature(error) => match error {
+                SignatureError::InvalidSignature => "SIGNATURE_INVALID",
+                SignatureError::MissingRecoveryId => "SIGNATURE_MISSING_RECOVERY_ID",
+                SignatureError::RecoveryFailed => "SIGNATURE_RECOVERY_FAILED",
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn error_codes() {
+        let error = Error::from(StatementError::MessageHashMismatch);
+        assert_eq!(error.category(), "statement");
+        assert_eq!(error.code(), "STATEMENT_MESSAGE_HASH_MISMATCH");
+        assert_eq!(error.to_string(), "Signed message hash mismatch");
+
+        let error = Error::from(MembershipError::from(InterpolationError::TriedToInvertZero));
+        assert_eq!(error.category(), "membership");
+        assert_eq!(error.code(), "MEMBERSHIP_INTERPOLATION");
+        assert_eq!(
+            error.to_string(),
+            "interpolation error: attempted to invert a zero scalar"
+        );
+    }
+}
@@ -1,8 +1,9 @@
-use crate::arithmetic::{Modular, Point};
+use crate::arithmetic::{Modular, Point, Scalar};
 use crate::curve::Curve;
 use crate::U256;
 
 use bigint::Encoding;
+use rand_core::{CryptoRng, RngCore};
 use sha3::{Digest, Keccak256};
 
 pub struct PointHasher {
@@ -32,12 +33,129 @@ impl PointHasher {
         }
     }
 
+    pub fn insert_bytes(&mut self, bytes: &[u8]) {
+        self.hasher.update(bytes);
+    }
+
     pub fn finalize(self) -> U256 {
         let finalized = self.hasher.finalize();
         U256::from_be_bytes(finalized[0..32].try_into().unwrap())
     }
 }
 
+/// Fiat-Shamir transcript of a protocol with several challenge rounds.
+///
+/// Every challenge depends on all messages appended before it, including
+/// the previous challenges.
+#[derive(Clone)]
+pub struct Transcript {
+    hasher: Keccak256,
+}
+
+impl Transcript {
+    pub fn new(label: &[u8]) -> Self {
+        let mut hasher = Keccak256::new();
+        hasher.update(label);
+        Self { hasher }
+    }
+
+    pub fn append_u64(&mut self, number: u64) {
+        self.hasher.update(number.to_be_bytes());
+    }
+
+    /// Appends a length-prefixed byte string.
+    pub fn append_bytes(&mut self, bytes: &[u8]) {
+        self.append_u64(bytes.len() as u64);
+        self.hasher.update(bytes);
+    }
+
+    pub fn append_scalar<C: Curve>(&mut self, scalar: &Scalar<C>) {
+        self.hasher.update(scalar.inner().to_be_bytes());
+    }
+
+    pub fn append_point<C: Curve>(&mut self, point: &Point<C>) {
+        // affine coordinates are hashed because the projective
+        // representation of a point is not unique
+        let affine = point.to_affine();
+        self.hasher.update(affine.x().inner().to_be_bytes());
+        self.hasher.update(affine.y().inner().to_be_bytes());
+    }
+
+    pub fn challenge<C: Curve>(&mut self) -> Scalar<C> {
+        let digest = self.hasher.clone().finalize();
+        self.hasher.update(digest);
+        Scalar::new(U256::from_be_bytes(digest[0..32].try_into().unwrap()))
+    }
+
+    /// Random number generator keyed by every message appended so far.
+    pub fn into_rng(self) -> TranscriptRng {
+        TranscriptRng::from_seed(self.hasher.finalize().into())
+    }
+}
+
+/// Deterministic random number generator producing the blocks
+/// `Keccak256(seed || counter)`.
+///
+/// The output is reproducible from the seed and unpredictable without it.
+#[derive(Clone)]
+pub struct TranscriptRng {
+    seed: [u8; 32],
+    counter: u64,
+    block: [u8; 32],
+    position: usize,
+}
+
+impl TranscriptRng {
+    pub fn from_seed(seed: [u8; 32]) -> Self {
+        Self {
+            seed,
+            counter: 0,
+            block: [0; 32],
+            position: 32,
+        }
+    }
+
+    /// Derives an independent generator, e.g. for a round of a proof that
+    /// may be computed on another thread.
+    pub fn fork<R: RngCore>(rng: &mut R) -> Self {
+        let mut seed = [0; 32];
+        rng.fill_bytes(&mut seed);
+        Self::from_seed(seed)
+    }
+}
+
+impl RngCore for TranscriptRng {
+    fn next_u32(&mut self) -> u32 {
+        rand_core::impls::next_u32_via_fill(self)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        rand_core::impls::next_u64_via_fill(self)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for byte in dest.iter_mut() {
+            if self.position == self.block.len() {
+                let mut hasher = Keccak256::new();
+                hasher.update(self.seed);
+                hasher.update(self.counter.to_be_bytes());
+                self.block = hasher.finalize().into();
+                self.counter += 1;
+                self.position = 0;
+            }
+            *byte = self.block[self.position];
+            self.position += 1;
+        }
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
+}
+
+impl CryptoRng for TranscriptRng {}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -71,4 +189,25 @@ mod test {
         hasher.insert_point(&test_point);
         assert_eq!(hasher.finalize(), expected);
     }
+
+    #[test]
+    fn transcript_rng() {
+        let mut transcript = Transcript::new(b"test");
+        transcript.append_bytes(b"seed");
+        let mut rng = transcript.clone().into_rng();
+
+        // the output does not depend on how it is requested
+        let mut bytes = [0u8; 80];
+        rng.clone().fill_bytes(&mut bytes);
+        let mut pieces = [0u8; 80];
+        let (head, tail) = pieces.split_at_mut(33);
+        rng.fill_bytes(head);
+        rng.fill_bytes(tail);
+        assert_eq!(bytes, pieces);
+
+        transcript.append_u64(0);
+        let mut other = [0u8; 80];
+        transcript.into_rng().fill_bytes(&mut other);
+        assert_ne!(bytes, other);
+    }
 }
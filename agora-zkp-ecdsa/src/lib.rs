@@ -1,56 +1,313 @@
-#![feature(int_log)]
 #![deny(clippy::dbg_macro)]
 #![deny(clippy::all)]
 
 pub mod arithmetic;
 pub mod curve;
+pub mod ecdsa;
+pub mod error;
 mod hasher;
 pub mod parse;
 pub mod pedersen;
 pub mod proofs;
+pub mod ring;
+pub mod statement;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod vectors;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod verifier;
 
-use arithmetic::Point;
+use arithmetic::{Point, Scalar};
 pub use bigint::U256;
 use borsh::BorshSerialize;
 use curve::{Secp256k1, Tom256k1};
+use error::{Error, ParseError};
 use parse::*;
 use pedersen::PedersenCycle;
-use proofs::ZkAttestProof;
+use proofs::{ProofMode, ZkAttestPrecomputation, ZkAttestProof};
+use ring::RingDigest;
+use statement::StatementMode;
 use wasm_bindgen::prelude::*;
 
+use std::cell::Cell;
+
 #[derive(serde::Serialize)]
 #[serde(rename_all = "camelCase")]
 struct ProofOutput {
     guild_id: String,
     r_point: Point<Secp256k1>,
+    ring_digest: String,
     proof_binary: Vec<u8>,
 }
 
+/// Converts the error into a JavaScript `Error` whose `code` and `category`
+/// properties identify the error.
+impl From<Error> for JsValue {
+    fn from(error: Error) -> Self {
+        let js_error = js_sys::Error::new(&error.to_string());
+        js_error.set_name("ZkpError");
+        // NOTE setting a property of an ordinary object never fails
+        let _ = js_sys::Reflect::set(&js_error, &"code".into(), &error.code().into());
+        let _ = js_sys::Reflect::set(&js_error, &"category".into(), &error.category().into());
+        js_error.into()
+    }
+}
+
+impl From<ParseError> for JsValue {
+    fn from(error: ParseError) -> Self {
+        Error::from(error).into()
+    }
+}
+
+fn invalid_encoding<E: ToString>(error: E) -> Error {
+    ParseError::InvalidEncoding(error.to_string()).into()
+}
+
 #[wasm_bindgen(js_name = "generateProof")]
 pub fn generate_proof(input: JsValue, ring: JsValue) -> Result<JsValue, JsValue> {
     let mut rng = rand_core::OsRng;
     let pedersen = PedersenCycle::<Secp256k1, Tom256k1>::new(&mut rng);
 
     let input: ParsedProofInput<Secp256k1> = serde_wasm_bindgen::from_value::<ProofInput>(input)
-        .map_err(|e| e.to_string())?
+        .map_err(invalid_encoding)?
         .try_into()?;
 
-    let wasm_ring = serde_wasm_bindgen::from_value::<Ring>(ring).map_err(|e| e.to_string())?;
-    let ring: ParsedRing<Tom256k1> = parse_ring(wasm_ring)?;
+    let ring = parse_js_ring(ring)?;
 
     let zk_attest_proof = ZkAttestProof::construct(&mut rng, pedersen, input, &ring)?;
+    proof_output(zk_attest_proof)
+}
+
+/// Offline phase of `generateProof` that can run while the wallet prompt is
+/// open. Returns the serialized precomputation to be passed to
+/// `finalizeProof` once the signature is available.
+///
+/// A precomputation must be finalized only once, proofs finalized from the
+/// same bytes are linkable. The module keeps no record of them, the caller
+/// has to discard the bytes after `finalizeProof`, or track them by their
+/// `precomputationId` if they are stored.
+#[wasm_bindgen(js_name = "precomputeProof")]
+pub fn precompute_proof(pubkey: String, index: usize, ring: JsValue) -> Result<Vec<u8>, JsValue> {
+    let mut rng = rand_core::OsRng;
+    let pedersen = PedersenCycle::<Secp256k1, Tom256k1>::new(&mut rng);
+
+    let pubkey = parse_pubkey::<Secp256k1>(&pubkey)?;
+    let ring = parse_js_ring(ring)?;
+
+    let precomputation = ZkAttestProof::precompute(&mut rng, pedersen, &pubkey, index, &ring)?;
+    serialize_precomputation(&precomputation)
+}
+
+/// Hex encoded identifier of a serialized precomputation, the same for every
+/// copy of its bytes.
+#[wasm_bindgen(js_name = "precomputationId")]
+pub fn precomputation_id(precomputation: Vec<u8>) -> Result<String, JsValue> {
+    Ok(to_hex(&deserialize_precomputation(&precomputation)?.id()))
+}
+
+/// `domain` is the EIP-712 domain if the message was signed as typed data,
+/// it is left `undefined` for personal signatures.
+#[wasm_bindgen(js_name = "finalizeProof")]
+pub fn finalize_proof(
+    precomputation: Vec<u8>,
+    msg_hash: String,
+    signature: String,
+    guild_id: String,
+    domain: JsValue,
+) -> Result<JsValue, JsValue> {
+    let mut rng = rand_core::OsRng;
+
+    let precomputation = deserialize_precomputation(&precomputation)?;
+    let msg_hash = parse_msg_hash(&msg_hash)?;
+    let signature = parse_signature(&signature)?;
+    let statement = parse_js_statement(domain)?;
+
+    let zk_attest_proof =
+        precomputation.finalize(&mut rng, msg_hash, &signature, guild_id, statement)?;
+    proof_output(zk_attest_proof)
+}
+
+/// Prover handle that keeps the parsed ring and its digest between proofs.
+///
+/// The progress callback is called with the number of completed and total
+/// rounds of the ECDSA proof, returning `false` from it, or calling `cancel`
+/// from it, aborts the proof with the `EXP_CANCELLED` error.
+///
+/// Every proof and precomputation draws fresh Pedersen parameters, proofs
+/// sharing them could be linked to each other.
+///
+/// `setMode("circuit")` generates a much smaller signature proof that takes
+/// longer to compute and reports progress only at its start and end.
+#[wasm_bindgen]
+pub struct Prover {
+    mode: ProofMode,
+    ring: ParsedRing<Tom256k1>,
+    ring_digest: RingDigest,
+    on_progress: Option<js_sys::Function>,
+    cancelled: Cell<bool>,
+}
+
+#[wasm_bindgen]
+impl Prover {
+    #[wasm_bindgen(constructor)]
+    pub fn new(ring: JsValue) -> Result<Prover, JsValue> {
+        let ring = parse_js_ring(ring)?;
+        Ok(Self {
+            mode: ProofMode::default(),
+            ring_digest: RingDigest::new(&ring),
+            ring,
+            on_progress: None,
+            cancelled: Cell::new(false),
+        })
+    }
+
+    #[wasm_bindgen(js_name = "setRing")]
+    pub fn set_ring(&mut self, ring: JsValue) -> Result<(), JsValue> {
+        self.ring = parse_js_ring(ring)?;
+        self.ring_digest = RingDigest::new(&self.ring);
+        Ok(())
+    }
+
+    #[wasm_bindgen(getter, js_name = "ringDigest")]
+    pub fn ring_digest(&self) -> String {
+        self.ring_digest.to_hex()
+    }
+
+    #[wasm_bindgen(js_name = "setMode")]
+    pub fn set_mode(&mut self, mode: &str) -> Result<(), JsValue> {
+        self.mode = mode.parse()?;
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = "onProgress")]
+    pub fn on_progress(&mut self, callback: Option<js_sys::Function>) {
+        self.on_progress = callback;
+    }
+
+    /// Aborts the running proof at its next progress report.
+    pub fn cancel(&self) {
+        self.cancelled.set(true);
+    }
+
+    pub fn prove(&self, input: JsValue) -> Result<JsValue, JsValue> {
+        let input: ParsedProofInput<Secp256k1> =
+            serde_wasm_bindgen::from_value::<ProofInput>(input)
+                .map_err(invalid_encoding)?
+                .try_into()?;
+        let mut rng = rand_core::OsRng;
+        let pedersen = PedersenCycle::new(&mut rng);
+        let precomputation = ZkAttestProof::precompute_with_mode(
+            &mut rng,
+            pedersen,
+            &input.pubkey,
+            input.index,
+            &self.ring,
+            self.mode,
+        )?;
+        self.finalize_precomputation(
+            precomputation,
+            input.msg_hash,
+            &input.signature,
+            input.guild_id,
+            input.statement,
+        )
+    }
+
+    pub fn precompute(&self, pubkey: String, index: usize) -> Result<Vec<u8>, JsValue> {
+        let pubkey = parse_pubkey::<Secp256k1>(&pubkey)?;
+        let mut rng = rand_core::OsRng;
+        let pedersen = PedersenCycle::new(&mut rng);
+        let precomputation = ZkAttestProof::precompute_with_mode(
+            &mut rng, pedersen, &pubkey, index, &self.ring, self.mode,
+        )?;
+        serialize_precomputation(&precomputation)
+    }
+
+    pub fn finalize(
+        &self,
+        precomputation: Vec<u8>,
+        msg_hash: String,
+        signature: String,
+        guild_id: String,
+        domain: JsValue,
+    ) -> Result<JsValue, JsValue> {
+        let precomputation = deserialize_precomputation(&precomputation)?;
+        let msg_hash = parse_msg_hash(&msg_hash)?;
+        let signature = parse_signature(&signature)?;
+        let statement = parse_js_statement(domain)?;
+        self.finalize_precomputation(precomputation, msg_hash, &signature, guild_id, statement)
+    }
+}
+
+impl Prover {
+    fn finalize_precomputation(
+        &self,
+        precomputation: ZkAttestPrecomputation<Secp256k1, Tom256k1>,
+        msg_hash: Scalar<Secp256k1>,
+        signature: &Signature<Secp256k1>,
+        guild_id: String,
+        statement: StatementMode,
+    ) -> Result<JsValue, JsValue> {
+        self.cancelled.set(false);
+        let mut progress = |done: usize, total: usize| {
+            let proceed = match &self.on_progress {
+                // NOTE a throwing callback cancels the proof
+                Some(callback) => callback
+                    .call2(&JsValue::NULL, &done.into(), &total.into())
+                    .is_ok_and(|result| result != JsValue::FALSE),
+                None => true,
+            };
+            proceed && !self.cancelled.get()
+        };
+        let zk_attest_proof = precomputation.finalize_with_progress(
+            &mut rand_core::OsRng,
+            msg_hash,
+            signature,
+            guild_id,
+            statement,
+            &mut progress,
+        )?;
+        proof_output(zk_attest_proof)
+    }
+}
+
+fn serialize_precomputation(
+    precomputation: &ZkAttestPrecomputation<Secp256k1, Tom256k1>,
+) -> Result<Vec<u8>, JsValue> {
+    precomputation
+        .try_to_vec()
+        .map_err(|e| JsValue::from(invalid_encoding(e)))
+}
+
+fn deserialize_precomputation(
+    bytes: &[u8],
+) -> Result<ZkAttestPrecomputation<Secp256k1, Tom256k1>, Error> {
+    borsh::BorshDeserialize::try_from_slice(bytes).map_err(invalid_encoding)
+}
+
+fn parse_js_ring(ring: JsValue) -> Result<ParsedRing<Tom256k1>, Error> {
+    let wasm_ring = serde_wasm_bindgen::from_value::<Ring>(ring).map_err(invalid_encoding)?;
+    Ok(parse_ring(wasm_ring)?)
+}
+
+fn parse_js_statement(domain: JsValue) -> Result<StatementMode, Error> {
+    let domain = serde_wasm_bindgen::from_value::<Option<TypedDataDomain>>(domain)
+        .map_err(invalid_encoding)?;
+    Ok(parse_statement(domain)?)
+}
 
+fn proof_output(zk_attest_proof: ZkAttestProof<Secp256k1, Tom256k1>) -> Result<JsValue, JsValue> {
     let proof_binary = zk_attest_proof
         .try_to_vec()
-        .map_err(|e| JsValue::from(e.to_string()))?;
+        .map_err(|e| JsValue::from(invalid_encoding(e)))?;
 
     let proof_output = ProofOutput {
         guild_id: zk_attest_proof.guild_id,
         r_point: zk_attest_proof.r_point,
+        ring_digest: zk_attest_proof.ring_digest.to_hex(),
         proof_binary,
     };
 
-    serde_wasm_bindgen::to_value(&proof_output).map_err(|e| JsValue::from(e.to_string()))
+    serde_wasm_bindgen::to_value(&proof_output).map_err(|e| JsValue::from(invalid_encoding(e)))
 }
 
 // This function is only for wasm test purposes as the
@@ -61,10 +318,9 @@ pub fn verify_proof(proof: Vec<u8>, ring: JsValue) -> Result<JsValue, JsValue> {
     let mut rng = rand_core::OsRng;
 
     let proof: ZkAttestProof<Secp256k1, Tom256k1> =
-        borsh::BorshDeserialize::try_from_slice(proof.as_slice()).map_err(|e| e.to_string())?;
+        borsh::BorshDeserialize::try_from_slice(proof.as_slice()).map_err(invalid_encoding)?;
 
-    let wasm_ring = serde_wasm_bindgen::from_value::<Ring>(ring).map_err(|e| e.to_string())?;
-    let ring: ParsedRing<Tom256k1> = parse_ring(wasm_ring)?;
+    let ring = parse_js_ring(ring)?;
 
     proof.verify(&mut rng, &ring)?;
     Ok(JsValue::from(true))
@@ -1,5 +1,8 @@
 use crate::arithmetic::{AffinePoint, FieldElement, Modular, Scalar};
 use crate::curve::Curve;
+use crate::ecdsa;
+use crate::error::{Error, ParseError};
+use crate::statement::{Eip712Domain, StatementMode};
 use crate::U256;
 
 use serde::{Deserialize, Serialize};
@@ -7,14 +10,32 @@ use serde::{Deserialize, Serialize};
 pub type Ring = Vec<String>;
 pub type ParsedRing<C> = Vec<Scalar<C>>;
 
-#[derive(Clone, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ProofInput {
     pub msg_hash: String,
-    pub pubkey: String,
+    /// Recovered from the signature if missing.
+    #[serde(default)]
+    pub pubkey: Option<String>,
     pub signature: String,
     pub index: usize,
     pub guild_id: String,
+    /// Domain of an EIP-712 typed signature, personal signatures have none.
+    #[serde(default)]
+    pub domain: Option<TypedDataDomain>,
+    /// Converts a high-s signature to its low-s form before proving.
+    #[serde(default)]
+    pub normalize_s: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypedDataDomain {
+    pub name: String,
+    #[serde(default)]
+    pub version: Option<String>,
+    pub chain_id: u64,
+    pub verifying_contract: String,
 }
 
 pub struct ParsedProofInput<C: Curve> {
@@ -23,36 +44,82 @@ pub struct ParsedProofInput<C: Curve> {
     pub signature: Signature<C>,
     pub index: usize,
     pub guild_id: String,
+    pub statement: StatementMode,
 }
 
 impl<C: Curve> TryFrom<ProofInput> for ParsedProofInput<C> {
-    type Error = String;
+    type Error = Error;
     fn try_from(rhs: ProofInput) -> Result<Self, Self::Error> {
-        let hash = rhs.msg_hash.trim_start_matches("0x");
-        if hash.len() != 64 {
-            return Err("invalid hash length".to_string());
+        let msg_hash = parse_msg_hash(&rhs.msg_hash)?;
+        let mut signature = parse_signature(&rhs.signature)?;
+        if rhs.normalize_s {
+            signature.normalize_s();
         }
+        let pubkey = match rhs.pubkey {
+            Some(pubkey) => parse_pubkey(&pubkey)?,
+            None => ecdsa::recover(msg_hash, &signature)?,
+        };
         Ok(Self {
-            msg_hash: Scalar::new(U256::from_be_hex(hash)),
-            pubkey: parse_pubkey(&rhs.pubkey)?,
-            signature: parse_signature(&rhs.signature)?,
+            msg_hash,
+            pubkey,
+            signature,
             index: rhs.index,
             guild_id: rhs.guild_id,
+            statement: parse_statement(rhs.domain)?,
+        })
+    }
+}
+
+impl TryFrom<TypedDataDomain> for Eip712Domain {
+    type Error = ParseError;
+    fn try_from(rhs: TypedDataDomain) -> Result<Self, Self::Error> {
+        let verifying_contract = parse_hex(&rhs.verifying_contract)?
+            .try_into()
+            .map_err(|_| ParseError::InvalidAddress)?;
+        Ok(Self {
+            name: rhs.name,
+            version: rhs.version,
+            chain_id: rhs.chain_id,
+            verifying_contract,
         })
     }
 }
 
+/// Typed data statement in the domain if there is one, personal sign
+/// otherwise.
+pub fn parse_statement(domain: Option<TypedDataDomain>) -> Result<StatementMode, ParseError> {
+    Ok(match domain {
+        Some(domain) => StatementMode::TypedData(domain.try_into()?),
+        None => StatementMode::PersonalSign,
+    })
+}
+
 pub struct Signature<C> {
     pub r: Scalar<C>,
     pub s: Scalar<C>,
+    /// Parity of the y coordinate of the signature's R point, needed to
+    /// recover the public key.
+    pub recovery_id: Option<u8>,
 }
 
-enum Parse {
-    Pubkey,
-    Signature,
+impl<C: Curve> Signature<C> {
+    /// Returns `true` if `s` is in the upper half of the scalar field, which
+    /// EIP-2 forbids for transactions.
+    pub fn is_high_s(&self) -> bool {
+        self.s.inner() > &(C::ORDER >> 1)
+    }
+
+    /// Replaces a high `s` by `n - s`. Both signatures are valid, the
+    /// recovery id is flipped as the R point is negated.
+    pub fn normalize_s(&mut self) {
+        if self.is_high_s() {
+            self.s = -self.s;
+            self.recovery_id = self.recovery_id.map(|id| id ^ 1);
+        }
+    }
 }
 
-pub fn parse_ring<C: Curve>(ring: Ring) -> Result<ParsedRing<C>, String> {
+pub fn parse_ring<C: Curve>(ring: Ring) -> Result<ParsedRing<C>, ParseError> {
     let mut parsed = ParsedRing::with_capacity(ring.len());
     for pk in ring.iter() {
         parsed.push(extract_x_coordinate(pk)?);
@@ -60,50 +127,123 @@ pub fn parse_ring<C: Curve>(ring: Ring) -> Result<ParsedRing<C>, String> {
     Ok(parsed)
 }
 
-fn extract_x_coordinate<C: Curve>(pubkey: &str) -> Result<Scalar<C>, String> {
-    let stripped = pubkey.trim_start_matches("0x").trim_start_matches("04");
+// Ring entries are bare x coordinates or public keys, with or without the
+// `04` prefix of uncompressed keys.
+fn extract_x_coordinate<C: Curve>(pubkey: &str) -> Result<Scalar<C>, ParseError> {
+    let stripped = pubkey.strip_prefix("0x").unwrap_or(pubkey);
+    let x = match stripped.len() {
+        64 => parse_coordinate(stripped)?,
+        128 => parse_coordinates(stripped)?.0,
+        _ => parse_uncompressed(stripped)?.0,
+    };
+    Ok(Scalar::new(x))
+}
+
+// Parses the coordinates of a `04 || x || y` key without the `0x` prefix.
+fn parse_uncompressed(pubkey: &str) -> Result<(U256, U256), ParseError> {
+    parse_coordinates(pubkey.strip_prefix("04").ok_or(ParseError::InvalidPubkey)?)
+}
+
+fn parse_coordinates(coordinates: &str) -> Result<(U256, U256), ParseError> {
+    if coordinates.len() != 128 || !is_hex(coordinates) {
+        return Err(ParseError::InvalidPubkey);
+    }
+    Ok((
+        parse_coordinate(&coordinates[..64])?,
+        parse_coordinate(&coordinates[64..])?,
+    ))
+}
+
+fn parse_coordinate(hex: &str) -> Result<U256, ParseError> {
     // NOTE this check avoids explicit panics by `from_be_hex`
-    if stripped.len() > 128 {
-        return Err("invalid pubkey".to_string());
+    if hex.len() != 64 || !is_hex(hex) {
+        return Err(ParseError::InvalidPubkey);
     }
-    Ok(Scalar::new(U256::from_be_hex(&stripped[..64])))
+    Ok(U256::from_be_hex(hex))
 }
 
-fn parse_pubkey<C: Curve>(pubkey: &str) -> Result<AffinePoint<C>, String> {
-    let (x, y) = parse_str(pubkey, Parse::Pubkey)?;
-    Ok(AffinePoint::new(
-        FieldElement::<C>::new(x),
-        FieldElement::<C>::new(y),
-    ))
+fn is_hex(hex: &str) -> bool {
+    hex.bytes().all(|byte| byte.is_ascii_hexdigit())
 }
 
-fn parse_signature<C: Curve>(signature: &str) -> Result<Signature<C>, String> {
-    let (r, s) = parse_str(signature, Parse::Signature)?;
+pub fn parse_msg_hash<C: Curve>(msg_hash: &str) -> Result<Scalar<C>, ParseError> {
+    let hash = msg_hash.trim_start_matches("0x");
+    if hash.len() != 64 {
+        return Err(ParseError::InvalidHashLength);
+    }
+    Ok(Scalar::new(U256::from_be_hex(hash)))
+}
+
+/// Parses an uncompressed `0x04 || x || y` public key, the `0x` prefix is
+/// optional. Keys that are not on the curve are rejected.
+pub fn parse_pubkey<C: Curve>(pubkey: &str) -> Result<AffinePoint<C>, ParseError> {
+    let (x, y) = parse_uncompressed(pubkey.strip_prefix("0x").unwrap_or(pubkey))?;
+    let point = AffinePoint::new(FieldElement::<C>::new(x), FieldElement::<C>::new(y));
+    if point.is_identity() || !point.is_on_curve() {
+        return Err(ParseError::InvalidPubkey);
+    }
+    Ok(point)
+}
+
+/// Parses a 65 byte `r || s || v` signature or a 64 byte EIP-2098 compact
+/// `r || yParity || s` signature.
+///
+/// `v` may be the bare recovery id, the recovery id plus 27, or encode a chain
+/// id as in EIP-155, in which case it can be longer than a single byte.
+pub fn parse_signature<C: Curve>(signature: &str) -> Result<Signature<C>, ParseError> {
+    let bytes = parse_hex(signature)?;
+    if bytes.len() < 64 || bytes.len() > 72 {
+        return Err(ParseError::InvalidBytes);
+    }
+    let r = U256::from_be_slice(&bytes[..32]);
+    let mut s = U256::from_be_slice(&bytes[32..64]);
+    let recovery_id = if bytes.len() == 64 {
+        // NOTE the top bit of s is the parity, s is always low in this form
+        let parity = bytes[32] >> 7;
+        s &= U256::MAX >> 1;
+        parity
+    } else {
+        let v = bytes[64..]
+            .iter()
+            .fold(0u64, |v, byte| (v << 8) | u64::from(*byte));
+        parse_recovery_id(v)?
+    };
     Ok(Signature {
         r: Scalar::new(r),
         s: Scalar::new(s),
+        recovery_id: Some(recovery_id),
     })
 }
 
-fn parse_str(slice: &str, into: Parse) -> Result<(U256, U256), String> {
-    let trimmed = slice.trim_start_matches("0x");
-    if trimmed.len() != 130 {
-        return Err("invalid bytes".to_string());
+fn parse_recovery_id(v: u64) -> Result<u8, ParseError> {
+    match v {
+        0 | 1 => Ok(v as u8),
+        27 | 28 => Ok((v - 27) as u8),
+        // v = 2 * chain id + 35 + recovery id
+        35.. => Ok(((v - 35) % 2) as u8),
+        _ => Err(ParseError::InvalidRecoveryId),
     }
-    match into {
-        Parse::Pubkey => {
-            // NOTE pubkeys always start with 0x04
-            let x = U256::from_be_hex(&trimmed[2..66]);
-            let y = U256::from_be_hex(&trimmed[66..]);
-            Ok((x, y))
-        }
-        Parse::Signature => {
-            let r = U256::from_be_hex(&trimmed[0..64]);
-            let s = U256::from_be_hex(&trimmed[64..128]);
-            // NOTE last 2 bytes represent the recovery `v` parameter
-            Ok((r, s))
-        }
+}
+
+/// Decodes a hex string with an optional `0x` prefix into bytes.
+pub fn parse_hex(hex: &str) -> Result<Vec<u8>, ParseError> {
+    let stripped = hex.trim_start_matches("0x");
+    if !stripped.len().is_multiple_of(2) || !stripped.is_ascii() {
+        return Err(ParseError::InvalidBytes);
     }
+    (0..stripped.len())
+        .step_by(2)
+        .map(|i| parse_byte(&stripped[i..i + 2]))
+        .collect()
+}
+
+/// Encodes bytes as a lowercase hex string without prefix.
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn parse_byte(hex: &str) -> Result<u8, ParseError> {
+    u8::from_str_radix(hex, 16).map_err(|_| ParseError::InvalidBytes)
 }
 
 #[cfg(test)]
@@ -124,6 +264,30 @@ mod test {
                 "08c6cd9400645819c8c556a6e83e0a7728f070a813bb9d24d5c24290e21fc5e4"
             ))
         );
+        // bare x coordinates are taken as they are, even if they start with 04
+        let bare = "04c6cd9400645819c8c556a6e83e0a7728f070a813bb9d24d5c24290e21fc5e4";
+        assert_eq!(
+            extract_x_coordinate::<Tom256k1>(bare).unwrap(),
+            Scalar::<Tom256k1>::new(U256::from_be_hex(bare))
+        );
+
+        for invalid in [
+            "",
+            "0x04",
+            &pubkey[..100],
+            &pubkey.replace("0x04", "0x0404"),
+            &pubkey.replace("0x04", "0x0x04"),
+            &pubkey.replace("0x04", "0x05"),
+            &pubkey[4..126],
+            &pubkey.replace("c5e4", "c5g4"),
+            &pubkey.replace("c5e4", "c5é"),
+            &bare.replace("c5e4", "c5ex"),
+        ] {
+            assert_eq!(
+                extract_x_coordinate::<Tom256k1>(invalid).err(),
+                Some(ParseError::InvalidPubkey)
+            );
+        }
     }
 
     #[test]
@@ -161,6 +325,70 @@ mod test {
         );
 
         assert_eq!(pubkey_point.z(), &FieldElement::ONE);
+        // not on the curve
+        assert_eq!(
+            parse_pubkey::<Secp256k1>(&pubkey.replace("2718", "2719")).err(),
+            Some(ParseError::InvalidPubkey)
+        );
+        assert_eq!(
+            parse_pubkey::<Secp256k1>(&pubkey[2..66]).err(),
+            Some(ParseError::InvalidPubkey)
+        );
+
+        assert_eq!(sig.recovery_id, Some(1));
+        assert_eq!(
+            parse_signature::<Secp256k1>(&signature.replace("311c", "311d")).err(),
+            Some(ParseError::InvalidRecoveryId)
+        );
+    }
+
+    #[test]
+    fn signature_formats() {
+        let r = "45c4039b611c0cc207ff7fb7a6899ea0431aac2cf37515d74a71f2df00e2c3e0";
+        let s = "096fad5e7eda762898fffd4644f8a7a406bf6bde868814ea03058c882fcd2331";
+        let expected = parse_signature::<Secp256k1>(&format!("{}{}1c", r, s)).unwrap();
+
+        let same = |signature: String| {
+            let parsed = parse_signature::<Secp256k1>(&signature).unwrap();
+            parsed.r == expected.r
+                && parsed.s == expected.s
+                && parsed.recovery_id == expected.recovery_id
+        };
+        assert!(same(format!("0x{}{}01", r, s)));
+        // EIP-155 on mainnet and on a chain id that does not fit into a byte
+        assert!(same(format!("{}{}26", r, s)));
+        assert!(same(format!("{}{}0100000000000024", r, s)));
+        // EIP-2098 compact form with the parity in the top bit of s
+        assert!(same(format!("{}8{}", r, &s[1..])));
+        assert_eq!(
+            parse_signature::<Secp256k1>(&format!("{}{}", r, s))
+                .unwrap()
+                .recovery_id,
+            Some(0)
+        );
+
+        assert_eq!(
+            parse_signature::<Secp256k1>(&format!("{}{}02", r, s)).err(),
+            Some(ParseError::InvalidRecoveryId)
+        );
+        assert_eq!(
+            parse_signature::<Secp256k1>(&r.repeat(2)[..126]).err(),
+            Some(ParseError::InvalidBytes)
+        );
+
+        let mut high = parse_signature::<Secp256k1>(&format!("{}{}1c", r, s)).unwrap();
+        high.s = -high.s;
+        high.recovery_id = Some(0);
+        assert!(high.is_high_s());
+        assert!(!expected.is_high_s());
+        high.normalize_s();
+        assert!(!high.is_high_s());
+        assert!(high.s == expected.s && high.recovery_id == expected.recovery_id);
+
+        assert_eq!(parse_hex("0x00ff10").unwrap(), vec![0x00, 0xff, 0x10]);
+        assert_eq!(to_hex(&[0x00, 0xff, 0x10]), "00ff10");
+        assert!(parse_hex("0ff").is_err());
+        assert!(parse_hex("zz").is_err());
     }
 
     #[test]
@@ -168,9 +396,11 @@ mod test {
         let input = ProofInput {
             msg_hash: "0x1ab4850e7f0a85a521e87b274e3130efdb45f6a47e74e6dcebf5591c6bc8f16e".to_string(),
             signature:"0x45c4039b611c0cc207ff7fb7a6899ea0431aac2cf37515d74a71f2df00e2c3e0096fad5e7eda762898fffd4644f8a7a406bf6bde868814ea03058c882fcd23311c".to_string(),
-            pubkey:"0x0408c6cd9400645819c8c556a6e83e0a7728f070a813bb9d24d5c24290e21fc5e438396f9333264d3e7c1d3e6ee1bc572b2f00b98db7065e9bf278f2b8dbe02718".to_string(),
+            pubkey: Some("0x0408c6cd9400645819c8c556a6e83e0a7728f070a813bb9d24d5c24290e21fc5e438396f9333264d3e7c1d3e6ee1bc572b2f00b98db7065e9bf278f2b8dbe02718".to_string()),
             index: 1,
             guild_id: "Our-guild#2314".to_string(),
+            domain: None,
+            normalize_s: false,
         };
         let ring = vec![
             "0x1679349AeA848f928cE886fbAE10a85660CBFecE0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000".to_string(),
@@ -178,7 +408,7 @@ mod test {
             "0x7679349AeA848f928cE886fbAE10a85660CBFecF0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000".to_string(),
         ];
 
-        let parsed_input: ParsedProofInput<Secp256k1> = input.try_into().unwrap();
+        let parsed_input: ParsedProofInput<Secp256k1> = input.clone().try_into().unwrap();
         let parsed_ring: ParsedRing<Tom256k1> = parse_ring(ring).unwrap();
 
         assert_eq!(
@@ -188,6 +418,15 @@ mod test {
             ))
         );
         assert_eq!(parsed_input.guild_id, "Our-guild#2314");
+        assert_eq!(parsed_input.signature.recovery_id, Some(1));
+
+        let recovered: ParsedProofInput<Secp256k1> = ProofInput {
+            pubkey: None,
+            ..input
+        }
+        .try_into()
+        .unwrap();
+        assert_eq!(recovered.pubkey, parsed_input.pubkey);
         assert_eq!(
             parsed_ring[0],
             Scalar::new(U256::from_be_hex(
@@ -25,6 +25,12 @@ impl<C: Curve, CC: Cycle<C>> PedersenCycle<C, CC> {
     pub fn cycle(&self) -> PedersenGenerator<CC> {
         self.cycle
     }
+
+    /// Replaces the generator of the cycle curve, e.g. by one that is
+    /// required by a circuit proof.
+    pub fn with_cycle(self, cycle: PedersenGenerator<CC>) -> Self {
+        Self { cycle, ..self }
+    }
 }
 
 #[derive(Copy, Clone, Debug, BorshDeserialize, BorshSerialize)]
@@ -36,6 +42,12 @@ impl<C: Curve> PedersenGenerator<C> {
         Self(Point::<C>::GENERATOR * random_scalar)
     }
 
+    /// Wraps a point with unknown discrete logarithm, e.g. one obtained by
+    /// hashing to the curve.
+    pub fn from_point(point: Point<C>) -> Self {
+        Self(point)
+    }
+
     pub fn generator(&self) -> Point<C> {
         self.0
     }
@@ -0,0 +1,534 @@
+use super::membership::{MembershipCommitments, MembershipProof};
+use super::utils::{optimal_base, Optimize};
+use crate::arithmetic::{Modular, Point, Scalar};
+use crate::curve::Curve;
+use crate::error::{Error, MembershipError, ParameterError};
+use crate::hasher::PointHasher;
+use crate::pedersen::*;
+use crate::ring::RingDigest;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+
+/// Boolean formula over ring memberships, e.g. "holds NFT A AND (token B OR
+/// token C)" is `And([Ring(0), Or([Ring(1), Ring(2)])])`.
+///
+/// `Ring(i)` refers to the `i`th ring passed to the proof.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RingFormula {
+    Ring(usize),
+    And(Vec<RingFormula>),
+    Or(Vec<RingFormula>),
+}
+
+impl RingFormula {
+    /// Checks that every referenced ring exists and that no connective is
+    /// empty.
+    pub fn validate(&self, ring_count: usize) -> Result<(), ParameterError> {
+        match self {
+            Self::Ring(i) if *i >= ring_count => Err(ParameterError::InvalidRingReference),
+            Self::Ring(_) => Ok(()),
+            Self::And(children) | Self::Or(children) => {
+                if children.is_empty() {
+                    return Err(ParameterError::EmptyFormula);
+                }
+                children
+                    .iter()
+                    .try_for_each(|child| child.validate(ring_count))
+            }
+        }
+    }
+
+    // Encodes the formula unambiguously, so that formulas of different shape
+    // or ring references never hash to the same transcript.
+    fn insert_into(&self, hasher: &mut PointHasher) {
+        match self {
+            Self::Ring(i) => {
+                hasher.insert_bytes(&[0]);
+                hasher.insert_bytes(&(*i as u64).to_be_bytes());
+            }
+            Self::And(children) | Self::Or(children) => {
+                let tag = if matches!(self, Self::And(_)) { 1 } else { 2 };
+                hasher.insert_bytes(&[tag]);
+                hasher.insert_bytes(&(children.len() as u64).to_be_bytes());
+                children.iter().for_each(|child| child.insert_into(hasher));
+            }
+        }
+    }
+
+    /// Evaluates the formula given the index of the key in each ring (if
+    /// present).
+    pub fn is_satisfied_by(&self, indices: &[Option<usize>]) -> bool {
+        match self {
+            Self::Ring(i) => matches!(indices.get(*i), Some(Some(_))),
+            Self::And(children) => children.iter().all(|c| c.is_satisfied_by(indices)),
+            Self::Or(children) => children.iter().any(|c| c.is_satisfied_by(indices)),
+        }
+    }
+}
+
+/// Proof that a committed key is a member of several rings under a
+/// [`RingFormula`].
+///
+/// Conjunctions are plain [`MembershipProof`]s answering the same challenge,
+/// disjunctions follow Cramer-Damgård-Schoenmakers: the challenges of the
+/// branches have to sum up to the challenge of the parent, so the prover is
+/// free to pick all but one of them and simulate the branches it cannot
+/// satisfy.
+#[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
+pub enum CompoundMembershipProof<C: Curve> {
+    Ring(MembershipProof<C>),
+    And(Vec<CompoundMembershipProof<C>>),
+    Or {
+        // challenges of all but the last branch, the last one is implied
+        challenges: Vec<Scalar<C>>,
+        branches: Vec<CompoundMembershipProof<C>>,
+    },
+}
+
+// Proof tree after the first move of the protocol, i.e. before the root
+// challenge is known.
+enum PendingProof<C: Curve> {
+    Ring(Box<MembershipCommitments<C>>),
+    And(Vec<PendingProof<C>>),
+    Or(Vec<PendingBranch<C>>),
+}
+
+enum PendingBranch<C: Curve> {
+    Real(PendingProof<C>),
+    Simulated {
+        challenge: Scalar<C>,
+        proof: CompoundMembershipProof<C>,
+    },
+}
+
+struct Context<'a, C: Curve> {
+    pedersen_generator: &'a PedersenGenerator<C>,
+    commitment_to_key: &'a Point<C>,
+    rings: &'a [&'a [Scalar<C>]],
+}
+
+impl<C: Curve> CompoundMembershipProof<C> {
+    const HASH_ID: &'static [u8] = b"compound-membership-proof";
+
+    /// Proves that the key committed in `commitment_to_key` satisfies
+    /// `formula`, where `indices[i]` is the position of the key in `rings[i]`
+    /// (if it is present there at all).
+    pub fn construct<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        pedersen_generator: &PedersenGenerator<C>,
+        commitment_to_key: &PedersenCommitment<C>,
+        formula: &RingFormula,
+        indices: &[Option<usize>],
+        rings: &[&[Scalar<C>]],
+    ) -> Result<Self, Error> {
+        formula.validate(rings.len())?;
+        if indices.len() != rings.len() {
+            return Err(ParameterError::InvalidIndexCount.into());
+        }
+        if !formula.is_satisfied_by(indices) {
+            return Err(MembershipError::FormulaNotSatisfied.into());
+        }
+
+        let key_point = commitment_to_key.commitment();
+        let context = Context {
+            pedersen_generator,
+            commitment_to_key: &key_point,
+            rings,
+        };
+
+        let pending = context.commit(rng, formula, indices)?;
+        let mut hasher = Self::hasher(&key_point, formula, rings);
+        pending.insert_into(&mut hasher);
+        let challenge = Scalar::new(hasher.finalize());
+
+        Ok(pending.respond(commitment_to_key, challenge))
+    }
+
+    pub fn verify<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pedersen_generator: &PedersenGenerator<C>,
+        commitment_to_key: &Point<C>,
+        formula: &RingFormula,
+        rings: &[&[Scalar<C>]],
+    ) -> Result<(), Error> {
+        formula.validate(rings.len())?;
+        let context = Context {
+            pedersen_generator,
+            commitment_to_key,
+            rings,
+        };
+
+        let mut hasher = Self::hasher(commitment_to_key, formula, rings);
+        self.insert_into(&mut hasher);
+        let challenge = Scalar::new(hasher.finalize());
+
+        context.verify(rng, self, formula, challenge)
+    }
+
+    // The challenge commits to the whole statement: the key commitment, the
+    // formula and the digest of every ring it refers to.
+    fn hasher(
+        commitment_to_key: &Point<C>,
+        formula: &RingFormula,
+        rings: &[&[Scalar<C>]],
+    ) -> PointHasher {
+        let mut hasher = PointHasher::new(Self::HASH_ID);
+        hasher.insert_point(commitment_to_key);
+        formula.insert_into(&mut hasher);
+        hasher.insert_bytes(&(rings.len() as u64).to_be_bytes());
+        for ring in rings {
+            hasher.insert_bytes(RingDigest::new(ring).as_bytes());
+        }
+        hasher
+    }
+
+    fn insert_into(&self, hasher: &mut PointHasher) {
+        match self {
+            Self::Ring(proof) => proof.insert_into(hasher),
+            Self::And(children)
+            | Self::Or {
+                branches: children, ..
+            } => children.iter().for_each(|child| child.insert_into(hasher)),
+        }
+    }
+}
+
+impl<'a, C: Curve> Context<'a, C> {
+    fn commit<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        formula: &RingFormula,
+        indices: &[Option<usize>],
+    ) -> Result<PendingProof<C>, Error> {
+        match formula {
+            RingFormula::Ring(i) => {
+                let index = indices[*i].ok_or(MembershipError::FormulaNotSatisfied)?;
+                let ring = self.rings[*i];
+                let base = optimal_base(ring.len(), Optimize::ProverTime);
+                let commitments =
+                    MembershipProof::commit(rng, self.pedersen_generator, index, ring, base)?;
+                Ok(PendingProof::Ring(Box::new(commitments)))
+            }
+            RingFormula::And(children) => children
+                .iter()
+                .map(|child| self.commit(rng, child, indices))
+                .collect::<Result<Vec<_>, _>>()
+                .map(PendingProof::And),
+            RingFormula::Or(children) => {
+                // NOTE unwrap is fine because the formula is satisfied
+                let real = children
+                    .iter()
+                    .position(|child| child.is_satisfied_by(indices))
+                    .unwrap();
+                let mut branches = Vec::with_capacity(children.len());
+                for (i, child) in children.iter().enumerate() {
+                    if i == real {
+                        branches.push(PendingBranch::Real(self.commit(rng, child, indices)?));
+                    } else {
+                        let challenge = Scalar::random(rng);
+                        let proof = self.simulate(rng, child, challenge)?;
+                        branches.push(PendingBranch::Simulated { challenge, proof });
+                    }
+                }
+                Ok(PendingProof::Or(branches))
+            }
+        }
+    }
+
+    fn simulate<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        formula: &RingFormula,
+        challenge: Scalar<C>,
+    ) -> Result<CompoundMembershipProof<C>, Error> {
+        match formula {
+            RingFormula::Ring(i) => MembershipProof::simulate(
+                rng,
+                self.pedersen_generator,
+                self.commitment_to_key,
+                self.rings[*i],
+                challenge,
+                // NOTE the base only depends on the public ring length
+                optimal_base(self.rings[*i].len(), Optimize::ProverTime),
+            )
+            .map(CompoundMembershipProof::Ring),
+            RingFormula::And(children) => children
+                .iter()
+                .map(|child| self.simulate(rng, child, challenge))
+                .collect::<Result<Vec<_>, _>>()
+                .map(CompoundMembershipProof::And),
+            RingFormula::Or(children) => {
+                let mut challenges = Vec::with_capacity(children.len() - 1);
+                let mut last_challenge = challenge;
+                for _ in 1..children.len() {
+                    let branch_challenge = Scalar::random(rng);
+                    last_challenge -= branch_challenge;
+                    challenges.push(branch_challenge);
+                }
+                let branches = children
+                    .iter()
+                    .zip(challenges.iter().chain(std::iter::once(&last_challenge)))
+                    .map(|(child, &c)| self.simulate(rng, child, c))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(CompoundMembershipProof::Or {
+                    challenges,
+                    branches,
+                })
+            }
+        }
+    }
+
+    fn verify<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        proof: &CompoundMembershipProof<C>,
+        formula: &RingFormula,
+        challenge: Scalar<C>,
+    ) -> Result<(), Error> {
+        match (proof, formula) {
+            (CompoundMembershipProof::Ring(proof), RingFormula::Ring(i)) => proof
+                .verify_with_challenge(
+                    rng,
+                    self.pedersen_generator,
+                    self.commitment_to_key,
+                    self.rings[*i],
+                    challenge,
+                ),
+            (CompoundMembershipProof::And(proofs), RingFormula::And(children))
+                if proofs.len() == children.len() =>
+            {
+                proofs
+                    .iter()
+                    .zip(children)
+                    .try_for_each(|(proof, child)| self.verify(rng, proof, child, challenge))
+            }
+            (
+                CompoundMembershipProof::Or {
+                    challenges,
+                    branches,
+                },
+                RingFormula::Or(children),
+            ) if branches.len() == children.len() && challenges.len() + 1 == children.len() => {
+                let mut last_challenge = challenge;
+                for branch_challenge in challenges {
+                    last_challenge -= *branch_challenge;
+                }
+                branches
+                    .iter()
+                    .zip(children)
+                    .zip(challenges.iter().chain(std::iter::once(&last_challenge)))
+                    .try_for_each(|((proof, child), &c)| self.verify(rng, proof, child, c))
+            }
+            _ => Err(MembershipError::FormulaMismatch.into()),
+        }
+    }
+}
+
+impl<C: Curve> PendingProof<C> {
+    fn insert_into(&self, hasher: &mut PointHasher) {
+        match self {
+            Self::Ring(commitments) => commitments.insert_into(hasher),
+            Self::And(children) => children.iter().for_each(|child| child.insert_into(hasher)),
+            Self::Or(branches) => branches.iter().for_each(|branch| match branch {
+                PendingBranch::Real(pending) => pending.insert_into(hasher),
+                PendingBranch::Simulated { proof, .. } => proof.insert_into(hasher),
+            }),
+        }
+    }
+
+    fn respond(
+        self,
+        commitment_to_key: &PedersenCommitment<C>,
+        challenge: Scalar<C>,
+    ) -> CompoundMembershipProof<C> {
+        match self {
+            Self::Ring(commitments) => {
+                CompoundMembershipProof::Ring(commitments.respond(commitment_to_key, challenge))
+            }
+            Self::And(children) => CompoundMembershipProof::And(
+                children
+                    .into_iter()
+                    .map(|child| child.respond(commitment_to_key, challenge))
+                    .collect(),
+            ),
+            Self::Or(branches) => {
+                let mut real_challenge = challenge;
+                for branch in branches.iter() {
+                    if let PendingBranch::Simulated { challenge, .. } = branch {
+                        real_challenge -= *challenge;
+                    }
+                }
+
+                let mut challenges = Vec::with_capacity(branches.len());
+                let mut proofs = Vec::with_capacity(branches.len());
+                for branch in branches {
+                    match branch {
+                        PendingBranch::Real(pending) => {
+                            challenges.push(real_challenge);
+                            proofs.push(pending.respond(commitment_to_key, real_challenge));
+                        }
+                        PendingBranch::Simulated { challenge, proof } => {
+                            challenges.push(challenge);
+                            proofs.push(proof);
+                        }
+                    }
+                }
+                challenges.pop();
+
+                CompoundMembershipProof::Or {
+                    challenges,
+                    branches: proofs,
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::curve::Tom256k1;
+    use crate::U256;
+    use rand::rngs::StdRng;
+    use rand_core::SeedableRng;
+
+    type TomScalar = Scalar<Tom256k1>;
+
+    fn ring(offset: u32, len: u32) -> Vec<TomScalar> {
+        (offset..offset + len)
+            .map(|i| TomScalar::new(U256::from_u32(i)))
+            .collect()
+    }
+
+    #[test]
+    fn formula_evaluation() {
+        let formula = RingFormula::And(vec![
+            RingFormula::Ring(0),
+            RingFormula::Or(vec![RingFormula::Ring(1), RingFormula::Ring(2)]),
+        ]);
+        assert!(formula.validate(3).is_ok());
+        assert!(formula.validate(2).is_err());
+        assert!(RingFormula::Or(vec![]).validate(1).is_err());
+
+        assert!(formula.is_satisfied_by(&[Some(0), None, Some(3)]));
+        assert!(formula.is_satisfied_by(&[Some(0), Some(1), None]));
+        assert!(!formula.is_satisfied_by(&[Some(0), None, None]));
+        assert!(!formula.is_satisfied_by(&[None, Some(1), Some(3)]));
+    }
+
+    #[test]
+    fn valid_compound_membership_proof() {
+        let mut rng = StdRng::from_seed([26; 32]);
+        let pedersen_generator = PedersenGenerator::<Tom256k1>::new(&mut rng);
+
+        // the key (7) is in rings 0 and 2 but not in ring 1
+        let ring_0 = ring(0, 8);
+        let ring_1 = ring(100, 5);
+        let mut ring_2 = ring(200, 3);
+        ring_2.push(TomScalar::new(U256::from_u32(7)));
+        let rings = [ring_0.as_slice(), ring_1.as_slice(), ring_2.as_slice()];
+        let indices = [Some(7), None, Some(3)];
+
+        let commitment_to_key = pedersen_generator.commit(&mut rng, ring_0[7]);
+        let formula = RingFormula::And(vec![
+            RingFormula::Ring(0),
+            RingFormula::Or(vec![RingFormula::Ring(1), RingFormula::Ring(2)]),
+        ]);
+
+        let proof = CompoundMembershipProof::construct(
+            &mut rng,
+            &pedersen_generator,
+            &commitment_to_key,
+            &formula,
+            &indices,
+            &rings,
+        )
+        .unwrap();
+
+        assert!(proof
+            .verify(
+                &mut rng,
+                &pedersen_generator,
+                &commitment_to_key.commitment(),
+                &formula,
+                &rings,
+            )
+            .is_ok());
+
+        // a different formula with the same shape is rejected
+        let swapped = RingFormula::And(vec![
+            RingFormula::Ring(1),
+            RingFormula::Or(vec![RingFormula::Ring(0), RingFormula::Ring(2)]),
+        ]);
+        assert!(proof
+            .verify(
+                &mut rng,
+                &pedersen_generator,
+                &commitment_to_key.commitment(),
+                &swapped,
+                &rings,
+            )
+            .is_err());
+
+        // the proof does not hold for another key commitment
+        let other_commitment = pedersen_generator.commit(&mut rng, ring_0[7]);
+        assert!(proof
+            .verify(
+                &mut rng,
+                &pedersen_generator,
+                &other_commitment.commitment(),
+                &formula,
+                &rings,
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn unsatisfied_formula() {
+        let mut rng = StdRng::from_seed([26; 32]);
+        let pedersen_generator = PedersenGenerator::<Tom256k1>::new(&mut rng);
+        let ring_0 = ring(0, 4);
+        let ring_1 = ring(100, 4);
+        let rings = [ring_0.as_slice(), ring_1.as_slice()];
+
+        let commitment_to_key = pedersen_generator.commit(&mut rng, ring_0[2]);
+        let formula = RingFormula::And(vec![RingFormula::Ring(0), RingFormula::Ring(1)]);
+
+        assert_eq!(
+            CompoundMembershipProof::construct(
+                &mut rng,
+                &pedersen_generator,
+                &commitment_to_key,
+                &formula,
+                &[Some(2), None],
+                &rings,
+            )
+            .unwrap_err(),
+            MembershipError::FormulaNotSatisfied.into()
+        );
+
+        // claiming membership in ring 1 anyway yields an invalid proof
+        let proof = CompoundMembershipProof::construct(
+            &mut rng,
+            &pedersen_generator,
+            &commitment_to_key,
+            &formula,
+            &[Some(2), Some(2)],
+            &rings,
+        )
+        .unwrap();
+        assert!(proof
+            .verify(
+                &mut rng,
+                &pedersen_generator,
+                &commitment_to_key.commitment(),
+                &formula,
+                &rings,
+            )
+            .is_err());
+    }
+}
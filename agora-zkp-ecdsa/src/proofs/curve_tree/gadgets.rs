@@ -0,0 +1,109 @@
+use crate::arithmetic::{AffinePoint, FieldElement, Point, Scalar};
+use crate::curve::{Curve, Cycle};
+use crate::error::CircuitError;
+use crate::proofs::r1cs::gadgets::{
+    allocate_point, fixed_base_mul, FixedBaseTable, POINT_GATES, SCALAR_MUL_GATES,
+};
+use crate::proofs::r1cs::{ConstraintSystem, LinearCombination, Variable};
+
+/// Number of multiplication gates used by [`rerandomize`].
+pub const RERANDOMIZATION_GATES: usize = POINT_GATES + 1 + SCALAR_MUL_GATES;
+
+/// A point is permissible if `y + 1` is a square but `1 - y` is not.
+///
+/// At most one of `P` and `-P` is permissible, so the x-coordinate of a
+/// permissible point determines the point itself.
+pub fn is_permissible<C: Curve>(point: &AffinePoint<C>) -> bool {
+    let y = *point.y();
+    (y + FieldElement::ONE).is_square() && !(FieldElement::ONE - y).is_square()
+}
+
+/// Selects an entry of the vector commitment the proof is bound to and
+/// returns it as a linear combination.
+///
+/// Uses two gates per entry: one multiplying the entry with a bit and one
+/// checking that the bit is either zero or one.
+pub fn select<C: Curve, CS: ConstraintSystem<C>>(
+    cs: &mut CS,
+    len: usize,
+    position: Option<usize>,
+) -> Result<LinearCombination<C>, CircuitError> {
+    let mut selected = LinearCombination::zero();
+    let mut bit_sum = LinearCombination::zero();
+    let mut bits = Vec::with_capacity(len);
+    for i in 0..len {
+        let bit = position.map(|p| if p == i { Scalar::ONE } else { Scalar::ZERO });
+        let (_, bit, product) = cs.input_multiplier(i, bit)?;
+        selected = selected + product;
+        bit_sum = bit_sum + bit;
+        bits.push(bit);
+    }
+    for bit in bits {
+        let (_, _, product) = cs.multiply(bit.into(), LinearCombination::from(Scalar::ONE) - bit);
+        cs.constrain(product.into());
+    }
+    cs.constrain(bit_sum - Scalar::ONE);
+    Ok(selected)
+}
+
+/// Proves that `rerandomized = child + r * H` where `child` is a permissible
+/// point and returns the variable of its x-coordinate.
+///
+/// The scalar multiplication is computed by [`fixed_base_mul`] starting from
+/// the child.
+pub fn rerandomize<P, Ch, CS>(
+    cs: &mut CS,
+    table: &FixedBaseTable<P, Ch>,
+    child: Option<(AffinePoint<Ch>, Scalar<Ch>)>,
+    rerandomized: &Point<Ch>,
+) -> Result<Variable, CircuitError>
+where
+    P: Cycle<Ch>,
+    Ch: Curve,
+    CS: ConstraintSystem<P>,
+{
+    let (x, y) = allocate_point(cs, child.as_ref().map(|(point, _)| point))?;
+
+    // y + 1 = w^2
+    let root = match &child {
+        Some((point, _)) => Some(
+            (*point.y() + FieldElement::ONE)
+                .sqrt()
+                .ok_or(CircuitError::NotPermissible)?
+                .to_cycle_scalar::<P>(),
+        ),
+        None => None,
+    };
+    let (w_left, w_right, w_sq) = cs.allocate_multiplier(root.map(|w| (w, w)))?;
+    cs.constrain(LinearCombination::from(w_left) - w_right);
+    cs.constrain(LinearCombination::from(w_sq) - y - Scalar::<P>::ONE);
+
+    fixed_base_mul(cs, table, (x, y), child, rerandomized)?;
+    Ok(x)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::curve::{Secp256k1, Tom256k1};
+    use rand::rngs::StdRng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn permissible_points() {
+        let mut rng = StdRng::from_seed([5; 32]);
+        let mut permissible = 0;
+        for _ in 0..20 {
+            let point = (Point::<Secp256k1>::GENERATOR * Scalar::random(&mut rng)).to_affine();
+            let negated = -point;
+            assert!(!(is_permissible(&point) && is_permissible(&negated)));
+            permissible += is_permissible(&point) as usize;
+
+            let point = (Point::<Tom256k1>::GENERATOR * Scalar::random(&mut rng)).to_affine();
+            let negated = -point;
+            assert!(!(is_permissible(&point) && is_permissible(&negated)));
+            permissible += is_permissible(&point) as usize;
+        }
+        assert!(permissible > 0);
+    }
+}
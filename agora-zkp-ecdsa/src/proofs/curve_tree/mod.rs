@@ -0,0 +1,665 @@
+//! Curve Trees accumulator for very large rings.
+//!
+//! Leaves (x-coordinates of public keys as scalars of the cycle curve) are
+//! committed in groups of `branching` by Pedersen vector commitments on the
+//! cycle curve. The x-coordinates of these nodes are scalars of the base
+//! curve, so they are committed by the next layer on the base curve, and so
+//! on, alternating between the two curves until a single root remains.
+//!
+//! A membership proof rerandomizes every node on the path from the leaf to
+//! the root and proves for each layer in an arithmetic circuit that the
+//! rerandomized child is a rerandomization of an entry of the rerandomized
+//! parent. The size of the proof and the work of the verifier only depend on
+//! the depth of the tree.
+mod gadgets;
+
+pub use gadgets::RERANDOMIZATION_GATES;
+
+use gadgets::is_permissible;
+
+use super::r1cs::gadgets::FixedBaseTable;
+use super::r1cs::{ConstraintSystem, Prover, R1csGenerators, R1csProof, Verifier};
+use crate::arithmetic::multimult::MultiMult;
+use crate::arithmetic::{Point, Scalar};
+use crate::curve::{Curve, Cycle};
+use crate::error::{Error, MembershipError, ParameterError};
+use crate::hasher::Transcript;
+use crate::pedersen::{PedersenCommitment, PedersenGenerator};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use rand_core::{CryptoRng, RngCore};
+
+const GENERATOR_CAPACITY: usize = 1024;
+
+/// Largest branching factor for which a layer's circuit fits the
+/// generators.
+pub const MAX_BRANCHING: usize = (GENERATOR_CAPACITY - RERANDOMIZATION_GATES) / 2;
+
+/// Public parameters of curve trees with a given branching factor.
+///
+/// All generators are derived deterministically, so the parameters can be
+/// regenerated by anyone.
+pub struct CurveTreeParameters<C: Curve, CC: Curve> {
+    branching: usize,
+    base_generators: R1csGenerators<C>,
+    cycle_generators: R1csGenerators<CC>,
+    // rerandomization of cycle curve nodes in circuits over the base curve
+    base_table: FixedBaseTable<C, CC>,
+    // rerandomization of base curve nodes in circuits over the cycle curve
+    cycle_table: FixedBaseTable<CC, C>,
+}
+
+impl<C: Cycle<CC>, CC: Cycle<C>> CurveTreeParameters<C, CC> {
+    pub fn new(branching: usize) -> Result<Self, ParameterError> {
+        if !(2..=MAX_BRANCHING).contains(&branching) {
+            return Err(ParameterError::InvalidBranchingFactor);
+        }
+        let base_generators = R1csGenerators::new(b"curve-tree/base", GENERATOR_CAPACITY);
+        let cycle_generators = R1csGenerators::new(b"curve-tree/cycle", GENERATOR_CAPACITY);
+        let base_table = FixedBaseTable::new(
+            b"curve-tree/base-offset",
+            &cycle_generators.blinding().generator(),
+        );
+        let cycle_table = FixedBaseTable::new(
+            b"curve-tree/cycle-offset",
+            &base_generators.blinding().generator(),
+        );
+        Ok(Self {
+            branching,
+            base_generators,
+            cycle_generators,
+            base_table,
+            cycle_table,
+        })
+    }
+
+    pub fn branching(&self) -> usize {
+        self.branching
+    }
+
+    /// Pedersen generator that has to blind the commitment to the leaf.
+    pub fn pedersen_generator(&self) -> PedersenGenerator<CC> {
+        self.cycle_generators.blinding()
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Node<C: Curve> {
+    point: Point<C>,
+    blinding: Scalar<C>,
+}
+
+impl<C: Curve> Node<C> {
+    /// Commits to `values` and blinds the commitment until its point is
+    /// permissible.
+    fn commit(generators: &R1csGenerators<C>, values: &[Scalar<C>]) -> Self {
+        let mut multimult = MultiMult::new();
+        for (value, generator) in values.iter().zip(generators.g_vec()) {
+            if *value != Scalar::ZERO {
+                multimult.insert(*generator, *value);
+            }
+        }
+        let mut point = multimult.evaluate();
+        let mut blinding = Scalar::ZERO;
+        while !is_permissible(&point.to_affine()) {
+            point += &generators.blinding().generator();
+            blinding += Scalar::ONE;
+        }
+        Self { point, blinding }
+    }
+
+    fn x<CC: Cycle<C>>(&self) -> Scalar<CC> {
+        self.point.to_affine().x().to_cycle_scalar()
+    }
+}
+
+/// Returns the children of the `index`-th node, padded by repeating the
+/// first child.
+fn children<T: Clone>(values: &[T], index: usize, branching: usize) -> Vec<T> {
+    let start = index * branching;
+    let end = values.len().min(start + branching);
+    let mut chunk = values[start..end].to_vec();
+    chunk.resize(branching, values[start].clone());
+    chunk
+}
+
+fn commit_layer<C: Curve>(
+    generators: &R1csGenerators<C>,
+    values: &[Scalar<C>],
+    branching: usize,
+) -> Vec<Node<C>> {
+    (0..values.len().div_ceil(branching))
+        .map(|i| Node::commit(generators, &children(values, i, branching)))
+        .collect()
+}
+
+/// The root of a curve tree together with the depth of the tree.
+///
+/// Layers are counted from the leaves, so the root of a tree with odd
+/// depth lies on the cycle curve.
+#[derive(Clone, Copy, Debug, BorshDeserialize, BorshSerialize)]
+pub enum CurveTreeRoot<C: Curve, CC: Curve> {
+    Base { depth: u32, point: Point<C> },
+    Cycle { depth: u32, point: Point<CC> },
+}
+
+impl<C: Curve, CC: Curve> CurveTreeRoot<C, CC> {
+    pub fn depth(&self) -> usize {
+        match self {
+            Self::Base { depth, .. } | Self::Cycle { depth, .. } => *depth as usize,
+        }
+    }
+}
+
+pub struct CurveTree<C: Curve, CC: Curve> {
+    branching: usize,
+    leaves: Vec<Scalar<CC>>,
+    // layers 1, 3, 5, ...
+    cycle_layers: Vec<Vec<Node<CC>>>,
+    // layers 2, 4, 6, ...
+    base_layers: Vec<Vec<Node<C>>>,
+}
+
+impl<C: Cycle<CC>, CC: Cycle<C>> CurveTree<C, CC> {
+    pub fn new(
+        parameters: &CurveTreeParameters<C, CC>,
+        leaves: &[Scalar<CC>],
+    ) -> Result<Self, ParameterError> {
+        if leaves.is_empty() {
+            return Err(ParameterError::EmptyRing);
+        }
+        let branching = parameters.branching;
+        let mut cycle_layers = Vec::new();
+        let mut base_layers = Vec::new();
+        let mut cycle_values = leaves.to_vec();
+        loop {
+            let layer = commit_layer(&parameters.cycle_generators, &cycle_values, branching);
+            let base_values: Vec<Scalar<C>> = layer.iter().map(Node::x).collect();
+            cycle_layers.push(layer);
+            if base_values.len() == 1 {
+                break;
+            }
+
+            let layer = commit_layer(&parameters.base_generators, &base_values, branching);
+            cycle_values = layer.iter().map(Node::x).collect();
+            base_layers.push(layer);
+            if cycle_values.len() == 1 {
+                break;
+            }
+        }
+
+        Ok(Self {
+            branching,
+            leaves: leaves.to_vec(),
+            cycle_layers,
+            base_layers,
+        })
+    }
+
+    pub fn depth(&self) -> usize {
+        self.cycle_layers.len() + self.base_layers.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    pub fn leaves(&self) -> &[Scalar<CC>] {
+        &self.leaves
+    }
+
+    pub fn root(&self) -> CurveTreeRoot<C, CC> {
+        let depth = self.depth() as u32;
+        if depth % 2 == 1 {
+            CurveTreeRoot::Cycle {
+                depth,
+                point: self.cycle_layers[self.cycle_layers.len() - 1][0].point,
+            }
+        } else {
+            CurveTreeRoot::Base {
+                depth,
+                point: self.base_layers[self.base_layers.len() - 1][0].point,
+            }
+        }
+    }
+
+    fn cycle_node(&self, layer: usize, leaf_index: usize) -> &Node<CC> {
+        &self.cycle_layers[(layer - 1) / 2][leaf_index / self.branching.pow(layer as u32)]
+    }
+
+    fn base_node(&self, layer: usize, leaf_index: usize) -> &Node<C> {
+        &self.base_layers[layer / 2 - 1][leaf_index / self.branching.pow(layer as u32)]
+    }
+
+    /// Values committed by the node of `layer` on the path of the leaf.
+    fn cycle_children(&self, layer: usize, leaf_index: usize) -> Vec<Scalar<CC>> {
+        let node_index = leaf_index / self.branching.pow(layer as u32);
+        if layer == 1 {
+            children(&self.leaves, node_index, self.branching)
+        } else {
+            children(
+                &self.base_layers[(layer - 1) / 2 - 1],
+                node_index,
+                self.branching,
+            )
+            .iter()
+            .map(Node::x)
+            .collect()
+        }
+    }
+
+    fn base_children(&self, layer: usize, leaf_index: usize) -> Vec<Scalar<C>> {
+        let node_index = leaf_index / self.branching.pow(layer as u32);
+        children(
+            &self.cycle_layers[layer / 2 - 1],
+            node_index,
+            self.branching,
+        )
+        .iter()
+        .map(Node::x)
+        .collect()
+    }
+}
+
+/// Membership proof of a committed leaf in a curve tree.
+#[derive(Clone, Debug, BorshDeserialize, BorshSerialize)]
+pub struct CurveTreeProof<C: Curve, CC: Curve> {
+    // rerandomized nodes on the path below the root, layers 1, 3, 5, ...
+    cycle_nodes: Vec<Point<CC>>,
+    // layers 2, 4, 6, ...
+    base_nodes: Vec<Point<C>>,
+    cycle_proofs: Vec<R1csProof<CC>>,
+    base_proofs: Vec<R1csProof<C>>,
+}
+
+fn layer_transcript(layer: usize) -> Transcript {
+    let mut transcript = Transcript::new(b"curve-tree-layer");
+    transcript.append_u64(layer as u64);
+    transcript
+}
+
+/// The rerandomized node of a layer with the values it commits to.
+struct Parent<P: Curve> {
+    point: Point<P>,
+    values: Vec<Scalar<P>>,
+    blinding: Scalar<P>,
+}
+
+/// The node of the previous layer with its rerandomization.
+struct Child<Ch: Curve> {
+    node: Point<Ch>,
+    randomness: Scalar<Ch>,
+    rerandomized: Point<Ch>,
+}
+
+fn prove_leaf_layer<P: Curve, R: CryptoRng + RngCore>(
+    rng: &mut R,
+    generators: &R1csGenerators<P>,
+    parent: Parent<P>,
+    position: usize,
+    leaf: &PedersenCommitment<P>,
+    value: Scalar<P>,
+) -> Result<R1csProof<P>, Error> {
+    let mut prover = Prover::new(generators, layer_transcript(1));
+    let len = parent.values.len();
+    prover.vector_input(parent.point, parent.values, parent.blinding);
+    let selected = gadgets::select(&mut prover, len, Some(position))?;
+    let (_, leaf) = prover.commit(value, leaf.randomness());
+    prover.constrain(selected - leaf);
+    prover.prove(rng)
+}
+
+fn verify_leaf_layer<P: Curve, R: CryptoRng + RngCore>(
+    rng: &mut R,
+    generators: &R1csGenerators<P>,
+    branching: usize,
+    parent: &Point<P>,
+    leaf: &Point<P>,
+    proof: &R1csProof<P>,
+) -> Result<(), Error> {
+    let mut verifier = Verifier::new(generators, layer_transcript(1));
+    verifier.vector_input(*parent, branching);
+    let selected = gadgets::select(&mut verifier, branching, None)?;
+    let leaf = verifier.commit(*leaf);
+    verifier.constrain(selected - leaf);
+    verifier.verify(rng, proof)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn prove_node_layer<P: Cycle<Ch>, Ch: Curve, R: CryptoRng + RngCore>(
+    rng: &mut R,
+    generators: &R1csGenerators<P>,
+    table: &FixedBaseTable<P, Ch>,
+    layer: usize,
+    parent: Parent<P>,
+    position: usize,
+    child: &Child<Ch>,
+) -> Result<R1csProof<P>, Error> {
+    let mut transcript = layer_transcript(layer);
+    transcript.append_point(&child.rerandomized);
+    let mut prover = Prover::new(generators, transcript);
+    let len = parent.values.len();
+    prover.vector_input(parent.point, parent.values, parent.blinding);
+    let selected = gadgets::select(&mut prover, len, Some(position))?;
+    let x = gadgets::rerandomize(
+        &mut prover,
+        table,
+        Some((child.node.to_affine(), child.randomness)),
+        &child.rerandomized,
+    )?;
+    prover.constrain(selected - x);
+    prover.prove(rng)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn verify_node_layer<P: Cycle<Ch>, Ch: Curve, R: CryptoRng + RngCore>(
+    rng: &mut R,
+    generators: &R1csGenerators<P>,
+    table: &FixedBaseTable<P, Ch>,
+    branching: usize,
+    layer: usize,
+    parent: &Point<P>,
+    child: &Point<Ch>,
+    proof: &R1csProof<P>,
+) -> Result<(), Error> {
+    let mut transcript = layer_transcript(layer);
+    transcript.append_point(child);
+    let mut verifier = Verifier::new(generators, transcript);
+    verifier.vector_input(*parent, branching);
+    let selected = gadgets::select(&mut verifier, branching, None)?;
+    let x = gadgets::rerandomize(&mut verifier, table, None, child)?;
+    verifier.constrain(selected - x);
+    verifier.verify(rng, proof)
+}
+
+impl<C: Cycle<CC>, CC: Cycle<C>> CurveTreeProof<C, CC> {
+    /// Proves that the leaf at `index` is committed in `commitment`, which
+    /// has to be blinded by the parameters' Pedersen generator.
+    pub fn construct<R: CryptoRng + RngCore>(
+        rng: &mut R,
+        parameters: &CurveTreeParameters<C, CC>,
+        tree: &CurveTree<C, CC>,
+        index: usize,
+        commitment: &PedersenCommitment<CC>,
+    ) -> Result<Self, Error> {
+        if index >= tree.len() {
+            return Err(ParameterError::InvalidIndex.into());
+        }
+        let depth = tree.depth();
+        let branching = parameters.branching;
+        let position = |layer: usize| (index / branching.pow(layer as u32 - 1)) % branching;
+
+        // rerandomize the nodes on the path, except for the root
+        let mut cycle_path = Vec::new();
+        let mut base_path = Vec::new();
+        for layer in 1..=depth {
+            if layer % 2 == 1 {
+                let node = tree.cycle_node(layer, index);
+                let randomness = if layer < depth {
+                    Scalar::random(rng)
+                } else {
+                    Scalar::ZERO
+                };
+                let rerandomized =
+                    node.point + parameters.cycle_generators.blinding().generator() * randomness;
+                cycle_path.push((
+                    node,
+                    Child {
+                        node: node.point,
+                        randomness,
+                        rerandomized,
+                    },
+                ));
+            } else {
+                let node = tree.base_node(layer, index);
+                let randomness = if layer < depth {
+                    Scalar::random(rng)
+                } else {
+                    Scalar::ZERO
+                };
+                let rerandomized =
+                    node.point + parameters.base_generators.blinding().generator() * randomness;
+                base_path.push((
+                    node,
+                    Child {
+                        node: node.point,
+                        randomness,
+                        rerandomized,
+                    },
+                ));
+            }
+        }
+
+        let mut cycle_proofs = Vec::new();
+        let mut base_proofs = Vec::new();
+        for layer in 1..=depth {
+            if layer % 2 == 1 {
+                let (node, rerandomization) = &cycle_path[(layer - 1) / 2];
+                let parent = Parent {
+                    point: rerandomization.rerandomized,
+                    values: tree.cycle_children(layer, index),
+                    blinding: node.blinding + rerandomization.randomness,
+                };
+                let proof = if layer == 1 {
+                    let value = tree.leaves[index];
+                    prove_leaf_layer(
+                        rng,
+                        &parameters.cycle_generators,
+                        parent,
+                        position(layer),
+                        commitment,
+                        value,
+                    )?
+                } else {
+                    let child = &base_path[(layer - 1) / 2 - 1].1;
+                    prove_node_layer(
+                        rng,
+                        &parameters.cycle_generators,
+                        &parameters.cycle_table,
+                        layer,
+                        parent,
+                        position(layer),
+                        child,
+                    )?
+                };
+                cycle_proofs.push(proof);
+            } else {
+                let (node, rerandomization) = &base_path[layer / 2 - 1];
+                let parent = Parent {
+                    point: rerandomization.rerandomized,
+                    values: tree.base_children(layer, index),
+                    blinding: node.blinding + rerandomization.randomness,
+                };
+                let child = &cycle_path[layer / 2 - 1].1;
+                let proof = prove_node_layer(
+                    rng,
+                    &parameters.base_generators,
+                    &parameters.base_table,
+                    layer,
+                    parent,
+                    position(layer),
+                    child,
+                )?;
+                base_proofs.push(proof);
+            }
+        }
+
+        Ok(Self {
+            cycle_nodes: cycle_path
+                .iter()
+                .map(|(_, child)| child.rerandomized)
+                .take(depth / 2)
+                .collect(),
+            base_nodes: base_path
+                .iter()
+                .map(|(_, child)| child.rerandomized)
+                .take((depth - 1) / 2)
+                .collect(),
+            cycle_proofs,
+            base_proofs,
+        })
+    }
+
+    /// Verifies that `commitment` commits to a leaf of the tree with the
+    /// given root.
+    pub fn verify<R: CryptoRng + RngCore>(
+        &self,
+        rng: &mut R,
+        parameters: &CurveTreeParameters<C, CC>,
+        root: &CurveTreeRoot<C, CC>,
+        commitment: &Point<CC>,
+    ) -> Result<(), Error> {
+        let depth = root.depth();
+        let valid_root = match root {
+            CurveTreeRoot::Cycle { .. } => depth % 2 == 1,
+            CurveTreeRoot::Base { .. } => depth > 0 && depth.is_multiple_of(2),
+        };
+        if !valid_root {
+            return Err(MembershipError::InvalidRoot.into());
+        }
+        if self.cycle_proofs.len() != depth.div_ceil(2)
+            || self.base_proofs.len() != depth / 2
+            || self.cycle_nodes.len() != depth / 2
+            || self.base_nodes.len() != (depth - 1) / 2
+        {
+            return Err(MembershipError::InvalidProofLengths.into());
+        }
+
+        let branching = parameters.branching;
+        for layer in 1..=depth {
+            if layer % 2 == 1 {
+                let parent = match root {
+                    CurveTreeRoot::Cycle { point, .. } if layer == depth => point,
+                    _ => &self.cycle_nodes[(layer - 1) / 2],
+                };
+                let proof = &self.cycle_proofs[(layer - 1) / 2];
+                if layer == 1 {
+                    verify_leaf_layer(
+                        rng,
+                        &parameters.cycle_generators,
+                        branching,
+                        parent,
+                        commitment,
+                        proof,
+                    )?;
+                } else {
+                    let child = &self.base_nodes[(layer - 1) / 2 - 1];
+                    verify_node_layer(
+                        rng,
+                        &parameters.cycle_generators,
+                        &parameters.cycle_table,
+                        branching,
+                        layer,
+                        parent,
+                        child,
+                        proof,
+                    )?;
+                }
+            } else {
+                let parent = match root {
+                    CurveTreeRoot::Base { point, .. } if layer == depth => point,
+                    _ => &self.base_nodes[layer / 2 - 1],
+                };
+                let proof = &self.base_proofs[layer / 2 - 1];
+                let child = &self.cycle_nodes[layer / 2 - 1];
+                verify_node_layer(
+                    rng,
+                    &parameters.base_generators,
+                    &parameters.base_table,
+                    branching,
+                    layer,
+                    parent,
+                    child,
+                    proof,
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::curve::{Secp256k1, Tom256k1};
+    use rand::rngs::StdRng;
+    use rand_core::SeedableRng;
+
+    type TomScalar = Scalar<Tom256k1>;
+
+    fn random_leaves(rng: &mut StdRng, len: usize) -> Vec<TomScalar> {
+        (0..len).map(|_| TomScalar::random(rng)).collect()
+    }
+
+    #[test]
+    fn tree_structure() {
+        let mut rng = StdRng::from_seed([21; 32]);
+        let parameters = CurveTreeParameters::<Secp256k1, Tom256k1>::new(3).unwrap();
+        assert!(CurveTreeParameters::<Secp256k1, Tom256k1>::new(1).is_err());
+        assert!(CurveTreeParameters::<Secp256k1, Tom256k1>::new(MAX_BRANCHING + 1).is_err());
+        assert!(CurveTree::new(&parameters, &[]).is_err());
+
+        let tree = CurveTree::new(&parameters, &random_leaves(&mut rng, 3)).unwrap();
+        assert_eq!(tree.depth(), 1);
+        assert!(matches!(tree.root(), CurveTreeRoot::Cycle { depth: 1, .. }));
+        let tree = CurveTree::new(&parameters, &random_leaves(&mut rng, 7)).unwrap();
+        assert_eq!(tree.depth(), 2);
+        assert!(matches!(tree.root(), CurveTreeRoot::Base { depth: 2, .. }));
+        assert_eq!(tree.base_children(2, 6).len(), 3);
+        let tree = CurveTree::new(&parameters, &random_leaves(&mut rng, 10)).unwrap();
+        assert_eq!(tree.depth(), 3);
+    }
+
+    #[test]
+    fn valid_curve_tree_proof() {
+        let mut rng = StdRng::from_seed([22; 32]);
+        let parameters = CurveTreeParameters::<Secp256k1, Tom256k1>::new(2).unwrap();
+        let leaves = random_leaves(&mut rng, 5);
+        let tree = CurveTree::new(&parameters, &leaves).unwrap();
+        assert_eq!(tree.depth(), 3);
+        let root = tree.root();
+
+        let index = 3;
+        let commitment = parameters
+            .pedersen_generator()
+            .commit(&mut rng, leaves[index]);
+        let proof =
+            CurveTreeProof::construct(&mut rng, &parameters, &tree, index, &commitment).unwrap();
+        assert!(proof
+            .verify(&mut rng, &parameters, &root, &commitment.commitment())
+            .is_ok());
+
+        // commitment to a value that is not a leaf
+        let other = parameters
+            .pedersen_generator()
+            .commit(&mut rng, TomScalar::ONE);
+        assert!(proof
+            .verify(&mut rng, &parameters, &root, &other.commitment())
+            .is_err());
+
+        // tree with a different root
+        let other_tree = CurveTree::new(&parameters, &random_leaves(&mut rng, 5)).unwrap();
+        assert!(proof
+            .verify(
+                &mut rng,
+                &parameters,
+                &other_tree.root(),
+                &commitment.commitment()
+            )
+            .is_err());
+
+        // the proof does not reveal the leaf, but a forged one is rejected
+        let commitment = parameters
+            .pedersen_generator()
+            .commit(&mut rng, TomScalar::ONE);
+        let proof =
+            CurveTreeProof::construct(&mut rng, &parameters, &tree, index, &commitment).unwrap();
+        assert!(proof
+            .verify(&mut rng, &parameters, &root, &commitment.commitment())
+            .is_err());
+    }
+}
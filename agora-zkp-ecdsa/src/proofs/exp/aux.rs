@@ -2,10 +2,12 @@ use super::*;
 use crate::arithmetic::multimult::{MultiMult, Relation};
 use crate::arithmetic::{AffinePoint, Point, Scalar};
 use crate::curve::{Curve, Cycle};
-use crate::hasher::PointHasher;
+use crate::error::ExpError;
+use crate::hasher::{PointHasher, TranscriptRng};
 use crate::pedersen::{PedersenCommitment, PedersenCycle};
-use crate::proofs::SEC_PARAM;
+use crate::proofs::{Progress, SEC_PARAM};
 
+use rand_core::{CryptoRng, RngCore};
 #[cfg(feature = "parallel")]
 use rayon::prelude::*;
 
@@ -20,44 +22,80 @@ pub struct AuxiliaryCommitments<C: Curve, CC: Cycle<C>> {
     pub ty: PedersenCommitment<CC>,
 }
 
-pub fn commitments_vector<C: Curve, CC: Cycle<C>>(
+// Number of rounds generated between two progress reports.
+#[cfg(feature = "parallel")]
+fn chunk_size() -> usize {
+    rayon::current_num_threads()
+}
+#[cfg(not(feature = "parallel"))]
+fn chunk_size() -> usize {
+    1
+}
+
+// Commitments and responses are reported as separate steps of each round.
+fn report(progress: &mut dyn Progress, done: usize) -> Result<(), ExpError> {
+    if progress.report(done, 2 * SEC_PARAM) {
+        Ok(())
+    } else {
+        Err(ExpError::Cancelled)
+    }
+}
+
+// NOTE every round draws from its own generator forked in round order, so
+// the proof only depends on `rng`, not on how the rounds are scheduled
+pub fn commitments_vector<R: RngCore + CryptoRng, C: Curve, CC: Cycle<C>>(
+    rng: &mut R,
     base_gen: Point<C>,
     pedersen: &PedersenCycle<C, CC>,
-) -> Vec<AuxiliaryCommitments<C, CC>> {
-    #[cfg(feature = "parallel")]
-    let iter = (0..SEC_PARAM).into_par_iter();
-    #[cfg(not(feature = "parallel"))]
-    let iter = 0..SEC_PARAM;
-
-    iter.map(|_| {
-        let mut rng = rand_core::OsRng;
-        // exponent (XXX what if this is zero?)
-        let alpha = Scalar::random(&mut rng);
-        // random nonce
-        let r = Scalar::random(&mut rng);
-        // T = g^alpha
-        let t: AffinePoint<C> = (base_gen * alpha).into();
-        // A = g^alpha = h^r (essentially a commitment in the base curve)
-        let a = t + (pedersen.base().generator() * r).to_affine();
-
-        // commitment to Tx
-        let tx = pedersen.cycle().commit(&mut rng, t.x().to_cycle_scalar());
-        // commitment to Ty
-        let ty = pedersen.cycle().commit(&mut rng, t.y().to_cycle_scalar());
-
-        AuxiliaryCommitments {
-            alpha,
-            r,
-            a,
-            t,
-            tx,
-            ty,
-        }
-    })
-    .collect()
+    progress: &mut dyn Progress,
+) -> Result<Vec<AuxiliaryCommitments<C, CC>>, ExpError> {
+    let mut aux_vec = Vec::with_capacity(SEC_PARAM);
+    while aux_vec.len() < SEC_PARAM {
+        let chunk = chunk_size().min(SEC_PARAM - aux_vec.len());
+        let rngs = (0..chunk)
+            .map(|_| TranscriptRng::fork(rng))
+            .collect::<Vec<_>>();
+        #[cfg(feature = "parallel")]
+        let iter = rngs.into_par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let iter = rngs.into_iter();
+
+        let commitments = iter
+            .map(|mut rng| {
+                // exponent (XXX what if this is zero?)
+                let alpha = Scalar::random(&mut rng);
+                // random nonce
+                let r = Scalar::random(&mut rng);
+                // T = g^alpha
+                let t: AffinePoint<C> = (base_gen * alpha).into();
+                // A = g^alpha = h^r (essentially a commitment in the base curve)
+                let a = t + (pedersen.base().generator() * r).to_affine();
+
+                // commitment to Tx
+                let tx = pedersen.cycle().commit(&mut rng, t.x().to_cycle_scalar());
+                // commitment to Ty
+                let ty = pedersen.cycle().commit(&mut rng, t.y().to_cycle_scalar());
+
+                AuxiliaryCommitments {
+                    alpha,
+                    r,
+                    a,
+                    t,
+                    tx,
+                    ty,
+                }
+            })
+            .collect::<Vec<_>>();
+        aux_vec.extend(commitments);
+        report(progress, aux_vec.len())?;
+    }
+
+    Ok(aux_vec)
 }
 
-pub fn proofs<C: Curve, CC: Cycle<C>>(
+#[allow(clippy::too_many_arguments)]
+pub fn proofs<R: RngCore + CryptoRng, C: Curve, CC: Cycle<C>>(
+    rng: &mut R,
     aux_vec: Vec<AuxiliaryCommitments<C, CC>>,
     point_hasher: PointHasher,
     base_gen: Point<C>,
@@ -65,82 +103,110 @@ pub fn proofs<C: Curve, CC: Cycle<C>>(
     secrets: &ExpSecrets<C>,
     commitments: &ExpCommitments<C, CC>,
     q_point: Option<Point<C>>,
-) -> Result<Vec<SingleExpProof<C, CC>>, String> {
+    progress: &mut dyn Progress,
+) -> Result<Vec<SingleExpProof<C, CC>>, ExpError> {
     let challenge = padded_bits(point_hasher.finalize(), SEC_PARAM);
     debug_assert_eq!(aux_vec.len(), challenge.len());
 
-    #[cfg(feature = "parallel")]
-    let aux_vec_iter = aux_vec.into_par_iter();
-    #[cfg(feature = "parallel")]
-    let challenge_iter = challenge.into_par_iter();
-    #[cfg(not(feature = "parallel"))]
-    let aux_vec_iter = aux_vec.into_iter();
-    #[cfg(not(feature = "parallel"))]
-    let challenge_iter = challenge.into_iter();
-
-    let proofs = aux_vec_iter
-        .zip(challenge_iter)
-        .flat_map(|(aux, c_bit)| {
-            if c_bit {
-                let tx_r = aux.tx.randomness();
-                let ty_r = aux.ty.randomness();
-                Ok(SingleExpProof {
-                    a: aux.a,
-                    tx_p: aux.tx.commitment(),
-                    ty_p: aux.ty.commitment(),
-                    variant: ExpProofVariant::Odd {
-                        alpha: aux.alpha,
-                        r: aux.r,
-                        tx_r,
-                        ty_r,
-                    },
-                })
-            } else {
-                let z = aux.alpha - secrets.exp;
-                let mut t1 = base_gen * z;
-                if let Some(pt) = q_point.as_ref() {
-                    t1 += pt;
-                }
+    let mut rounds = aux_vec.into_iter().zip(challenge);
+    let mut proofs = Vec::with_capacity(SEC_PARAM);
+    loop {
+        let chunk = rounds
+            .by_ref()
+            .take(chunk_size())
+            .map(|(aux, c_bit)| (aux, c_bit, TranscriptRng::fork(rng)))
+            .collect::<Vec<_>>();
+        if chunk.is_empty() {
+            break;
+        }
+        #[cfg(feature = "parallel")]
+        let iter = chunk.into_par_iter();
+        #[cfg(not(feature = "parallel"))]
+        let iter = chunk.into_iter();
+
+        let responses = iter
+            .map(|(aux, c_bit, mut rng)| {
+                single_proof(
+                    &mut rng,
+                    aux,
+                    c_bit,
+                    base_gen,
+                    pedersen,
+                    secrets,
+                    commitments,
+                    q_point,
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        proofs.extend(responses);
+        report(progress, SEC_PARAM + proofs.len())?;
+    }
 
-                if t1.is_identity() {
-                    return Err("intermediate value is identity".to_owned());
-                }
-                let mut rng = rand_core::OsRng;
+    Ok(proofs)
+}
 
-                // Generate point add proof
-                let add_secret = PointAddSecrets::new(t1.into(), secrets.point, aux.t);
-                let add_commitments = add_secret.commit_p_only(
-                    &mut rng,
-                    &pedersen.cycle(),
-                    commitments.px.clone(),
-                    commitments.py.clone(),
-                    aux.tx.clone(),
-                    aux.ty.clone(),
-                );
-                let add_proof = PointAddProof::construct(
-                    &mut rng,
-                    &pedersen.cycle(),
-                    &add_commitments,
-                    &add_secret,
-                );
-
-                Ok(SingleExpProof {
-                    a: aux.a,
-                    tx_p: aux.tx.commitment(),
-                    ty_p: aux.ty.commitment(),
-                    variant: ExpProofVariant::Even {
-                        z,
-                        r: aux.r - commitments.exp.randomness(),
-                        t1_x: add_commitments.px.randomness(),
-                        t1_y: add_commitments.py.randomness(),
-                        add_proof,
-                    },
-                })
-            }
+#[allow(clippy::too_many_arguments)]
+fn single_proof<R: RngCore + CryptoRng, C: Curve, CC: Cycle<C>>(
+    rng: &mut R,
+    aux: AuxiliaryCommitments<C, CC>,
+    c_bit: bool,
+    base_gen: Point<C>,
+    pedersen: &PedersenCycle<C, CC>,
+    secrets: &ExpSecrets<C>,
+    commitments: &ExpCommitments<C, CC>,
+    q_point: Option<Point<C>>,
+) -> Result<SingleExpProof<C, CC>, ExpError> {
+    if c_bit {
+        let tx_r = aux.tx.randomness();
+        let ty_r = aux.ty.randomness();
+        Ok(SingleExpProof {
+            a: aux.a,
+            tx_p: aux.tx.commitment(),
+            ty_p: aux.ty.commitment(),
+            variant: ExpProofVariant::Odd {
+                alpha: aux.alpha,
+                r: aux.r,
+                tx_r,
+                ty_r,
+            },
         })
-        .collect::<Vec<_>>();
+    } else {
+        let z = aux.alpha - secrets.exp;
+        let mut t1 = base_gen * z;
+        if let Some(pt) = q_point.as_ref() {
+            t1 += pt;
+        }
 
-    Ok(proofs)
+        if t1.is_identity() {
+            return Err(ExpError::IdentityIntermediate);
+        }
+
+        // Generate point add proof
+        let add_secret = PointAddSecrets::new(t1.into(), secrets.point, aux.t);
+        let add_commitments = add_secret.commit_p_only(
+            rng,
+            &pedersen.cycle(),
+            commitments.px.clone(),
+            commitments.py.clone(),
+            aux.tx.clone(),
+            aux.ty.clone(),
+        );
+        let add_proof =
+            PointAddProof::construct(rng, &pedersen.cycle(), &add_commitments, &add_secret);
+
+        Ok(SingleExpProof {
+            a: aux.a,
+            tx_p: aux.tx.commitment(),
+            ty_p: aux.ty.commitment(),
+            variant: ExpProofVariant::Even {
+                z,
+                r: aux.r - commitments.exp.randomness(),
+                t1_x: add_commitments.px.randomness(),
+                t1_y: add_commitments.py.randomness(),
+                add_proof,
+            },
+        })
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -153,7 +219,7 @@ pub fn aggregate_proofs<C: Curve, CC: Cycle<C>>(
     point_hasher: PointHasher,
     tom_multimult: &Arc<Mutex<MultiMult<CC>>>,
     base_multimult: &Arc<Mutex<MultiMult<C>>>,
-) -> Result<(), String> {
+) -> Result<(), ExpError> {
     let challenge = padded_bits(point_hasher.finalize(), proofs.len());
 
     #[cfg(feature = "parallel")]
@@ -177,7 +243,7 @@ pub fn aggregate_proofs<C: Curve, CC: Cycle<C>>(
                     ty_r,
                 } => {
                     if !c_bit {
-                        return Err("challenge hash mismatch".to_owned());
+                        return Err(ExpError::ChallengeMismatch);
                     }
 
                     let t = base_gen.scalar_mul(alpha);
@@ -191,7 +257,7 @@ pub fn aggregate_proofs<C: Curve, CC: Cycle<C>>(
 
                     let coord_t: AffinePoint<C> = t.into();
                     if coord_t.is_identity() {
-                        return Err("intermediate value is identity".to_owned());
+                        return Err(ExpError::IdentityIntermediate);
                     }
 
                     let sx = coord_t.x().to_cycle_scalar::<CC>();
@@ -220,7 +286,7 @@ pub fn aggregate_proofs<C: Curve, CC: Cycle<C>>(
                     t1_y,
                 } => {
                     if c_bit {
-                        return Err("challenge hash mismatch".to_owned());
+                        return Err(ExpError::ChallengeMismatch);
                     }
 
                     let mut t = base_gen.scalar_mul(z);
@@ -239,7 +305,7 @@ pub fn aggregate_proofs<C: Curve, CC: Cycle<C>>(
 
                     let coord_t: AffinePoint<C> = t.into();
                     if coord_t.is_identity() {
-                        return Err("intermediate value is identity".to_owned());
+                        return Err(ExpError::IdentityIntermediate);
                     }
 
                     let sx = coord_t.x().to_cycle_scalar::<CC>();
@@ -3,9 +3,11 @@ mod aux;
 use crate::arithmetic::multimult::MultiMult;
 use crate::arithmetic::{AffinePoint, Point, Scalar};
 use crate::curve::{Curve, Cycle};
+use crate::error::ExpError;
 use crate::hasher::PointHasher;
 use crate::pedersen::*;
 use crate::proofs::point_add::{PointAddCommitmentPoints, PointAddProof, PointAddSecrets};
+use crate::proofs::Progress;
 
 use bigint::{Encoding, U256};
 use borsh::{BorshDeserialize, BorshSerialize};
@@ -65,6 +67,14 @@ impl<C: Curve> ExpSecrets<C> {
         Self { exp, point }
     }
 
+    pub fn exp(&self) -> Scalar<C> {
+        self.exp
+    }
+
+    pub fn point(&self) -> AffinePoint<C> {
+        self.point
+    }
+
     pub fn commit<R, CC>(
         &self,
         rng: &mut R,
@@ -100,6 +110,18 @@ impl<C: Curve, CC: Cycle<C>> ExpCommitmentPoints<C, CC> {
     pub fn new(exp: Point<C>, px: Point<CC>, py: Point<CC>) -> Self {
         Self { exp, px, py }
     }
+
+    pub fn exp(&self) -> &Point<C> {
+        &self.exp
+    }
+
+    pub fn px(&self) -> &Point<CC> {
+        &self.px
+    }
+
+    pub fn py(&self) -> &Point<CC> {
+        &self.py
+    }
 }
 
 #[derive(BorshDeserialize, BorshSerialize)]
@@ -110,14 +132,43 @@ pub struct ExpProof<C: Curve, CC: Cycle<C>> {
 impl<CC: Cycle<C>, C: Curve> ExpProof<C, CC> {
     const HASH_ID: &'static [u8] = b"exp-proof";
 
-    pub fn construct(
+    /// Number of repetitions the proof consists of.
+    pub fn rounds(&self) -> usize {
+        self.proofs.len()
+    }
+
+    pub fn construct<R: RngCore + CryptoRng>(
+        rng: &mut R,
         base_gen: Point<C>,
         pedersen: &PedersenCycle<C, CC>,
         secrets: &ExpSecrets<C>,
         commitments: &ExpCommitments<C, CC>,
         q_point: Option<Point<C>>,
-    ) -> Result<Self, String> {
-        let aux_vec = aux::commitments_vector(base_gen, pedersen);
+    ) -> Result<Self, ExpError> {
+        Self::construct_with_progress(
+            rng,
+            base_gen,
+            pedersen,
+            secrets,
+            commitments,
+            q_point,
+            &mut |_, _| true,
+        )
+    }
+
+    /// Constructs the proof, reporting each generated commitment and response
+    /// to `progress`. Fails with [`ExpError::Cancelled`] if `progress` asks
+    /// to stop.
+    pub fn construct_with_progress<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        base_gen: Point<C>,
+        pedersen: &PedersenCycle<C, CC>,
+        secrets: &ExpSecrets<C>,
+        commitments: &ExpCommitments<C, CC>,
+        q_point: Option<Point<C>>,
+        progress: &mut dyn Progress,
+    ) -> Result<Self, ExpError> {
+        let aux_vec = aux::commitments_vector(rng, base_gen, pedersen, progress)?;
 
         let mut point_hasher = PointHasher::new(Self::HASH_ID);
         point_hasher.insert_point(&commitments.px.commitment());
@@ -129,6 +180,7 @@ impl<CC: Cycle<C>, C: Curve> ExpProof<C, CC> {
         }
 
         let proofs = aux::proofs(
+            rng,
             aux_vec,
             point_hasher,
             base_gen,
@@ -136,6 +188,7 @@ impl<CC: Cycle<C>, C: Curve> ExpProof<C, CC> {
             secrets,
             commitments,
             q_point,
+            progress,
         )?;
 
         Ok(Self { proofs })
@@ -147,9 +200,9 @@ impl<CC: Cycle<C>, C: Curve> ExpProof<C, CC> {
         pedersen: &PedersenCycle<C, CC>,
         commitments: &ExpCommitmentPoints<C, CC>,
         q_point: Option<Point<C>>,
-    ) -> Result<(), String> {
+    ) -> Result<(), ExpError> {
         if super::SEC_PARAM > self.proofs.len() {
-            return Err("security level not achieved".to_owned());
+            return Err(ExpError::SecurityLevelNotAchieved);
         }
 
         let mut tom_multimult = MultiMult::<CC>::new();
@@ -198,7 +251,7 @@ impl<CC: Cycle<C>, C: Curve> ExpProof<C, CC> {
             .evaluate();
 
         if !(tom_res.is_identity() && base_res.is_identity()) {
-            return Err("proof is invalid".to_owned());
+            return Err(ExpError::VerificationFailed);
         }
         Ok(())
     }
@@ -265,7 +318,8 @@ mod test {
         let commitments = secrets.commit(&mut rng, &pedersen);
 
         let exp_proof =
-            ExpProof::construct(base_gen, &pedersen, &secrets, &commitments, None).unwrap();
+            ExpProof::construct(&mut rng, base_gen, &pedersen, &secrets, &commitments, None)
+                .unwrap();
 
         assert!(exp_proof
             .verify(base_gen, &pedersen, &commitments.into_commitments(), None)
@@ -285,9 +339,15 @@ mod test {
         let secrets = ExpSecrets::new(exponent, result.into());
         let commitments = secrets.commit(&mut rng, &pedersen);
 
-        let exp_proof =
-            ExpProof::construct(base_gen, &pedersen, &secrets, &commitments, Some(q_point))
-                .unwrap();
+        let exp_proof = ExpProof::construct(
+            &mut rng,
+            base_gen,
+            &pedersen,
+            &secrets,
+            &commitments,
+            Some(q_point),
+        )
+        .unwrap();
 
         assert!(exp_proof
             .verify(
@@ -312,7 +372,8 @@ mod test {
         let commitments = secrets.commit(&mut rng, &pedersen);
 
         let exp_proof =
-            ExpProof::construct(base_gen, &pedersen, &secrets, &commitments, None).unwrap();
+            ExpProof::construct(&mut rng, base_gen, &pedersen, &secrets, &commitments, None)
+                .unwrap();
 
         assert!(exp_proof
             .verify(base_gen, &pedersen, &commitments.into_commitments(), None)
@@ -1,7 +1,8 @@
-use super::utils::pad_ring_to_2n;
+use super::utils::{optimal_base, pad_ring_to_nm, Optimize};
 use crate::arithmetic::multimult::*;
 use crate::arithmetic::{Modular, Point, Scalar};
 use crate::curve::Curve;
+use crate::error::{Error, MembershipError, ParameterError};
 use crate::hasher::PointHasher;
 use crate::pedersen::*;
 use crate::U256;
@@ -10,6 +11,16 @@ use agora_interpolate::Polynomial;
 use borsh::{BorshDeserialize, BorshSerialize};
 use rand_core::{CryptoRng, RngCore};
 
+/// Groth-Kohlweiss one-out-of-many proof generalised to an n-ary
+/// decomposition of the index (Bootle et al.).
+///
+/// The index is written as `m` digits in base `n` and for every digit `j` and
+/// value `k > 0` the proof commits to the bit `l_jk = (index_j == k)`. The bit
+/// of `k = 0` is implied, as the bits of a digit sum up to one. For `n > 2`
+/// it is checked to be a bit as well, its commitments are appended to `cb`
+/// and `zb`. In the binary case this is exactly the original proof.
+///
+/// The base is not stored explicitly, it is implied by the length of `cl`.
 #[derive(Debug, Clone, BorshDeserialize, BorshSerialize)]
 pub struct MembershipProof<C: Curve> {
     cl: Vec<Point<C>>,
@@ -22,9 +33,86 @@ pub struct MembershipProof<C: Curve> {
     zd: Scalar<C>,
 }
 
+/// First move of the membership protocol: the commitments sent to the
+/// verifier along with the secret values needed to answer a challenge.
+///
+/// Answering two challenges with the same commitments reveals the index.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct MembershipCommitments<C: Curve> {
+    base: usize,
+    cl: Vec<Point<C>>,
+    ca: Vec<Point<C>>,
+    cb: Vec<Point<C>>,
+    cd: Vec<Point<C>>,
+    l_vec: Vec<Scalar<C>>,
+    a_vec: Vec<Scalar<C>>,
+    r_vec: Vec<Scalar<C>>,
+    s_vec: Vec<Scalar<C>>,
+    t_vec: Vec<Scalar<C>>,
+    rho_vec: Vec<Scalar<C>>,
+}
+
+impl<C: Curve> MembershipCommitments<C> {
+    pub fn challenge(&self) -> Scalar<C> {
+        MembershipProof::hash_commitments(&self.ca, &self.cb, &self.cd, &self.cl)
+    }
+
+    pub fn insert_into(&self, hasher: &mut PointHasher) {
+        MembershipProof::insert_commitments(hasher, &self.ca, &self.cb, &self.cd, &self.cl);
+    }
+
+    /// Second move of the protocol: answers the given challenge.
+    pub fn respond(
+        self,
+        commitment_to_key: &PedersenCommitment<C>,
+        challenge: Scalar<C>,
+    ) -> MembershipProof<C> {
+        let m = self.cd.len();
+        let width = self.base - 1;
+        let mut fi = Vec::<Scalar<C>>::with_capacity(m * width);
+        let mut za = Vec::<Scalar<C>>::with_capacity(m * width);
+        let mut zb = Vec::<Scalar<C>>::with_capacity(self.t_vec.len());
+        let mut zd =
+            commitment_to_key.randomness() * challenge.pow(&Scalar::new(U256::from_u64(m as u64)));
+
+        for i in 0..m * width {
+            fi.push(self.l_vec[i] * challenge + self.a_vec[i]);
+            za.push(self.r_vec[i] * challenge + self.s_vec[i]);
+            zb.push(self.r_vec[i] * (challenge - fi[i]) + self.t_vec[i]);
+        }
+
+        if self.base > 2 {
+            for j in 0..m {
+                let row = j * width..(j + 1) * width;
+                // opening of the implied commitment to the bit of k = 0,
+                // note that `challenge - f_0` is just the sum of the row
+                let r_0 = -sum(&self.r_vec[row.clone()]);
+                zb.push(r_0 * sum(&fi[row]) + self.t_vec[m * width + j]);
+            }
+        }
+
+        for (i, rho) in self.rho_vec.iter().enumerate() {
+            zd -= *rho * challenge.pow(&Scalar::new(U256::from_u64(i as u64)));
+        }
+
+        MembershipProof {
+            cl: self.cl,
+            ca: self.ca,
+            cb: self.cb,
+            cd: self.cd,
+            fi,
+            za,
+            zb,
+            zd,
+        }
+    }
+}
+
 impl<C: Curve> MembershipProof<C> {
     const HASH_ID: &'static [u8] = b"membership-proof";
 
+    /// Generates the proof with the base that is expected to be the fastest
+    /// to prove for the length of the ring.
     pub fn construct<R: RngCore + CryptoRng>(
         rng: &mut R,
         pedersen_generator: &PedersenGenerator<C>,
@@ -34,118 +122,243 @@ impl<C: Curve> MembershipProof<C> {
         // 160 bit, so it should fit unless C::PRIME_MODULUS is less than
         // 2^160)
         ring: &[Scalar<C>],
-    ) -> Result<Self, String> {
+    ) -> Result<Self, Error> {
+        let base = optimal_base(ring.len(), Optimize::ProverTime);
+        Self::construct_with_base(
+            rng,
+            pedersen_generator,
+            commitment_to_key,
+            index,
+            ring,
+            base,
+        )
+    }
+
+    pub fn construct_with_base<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        pedersen_generator: &PedersenGenerator<C>,
+        commitment_to_key: &PedersenCommitment<C>,
+        index: usize,
+        ring: &[Scalar<C>],
+        base: usize,
+    ) -> Result<Self, Error> {
+        let commitments = Self::commit(rng, pedersen_generator, index, ring, base)?;
+        let challenge = commitments.challenge();
+        Ok(commitments.respond(commitment_to_key, challenge))
+    }
+
+    /// First move of the protocol, committing to the digits of `index`.
+    pub fn commit<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        pedersen_generator: &PedersenGenerator<C>,
+        index: usize,
+        ring: &[Scalar<C>],
+        base: usize,
+    ) -> Result<MembershipCommitments<C>, Error> {
         if index >= ring.len() {
-            return Err("invalid index".to_string());
+            return Err(ParameterError::InvalidIndex.into());
         }
 
         let mut ring = ring.to_vec();
-        let n = pad_ring_to_2n(&mut ring)?; // log2(ring.len())
+        let m = pad_ring_to_nm(&mut ring, base)?; // log_base(ring.len())
+        let width = base - 1;
 
         // random scalar storages
-        let mut a_vec = Vec::<Scalar<C>>::with_capacity(n);
-        let mut l_vec = Vec::<Scalar<C>>::with_capacity(n);
-        let mut r_vec = Vec::<Scalar<C>>::with_capacity(n);
-        let mut s_vec = Vec::<Scalar<C>>::with_capacity(n);
-        let mut t_vec = Vec::<Scalar<C>>::with_capacity(n);
-        let mut rho_vec = Vec::<Scalar<C>>::with_capacity(n);
+        let mut a_vec = Vec::<Scalar<C>>::with_capacity(m * width);
+        let mut l_vec = Vec::<Scalar<C>>::with_capacity(m * width);
+        let mut r_vec = Vec::<Scalar<C>>::with_capacity(m * width);
+        let mut s_vec = Vec::<Scalar<C>>::with_capacity(m * width);
+        let mut t_vec = Vec::<Scalar<C>>::with_capacity(m * base);
+        let mut rho_vec = Vec::<Scalar<C>>::with_capacity(m);
 
         // commitment storages
-        let mut ca = Vec::<Point<C>>::with_capacity(n);
-        let mut cb = Vec::<Point<C>>::with_capacity(n);
-        let mut cd = Vec::<Point<C>>::with_capacity(n);
-        let mut cl = Vec::<Point<C>>::with_capacity(n);
-
-        let mut omegas = Vec::<Scalar<C>>::with_capacity(n);
+        let mut ca = Vec::<Point<C>>::with_capacity(m * width);
+        let mut cb = Vec::<Point<C>>::with_capacity(m * base);
+        let mut cd = Vec::<Point<C>>::with_capacity(m);
+        let mut cl = Vec::<Point<C>>::with_capacity(m * width);
 
         let mut tmp_index = index;
-        for i in 0..n {
-            l_vec.push(Scalar::new(U256::from_u64((tmp_index % 2) as u64)));
-            tmp_index /= 2;
-            a_vec.push(Scalar::random(rng));
-            r_vec.push(Scalar::random(rng));
-            s_vec.push(Scalar::random(rng));
-            t_vec.push(Scalar::random(rng));
-            rho_vec.push(Scalar::random(rng));
-
-            cl.push(
-                pedersen_generator
-                    .commit_with_randomness(l_vec[i], r_vec[i])
-                    .commitment(),
-            );
-            ca.push(
-                pedersen_generator
-                    .commit_with_randomness(a_vec[i], s_vec[i])
-                    .commitment(),
-            );
-            cb.push(
-                pedersen_generator
-                    .commit_with_randomness(l_vec[i] * a_vec[i], t_vec[i])
-                    .commitment(),
-            );
-
-            omegas.push(Scalar::new(U256::from_u64(i as u64)));
+        for _ in 0..m {
+            let digit = tmp_index % base;
+            tmp_index /= base;
+            for k in 1..base {
+                let l = Scalar::new(U256::from_u64((digit == k) as u64));
+                let a = Scalar::random(rng);
+                let r = Scalar::random(rng);
+                let s = Scalar::random(rng);
+                let t = Scalar::random(rng);
+
+                cl.push(pedersen_generator.commit_with_randomness(l, r).commitment());
+                ca.push(pedersen_generator.commit_with_randomness(a, s).commitment());
+                cb.push(
+                    pedersen_generator
+                        .commit_with_randomness(l * a, t)
+                        .commitment(),
+                );
+
+                l_vec.push(l);
+                a_vec.push(a);
+                r_vec.push(r);
+                s_vec.push(s);
+                t_vec.push(t);
+            }
         }
 
-        let mut poly_vals = Vec::<Scalar<C>>::new();
-        for omega in omegas.iter() {
-            let mut f0j = Vec::<Scalar<C>>::with_capacity(n);
-            let mut f1j = Vec::<Scalar<C>>::with_capacity(n);
-            let mut ratio = Vec::<Scalar<C>>::with_capacity(n);
-
-            let mut product = Scalar::ONE;
-            for j in 0..n {
-                f0j.push(&(Scalar::ONE - l_vec[j]) * omega - a_vec[j]);
-                f1j.push(&l_vec[j] * omega + a_vec[j]);
-                ratio.push(f1j[j] * f0j[j].inverse());
-                product *= f0j[j];
+        if base > 2 {
+            for j in 0..m {
+                let row = j * width..(j + 1) * width;
+                let l_0 = Scalar::ONE - sum(&l_vec[row.clone()]);
+                let a_0 = -sum(&a_vec[row]);
+                let t = Scalar::random(rng);
+                cb.push(
+                    pedersen_generator
+                        .commit_with_randomness(l_0 * a_0, t)
+                        .commitment(),
+                );
+                t_vec.push(t);
             }
+        }
 
-            let mut prod_vec = vec![product];
+        let omegas = (0..m)
+            .map(|i| Scalar::new(U256::from_u64(i as u64)))
+            .collect::<Vec<Scalar<C>>>();
 
-            for r in ratio.iter().take(n) {
-                let old_p_len = prod_vec.len();
-                for j in 0..old_p_len {
-                    prod_vec.push(r * &prod_vec[j]);
-                }
-            }
+        let mut poly_vals = Vec::<Scalar<C>>::with_capacity(m);
+        for omega in omegas.iter() {
+            let f_rows = (0..m)
+                .map(|j| {
+                    let row = j * width..(j + 1) * width;
+                    let f_row = l_vec[row.clone()]
+                        .iter()
+                        .zip(&a_vec[row])
+                        .map(|(l, a)| l * omega + *a)
+                        .collect::<Vec<_>>();
+                    digit_polynomials(*omega, &f_row)
+                })
+                .collect::<Vec<_>>();
+            let prod_vec = expand_products(&f_rows);
 
             let mut poly_val = Scalar::ZERO;
-            for i in 0..ring.len() {
-                poly_val += (ring[index] - ring[i]) * prod_vec[i];
+            for (key, prod) in ring.iter().zip(&prod_vec) {
+                poly_val += (ring[index] - *key) * *prod;
             }
             poly_vals.push(poly_val);
         }
 
-        let poly = Polynomial::interpolate(&omegas, &poly_vals).map_err(|e| e.to_string())?;
-        for (coeff, &rho) in poly.into_coeffs().into_iter().zip(&rho_vec) {
+        let poly = Polynomial::interpolate(&omegas, &poly_vals).map_err(MembershipError::from)?;
+        // NOTE the interpolated polynomial might have less coefficients if
+        // the leading ones are zero
+        let mut coeffs = poly.into_coeffs();
+        coeffs.resize(m, Scalar::ZERO);
+        for coeff in coeffs {
+            let rho = Scalar::random(rng);
             cd.push(
                 pedersen_generator
                     .commit_with_randomness(coeff, rho)
                     .commitment(),
             );
+            rho_vec.push(rho);
         }
 
-        if cl.len() != n || ca.len() != n || cb.len() != n || cd.len() != n {
-            return Err("invalid commitment lengths".to_owned());
+        if cl.len() != m * width || ca.len() != m * width || cd.len() != m {
+            return Err(MembershipError::InvalidCommitmentLengths.into());
         }
 
-        let challenge = Self::hash_commitments(&ca, &cb, &cd, &cl);
-        let mut fi = Vec::<Scalar<C>>::with_capacity(n);
-        let mut za = Vec::<Scalar<C>>::with_capacity(n);
-        let mut zb = Vec::<Scalar<C>>::with_capacity(n);
-        let mut zd =
-            commitment_to_key.randomness() * challenge.pow(&Scalar::new(U256::from_u64(n as u64)));
+        Ok(MembershipCommitments {
+            base,
+            cl,
+            ca,
+            cb,
+            cd,
+            l_vec,
+            a_vec,
+            r_vec,
+            s_vec,
+            t_vec,
+            rho_vec,
+        })
+    }
 
-        for i in 0..n {
-            fi.push(l_vec[i] * challenge + a_vec[i]);
-            za.push(r_vec[i] * challenge + s_vec[i]);
-            zb.push(r_vec[i] * (challenge - fi[i]) + t_vec[i]);
-            zd -= rho_vec[i] * challenge.pow(&Scalar::new(U256::from_u64(i as u64)));
+    /// Generates an accepting transcript for a given challenge without
+    /// knowing an opening of `commitment_to_key` to a ring member.
+    ///
+    /// Used for the branches of an OR composition that the prover cannot
+    /// satisfy. The resulting transcript is distributed identically to an
+    /// honest one.
+    pub fn simulate<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        pedersen_generator: &PedersenGenerator<C>,
+        commitment_to_key: &Point<C>,
+        ring: &[Scalar<C>],
+        challenge: Scalar<C>,
+        base: usize,
+    ) -> Result<Self, Error> {
+        let mut ring = ring.to_vec();
+        let m = pad_ring_to_nm(&mut ring, base)?; // log_base(ring.len())
+        let width = base - 1;
+
+        let mut cl = Vec::<Point<C>>::with_capacity(m * width);
+        let mut ca = Vec::<Point<C>>::with_capacity(m * width);
+        let mut cb = Vec::<Point<C>>::with_capacity(m * base);
+        let mut cd = Vec::<Point<C>>::with_capacity(m);
+        let mut fi = Vec::<Scalar<C>>::with_capacity(m * width);
+        let mut za = Vec::<Scalar<C>>::with_capacity(m * width);
+        let mut zb = Vec::<Scalar<C>>::with_capacity(m * base);
+
+        for i in 0..m * width {
+            // a commitment to a random value is a uniformly random point
+            cl.push(Point::<C>::GENERATOR * Scalar::random(rng));
+            fi.push(Scalar::random(rng));
+            za.push(Scalar::random(rng));
+            zb.push(Scalar::random(rng));
+            ca.push(
+                pedersen_generator
+                    .commit_with_randomness(fi[i], za[i])
+                    .commitment()
+                    - cl[i] * challenge,
+            );
+            cb.push(
+                pedersen_generator
+                    .commit_with_randomness(Scalar::ZERO, zb[i])
+                    .commitment()
+                    - cl[i] * (challenge - fi[i]),
+            );
+        }
+
+        if base > 2 {
+            for j in 0..m {
+                let row = j * width..(j + 1) * width;
+                let cl_0 = cl[row.clone()]
+                    .iter()
+                    .fold(Point::<C>::GENERATOR, |acc, c| acc - *c);
+                let zb_0 = Scalar::random(rng);
+                cb.push(
+                    pedersen_generator
+                        .commit_with_randomness(Scalar::ZERO, zb_0)
+                        .commitment()
+                        - cl_0 * sum(&fi[row]),
+                );
+                zb.push(zb_0);
+            }
         }
 
-        if fi.len() != n || za.len() != n || zb.len() != n {
-            return Err("invalid proof lengths".to_owned());
+        // cd[1..] are random, cd[0] is fixed by the final verification equation
+        let zd = Scalar::random(rng);
+        let total = Self::ring_sum(&ring, &fi, width, challenge);
+        let mut cd_0 = commitment_to_key * challenge.pow(&Scalar::new(U256::from_u64(m as u64)))
+            - pedersen_generator
+                .commit_with_randomness(total, zd)
+                .commitment();
+        cd.push(Point::<C>::IDENTITY);
+        for i in 1..m {
+            let cd_i = Point::<C>::GENERATOR * Scalar::random(rng);
+            cd_0 = cd_0 - cd_i * challenge.pow(&Scalar::new(U256::from_u64(i as u64)));
+            cd.push(cd_i);
+        }
+        if m > 0 {
+            cd[0] = cd_0;
+        } else {
+            cd.clear();
         }
 
         Ok(Self {
@@ -160,25 +373,66 @@ impl<C: Curve> MembershipProof<C> {
         })
     }
 
+    /// Number of digits of the index decomposition, the proof covers rings
+    /// of up to `base^digits` keys.
+    pub fn digits(&self) -> usize {
+        self.cd.len()
+    }
+
+    /// Base of the index decomposition the proof was generated with.
+    pub fn base(&self) -> usize {
+        Self::implied_base(&self.cd, &self.cl)
+    }
+
+    fn implied_base(cd: &[Point<C>], cl: &[Point<C>]) -> usize {
+        match cd.len() {
+            0 => 2,
+            m => cl.len() / m + 1,
+        }
+    }
+
     pub fn verify<R: RngCore + CryptoRng>(
         &self,
         rng: &mut R,
         pedersen_generator: &PedersenGenerator<C>,
         commitment_to_key: &Point<C>,
         ring: &[Scalar<C>],
-    ) -> Result<(), String> {
-        let mut ring = ring.to_vec();
-        let n = pad_ring_to_2n(&mut ring)?; // log2(ring.len())
+    ) -> Result<(), Error> {
+        let challenge = self.challenge();
+        self.verify_with_challenge(rng, pedersen_generator, commitment_to_key, ring, challenge)
+    }
 
-        let challenge = Self::hash_commitments(&self.ca, &self.cb, &self.cd, &self.cl);
+    /// Checks the proof against an externally provided challenge instead of
+    /// the Fiat-Shamir challenge derived from its own commitments.
+    pub fn verify_with_challenge<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pedersen_generator: &PedersenGenerator<C>,
+        commitment_to_key: &Point<C>,
+        ring: &[Scalar<C>],
+        challenge: Scalar<C>,
+    ) -> Result<(), Error> {
+        let base = self.base();
+        let mut ring = ring.to_vec();
+        let m = pad_ring_to_nm(&mut ring, base)?; // log_base(ring.len())
+        let width = base - 1;
+        let checks = if base > 2 { m * base } else { m * width };
+
+        if self.cd.len() != m
+            || [self.cl.len(), self.ca.len(), self.fi.len(), self.za.len()]
+                .iter()
+                .any(|&len| len != m * width)
+            || self.cb.len() != checks
+            || self.zb.len() != checks
+        {
+            return Err(MembershipError::InvalidProofLengths.into());
+        }
 
         let mut multimult = MultiMult::new();
         multimult.add_known(Point::<C>::GENERATOR);
         multimult.add_known(pedersen_generator.generator());
 
-        // NOTE unwraps here are fine because of length checks
-        // at proof construction
-        for i in 0..n {
+        for i in 0..m * width {
             let mut rel_0 = Relation::new();
             let mut rel_1 = Relation::new();
 
@@ -195,19 +449,25 @@ impl<C: Curve> MembershipProof<C> {
             rel_1.drain(rng, &mut multimult);
         }
 
-        let mut total = Scalar::ZERO;
-        for (i, key) in ring.iter().enumerate() {
-            let mut pix = Scalar::ONE;
-            for j in 0..n {
-                if i & (1 << j) == 0 {
-                    pix *= challenge - self.fi[j];
-                } else {
-                    pix *= self.fi[j];
+        if base > 2 {
+            for j in 0..m {
+                // the commitment to the bit of k = 0 is G - sum(cl_jk) and
+                // `challenge - f_0` is the sum of the row
+                let row = j * width..(j + 1) * width;
+                let multiplier = sum(&self.fi[row.clone()]);
+                let mut rel = Relation::new();
+                rel.insert(Point::<C>::GENERATOR, multiplier);
+                for cl in &self.cl[row] {
+                    rel.insert(*cl, -multiplier);
                 }
+                rel.insert(self.cb[m * width + j], Scalar::ONE);
+                rel.insert(pedersen_generator.generator(), -self.zb[m * width + j]);
+                rel.drain(rng, &mut multimult);
             }
-            total += key * &pix;
         }
 
+        let total = Self::ring_sum(&ring, &self.fi, width, challenge);
+
         let mut rel_final = Relation::new();
         for (i, cd_elem) in self.cd.iter().enumerate() {
             rel_final.insert(
@@ -218,7 +478,7 @@ impl<C: Curve> MembershipProof<C> {
 
         rel_final.insert(
             *commitment_to_key,
-            challenge.pow(&Scalar::new(U256::from_u64(n as u64))),
+            challenge.pow(&Scalar::new(U256::from_u64(m as u64))),
         );
         rel_final.insert(Point::<C>::GENERATOR, -total);
         rel_final.insert(pedersen_generator.generator(), -self.zd);
@@ -227,10 +487,37 @@ impl<C: Curve> MembershipProof<C> {
         if multimult.evaluate().is_identity() {
             Ok(())
         } else {
-            Err("failed to verify membership".to_owned())
+            Err(MembershipError::VerificationFailed.into())
         }
     }
 
+    pub fn challenge(&self) -> Scalar<C> {
+        Self::hash_commitments(&self.ca, &self.cb, &self.cd, &self.cl)
+    }
+
+    pub fn insert_into(&self, hasher: &mut PointHasher) {
+        Self::insert_commitments(hasher, &self.ca, &self.cb, &self.cd, &self.cl);
+    }
+
+    // sum of each (padded) ring element weighted by the polynomial
+    // `p_i(x) = prod_j f_{j, i_j}` evaluated at the challenge
+    fn ring_sum(
+        ring: &[Scalar<C>],
+        fi: &[Scalar<C>],
+        width: usize,
+        challenge: Scalar<C>,
+    ) -> Scalar<C> {
+        let f_rows = fi
+            .chunks(width)
+            .map(|f_row| digit_polynomials(challenge, f_row))
+            .collect::<Vec<_>>();
+        let mut total = Scalar::ZERO;
+        for (key, pix) in ring.iter().zip(expand_products(&f_rows)) {
+            total += key * &pix;
+        }
+        total
+    }
+
     fn hash_commitments(
         ca: &[Point<C>],
         cb: &[Point<C>],
@@ -238,23 +525,69 @@ impl<C: Curve> MembershipProof<C> {
         cl: &[Point<C>],
     ) -> Scalar<C> {
         let mut hasher = PointHasher::new(Self::HASH_ID);
-        // NOTE we are assuming that all input slices have the same length
-        // it is important to use this function in both `contruct` and `verify`
-        for i in 0..ca.len() {
-            hasher.insert_point(ca.get(i).unwrap());
-            hasher.insert_point(cb.get(i).unwrap());
-            hasher.insert_point(cd.get(i).unwrap());
-            hasher.insert_point(cl.get(i).unwrap());
+        Self::insert_commitments(&mut hasher, ca, cb, cd, cl);
+        Scalar::<C>::new(hasher.finalize())
+    }
+
+    fn insert_commitments(
+        hasher: &mut PointHasher,
+        ca: &[Point<C>],
+        cb: &[Point<C>],
+        cd: &[Point<C>],
+        cl: &[Point<C>],
+    ) {
+        // NOTE it is important to use this function in both `contruct` and
+        // `verify`. In the binary case all slices have the same length, for
+        // larger bases the shorter ones simply run out earlier. The base and
+        // the lengths are absorbed first, so the interleaving of the points
+        // is unambiguous
+        hasher.insert_bytes(&(Self::implied_base(cd, cl) as u64).to_be_bytes());
+        for commitments in [ca, cb, cd, cl] {
+            hasher.insert_bytes(&(commitments.len() as u64).to_be_bytes());
+        }
+        let max_len = ca.len().max(cb.len()).max(cd.len()).max(cl.len());
+        for i in 0..max_len {
+            for commitments in [ca, cb, cd, cl] {
+                if let Some(point) = commitments.get(i) {
+                    hasher.insert_point(point);
+                }
+            }
         }
+    }
+}
 
-        Scalar::<C>::new(hasher.finalize())
+fn sum<C: Curve>(scalars: &[Scalar<C>]) -> Scalar<C> {
+    scalars.iter().fold(Scalar::ZERO, |acc, s| acc + *s)
+}
+
+// values `f_{j, k}` of a digit for every `k` given the values for `k > 0`,
+// using that they sum up to the challenge
+fn digit_polynomials<C: Curve>(challenge: Scalar<C>, f_row: &[Scalar<C>]) -> Vec<Scalar<C>> {
+    let mut row = Vec::with_capacity(f_row.len() + 1);
+    row.push(challenge - sum(f_row));
+    row.extend_from_slice(f_row);
+    row
+}
+
+// `p_i = prod_j f_{j, i_j}` for every index `i` in the padded ring, where
+// `i_j` is the `j`th digit of `i`
+fn expand_products<C: Curve>(f_rows: &[Vec<Scalar<C>>]) -> Vec<Scalar<C>> {
+    let mut products = vec![Scalar::ONE];
+    for f_row in f_rows {
+        let mut expanded = Vec::with_capacity(products.len() * f_row.len());
+        for f in f_row {
+            expanded.extend(products.iter().map(|p| *p * *f));
+        }
+        products = expanded;
     }
+    products
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
     use crate::curve::Tom256k1;
+    use crate::proofs::utils::MAX_BASE;
     use rand::rngs::StdRng;
     use rand_core::SeedableRng;
 
@@ -358,7 +691,165 @@ mod test {
                 &commitment_to_key.commitment(),
                 &ring,
             ),
-            Err("failed to verify membership".to_string())
+            Err(MembershipError::VerificationFailed.into())
         );
     }
+
+    #[test]
+    fn simulated_membership_proof() {
+        let mut rng = StdRng::from_seed([117; 32]);
+        let pedersen_generator = PedersenGenerator::<Tom256k1>::new(&mut rng);
+        let ring = (0..5)
+            .map(|i| Scalar::<Tom256k1>::new(U256::from_u8(i)))
+            .collect::<Vec<_>>();
+
+        // the committed key is not in the ring
+        let commitment_to_key = pedersen_generator
+            .commit(&mut rng, Scalar::new(U256::from_u8(10)))
+            .commitment();
+        let challenge = Scalar::random(&mut rng);
+        let proof = MembershipProof::simulate(
+            &mut rng,
+            &pedersen_generator,
+            &commitment_to_key,
+            &ring,
+            challenge,
+            3,
+        )
+        .unwrap();
+        assert_eq!(proof.base(), 3);
+
+        assert!(proof
+            .verify_with_challenge(
+                &mut rng,
+                &pedersen_generator,
+                &commitment_to_key,
+                &ring,
+                challenge,
+            )
+            .is_ok());
+        assert!(proof
+            .verify(&mut rng, &pedersen_generator, &commitment_to_key, &ring)
+            .is_err());
+    }
+
+    #[test]
+    fn valid_n_ary_membership_proof() {
+        let mut rng = StdRng::from_seed([27; 32]);
+        let pedersen_generator = PedersenGenerator::<Tom256k1>::new(&mut rng);
+        // 2^4 + 1 keys, padded to 18 with base 3, 64 with base 4 and 64 with base 8
+        let ring = (0..17)
+            .map(|i| Scalar::<Tom256k1>::new(U256::from_u8(i)))
+            .collect::<Vec<_>>();
+
+        for base in [2, 3, 4, 8, 16] {
+            for index in [0, 5, 16] {
+                let commitment_to_key = pedersen_generator.commit(&mut rng, ring[index]);
+                let proof = MembershipProof::construct_with_base(
+                    &mut rng,
+                    &pedersen_generator,
+                    &commitment_to_key,
+                    index,
+                    &ring,
+                    base,
+                )
+                .unwrap();
+                assert_eq!(proof.base(), base);
+                assert!(proof
+                    .verify(
+                        &mut rng,
+                        &pedersen_generator,
+                        &commitment_to_key.commitment(),
+                        &ring,
+                    )
+                    .is_ok());
+
+                // wrong key
+                let other_commitment = pedersen_generator.commit(&mut rng, ring[(index + 1) % 17]);
+                assert!(proof
+                    .verify(
+                        &mut rng,
+                        &pedersen_generator,
+                        &other_commitment.commitment(),
+                        &ring,
+                    )
+                    .is_err());
+            }
+        }
+
+        let commitment_to_key = pedersen_generator.commit(&mut rng, ring[0]);
+        assert_eq!(
+            MembershipProof::construct_with_base(
+                &mut rng,
+                &pedersen_generator,
+                &commitment_to_key,
+                0,
+                &ring,
+                MAX_BASE + 1,
+            )
+            .unwrap_err(),
+            ParameterError::InvalidBase.into()
+        );
+    }
+
+    #[test]
+    fn n_ary_proof_with_wrong_ring() {
+        let mut rng = StdRng::from_seed([27; 32]);
+        let pedersen_generator = PedersenGenerator::<Tom256k1>::new(&mut rng);
+        let ring = (0..10)
+            .map(|i| Scalar::<Tom256k1>::new(U256::from_u8(i)))
+            .collect::<Vec<_>>();
+
+        let commitment_to_key = pedersen_generator.commit(&mut rng, ring[3]);
+        let proof = MembershipProof::construct_with_base(
+            &mut rng,
+            &pedersen_generator,
+            &commitment_to_key,
+            3,
+            &ring,
+            3,
+        )
+        .unwrap();
+
+        // a ring needing a different number of digits is rejected outright
+        let longer = (0..28)
+            .map(|i| Scalar::<Tom256k1>::new(U256::from_u8(i)))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            proof.verify(
+                &mut rng,
+                &pedersen_generator,
+                &commitment_to_key.commitment(),
+                &longer,
+            ),
+            Err(MembershipError::InvalidProofLengths.into())
+        );
+
+        let mut modified = ring.clone();
+        modified[3] = Scalar::new(U256::from_u8(100));
+        assert_eq!(
+            proof.verify(
+                &mut rng,
+                &pedersen_generator,
+                &commitment_to_key.commitment(),
+                &modified,
+            ),
+            Err(MembershipError::VerificationFailed.into())
+        );
+    }
+
+    #[test]
+    fn challenge_binds_proof_shape() {
+        let mut rng = StdRng::from_seed([27; 32]);
+        let points = (0..3)
+            .map(|_| Point::<Tom256k1>::GENERATOR * Scalar::random(&mut rng))
+            .collect::<Vec<_>>();
+        let (p, q, r) = (&points[0..1], &points[1..2], &points[2..3]);
+
+        // the same sequence of points split differently between the vectors
+        let challenge = MembershipProof::hash_commitments(p, q, &[], r);
+        assert_ne!(challenge, MembershipProof::hash_commitments(p, &[], q, r));
+        assert_ne!(challenge, MembershipProof::hash_commitments(&[], p, q, r));
+        assert_eq!(challenge, MembershipProof::hash_commitments(p, q, &[], r));
+    }
 }
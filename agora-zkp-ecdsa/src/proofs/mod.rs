@@ -1,20 +1,39 @@
+mod compound;
+mod curve_tree;
 mod equality;
 mod exp;
 mod membership;
 mod multiplication;
 mod point_add;
+mod progress;
+mod r1cs;
+mod scalar_mul;
 mod utils;
 
-use exp::{ExpCommitmentPoints, ExpCommitments};
-pub use exp::{ExpProof, ExpSecrets};
-use membership::MembershipProof;
+pub use compound::{CompoundMembershipProof, RingFormula};
+pub use curve_tree::{
+    CurveTree, CurveTreeParameters, CurveTreeProof, CurveTreeRoot, MAX_BRANCHING,
+};
+use exp::ExpCommitments;
+pub use exp::{ExpCommitmentPoints, ExpProof, ExpSecrets};
+pub use membership::{MembershipCommitments, MembershipProof};
+pub use progress::Progress;
+pub use scalar_mul::{ScalarMulParameters, ScalarMulProof};
+pub use utils::{optimal_base, pad_ring_to_nm, Optimize, MAX_BASE};
 
-use crate::arithmetic::{Modular, Point, Scalar};
+use crate::arithmetic::{AffinePoint, Modular, Point, Scalar};
 use crate::curve::{Curve, Cycle};
-use crate::hasher::PointHasher;
-use crate::parse::{ParsedProofInput, ParsedRing};
-use crate::pedersen::PedersenCycle;
+use crate::ecdsa;
+use crate::error::{
+    CircuitError, Error, ExpError, MembershipError, ParameterError, ParseError, StatementError,
+};
+use crate::hasher::{PointHasher, Transcript};
+use crate::parse::{ParsedProofInput, ParsedRing, Signature};
+use crate::pedersen::{PedersenCommitment, PedersenCycle};
+use crate::ring::{canonical_ring, RingDigest, RingRegistry};
+use crate::statement::StatementMode;
 
+use bigint::Encoding;
 use borsh::{BorshDeserialize, BorshSerialize};
 use rand_core::{CryptoRng, RngCore};
 
@@ -24,82 +43,488 @@ const SEC_PARAM: usize = 60;
 #[cfg(test)]
 const SEC_PARAM: usize = 10;
 
-const MSG_PREFIX: &str = "\x19Ethereum Signed Message:\n";
-const JOIN_GUILD_MSG: &str = "#zkp/join.guild.xyz/";
+/// The proof of knowledge of the signature a [`ZkAttestProof`] is generated
+/// with.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub enum ProofMode {
+    /// [`ExpProof`] of `SEC_PARAM` repetitions, fast to generate but large.
+    #[default]
+    Exp,
+    /// [`ScalarMulProof`] of logarithmic size, but slower to generate.
+    Circuit,
+}
+
+impl std::fmt::Display for ProofMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Self::Exp => write!(f, "exp"),
+            Self::Circuit => write!(f, "circuit"),
+        }
+    }
+}
+
+impl std::str::FromStr for ProofMode {
+    type Err = ParseError;
+    fn from_str(mode: &str) -> Result<Self, Self::Err> {
+        match mode {
+            "exp" => Ok(Self::Exp),
+            "circuit" => Ok(Self::Circuit),
+            _ => Err(ParseError::InvalidProofMode),
+        }
+    }
+}
+
+#[allow(clippy::large_enum_variant)]
+#[derive(BorshDeserialize, BorshSerialize)]
+pub enum SignatureProof<C: Curve, CC: Cycle<C>> {
+    Exp(ExpProof<C, CC>),
+    Circuit(ScalarMulProof<CC>),
+}
+
+impl<C: Curve, CC: Cycle<C>> SignatureProof<C, CC> {
+    pub fn mode(&self) -> ProofMode {
+        match self {
+            Self::Exp(_) => ProofMode::Exp,
+            Self::Circuit(_) => ProofMode::Circuit,
+        }
+    }
+
+    fn verify<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        r_point: Point<C>,
+        q_point: Point<C>,
+        pedersen: &PedersenCycle<C, CC>,
+        commitments: &ExpCommitmentPoints<C, CC>,
+    ) -> Result<(), Error> {
+        match self {
+            Self::Exp(proof) => proof.verify(r_point, pedersen, commitments, Some(q_point))?,
+            Self::Circuit(proof) => {
+                // NOTE the commitment is only binding with the generator of
+                // the circuit, whose discrete logarithm is unknown
+                let generator = ScalarMulParameters::<CC>::pedersen_generator().generator();
+                if pedersen.cycle().generator() != generator {
+                    return Err(CircuitError::InvalidGenerator.into());
+                }
+                proof.verify(
+                    rng,
+                    ScalarMulParameters::shared(),
+                    &r_point,
+                    &q_point,
+                    &commitments.px,
+                )?
+            }
+        }
+        Ok(())
+    }
+}
 
 /// Zero-knowledge proof consisting of an ECDSA and a Groth-Kohlweiss
 /// membership proof.
 ///
-/// Note, that the ring on which the membership proof is generated is not
-/// explicitly part of this proof because the backend does additional checks on
-/// its integrity before passing it to the veriication function.
+/// The ring itself is not part of the proof, only its [`RingDigest`]. The
+/// membership proof is generated on the canonical order of the ring and its
+/// challenge commits to the digest, so a proof only verifies against the
+/// ring it was generated on, regardless of the order of its keys. Verifiers
+/// keeping a [`RingRegistry`] can look the ring up by digest.
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct ZkAttestProof<C: Curve, CC: Cycle<C>> {
     pub pedersen: PedersenCycle<C, CC>,
     pub msg_hash: Scalar<C>,
     pub r_point: Point<C>,
     pub exp_commitments: ExpCommitmentPoints<C, CC>, // s1, pkx, pxy
-    pub signature_proof: ExpProof<C, CC>,
+    pub signature_proof: SignatureProof<C, CC>,
     pub membership_proof: MembershipProof<CC>,
+    pub ring_digest: RingDigest,
+    pub statement: StatementMode,
     pub guild_id: String,
 }
 
 impl<C: Curve, CC: Cycle<C>> ZkAttestProof<C, CC> {
+    const RING_HASH_ID: &'static [u8] = b"ring-membership-proof";
+    const DETERMINISTIC_ID: &'static [u8] = b"zk-attest-deterministic";
+
+    /// Generates the proof on the canonical order of `ring`, `input.index` is
+    /// the position of the key in `ring` as given.
     pub fn construct<R: RngCore + CryptoRng>(
         rng: &mut R,
         pedersen: PedersenCycle<C, CC>,
         input: ParsedProofInput<C>,
         ring: &ParsedRing<CC>,
-    ) -> Result<Self, String> {
-        let s_inv = input.signature.s.inverse();
-        let r_inv = input.signature.r.inverse();
-        let u1 = s_inv * input.msg_hash;
-        let u2 = s_inv * input.signature.r;
-        let r_point = Point::<C>::GENERATOR.double_mul(&u1, &Point::from(&input.pubkey), &u2);
-        let s1 = r_inv * input.signature.s;
-        let z1 = r_inv * input.msg_hash;
-        let q_point = Point::<C>::GENERATOR * z1;
+    ) -> Result<Self, Error> {
+        Self::construct_with_mode(rng, pedersen, input, ring, ProofMode::Exp)
+    }
 
-        let commitment_to_s1 = pedersen.base().commit_with_generator(rng, s1, r_point);
-        let commitment_to_pk_x = pedersen
-            .cycle()
-            .commit(rng, input.pubkey.x().to_cycle_scalar());
-        let commitment_to_pk_y = pedersen
-            .cycle()
-            .commit(rng, input.pubkey.y().to_cycle_scalar());
-
-        // generate membership proof on pubkey x coordinate
-        let membership_proof = MembershipProof::construct(
+    /// Same as [`construct`](Self::construct), generating the signature
+    /// proof in the given mode.
+    pub fn construct_with_mode<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        pedersen: PedersenCycle<C, CC>,
+        input: ParsedProofInput<C>,
+        ring: &ParsedRing<CC>,
+        mode: ProofMode,
+    ) -> Result<Self, Error> {
+        ecdsa::verify(input.msg_hash, &input.signature, &input.pubkey)?;
+        Self::precompute_with_mode(rng, pedersen, &input.pubkey, input.index, ring, mode)?.finalize(
             rng,
-            &pedersen.cycle(),
-            &commitment_to_pk_x,
-            input.index,
+            input.msg_hash,
+            &input.signature,
+            input.guild_id,
+            input.statement,
+        )
+    }
+
+    /// Same as [`construct_with_mode`](Self::construct_with_mode) with the
+    /// Pedersen parameters and all blinding factors drawn from a transcript
+    /// of `seed` and the inputs, so the same inputs always yield the same
+    /// proof.
+    ///
+    /// Meant for known-answer tests: proofs sharing a seed share their
+    /// parameters, so real proofs need a fresh, secret seed.
+    pub fn construct_deterministic(
+        seed: &[u8],
+        input: ParsedProofInput<C>,
+        ring: &ParsedRing<CC>,
+        mode: ProofMode,
+    ) -> Result<Self, Error> {
+        let mut transcript = Transcript::new(Self::DETERMINISTIC_ID);
+        transcript.append_bytes(seed);
+        transcript.append_scalar(&input.msg_hash);
+        transcript.append_scalar(&input.signature.r);
+        transcript.append_scalar(&input.signature.s);
+        transcript.append_point(&Point::from(&input.pubkey));
+        transcript.append_u64(input.index as u64);
+        transcript.append_bytes(input.guild_id.as_bytes());
+        // NOTE serializing the statement into a vector never fails
+        transcript.append_bytes(&input.statement.try_to_vec().unwrap());
+        transcript.append_bytes(&mode.try_to_vec().unwrap());
+        transcript.append_u64(ring.len() as u64);
+        for key in ring {
+            transcript.append_scalar(key);
+        }
+
+        let mut rng = transcript.into_rng();
+        let pedersen = PedersenCycle::new(&mut rng);
+        Self::construct_with_mode(&mut rng, pedersen, input, ring, mode)
+    }
+
+    /// Offline phase of the prover: commits to the public key and computes
+    /// the commitments of the membership proof over the ring, neither of
+    /// which depends on the signature. The membership proof is completed by
+    /// [`finalize`](ZkAttestPrecomputation::finalize), whose inputs its
+    /// challenge commits to.
+    pub fn precompute<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        pedersen: PedersenCycle<C, CC>,
+        pubkey: &AffinePoint<C>,
+        index: usize,
+        ring: &ParsedRing<CC>,
+    ) -> Result<ZkAttestPrecomputation<C, CC>, Error> {
+        Self::precompute_with_mode(rng, pedersen, pubkey, index, ring, ProofMode::Exp)
+    }
+
+    /// Same as [`precompute`](Self::precompute) for a signature proof in the
+    /// given mode. Circuit proofs replace the Pedersen generator of the cycle
+    /// curve by the one of the circuit.
+    ///
+    /// Fails with [`MembershipError::KeyMismatch`] unless the x coordinate of
+    /// `pubkey` is the key at `index` of `ring`.
+    pub fn precompute_with_mode<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        pedersen: PedersenCycle<C, CC>,
+        pubkey: &AffinePoint<C>,
+        index: usize,
+        ring: &ParsedRing<CC>,
+        mode: ProofMode,
+    ) -> Result<ZkAttestPrecomputation<C, CC>, Error> {
+        let pedersen = match mode {
+            ProofMode::Exp => pedersen,
+            ProofMode::Circuit => pedersen.with_cycle(ScalarMulParameters::pedersen_generator()),
+        };
+        let key = ring.get(index).ok_or(ParameterError::InvalidIndex)?;
+        if pubkey.x().to_cycle_scalar::<CC>() != *key {
+            return Err(MembershipError::KeyMismatch.into());
+        }
+        let canonical = canonical_ring(ring);
+        // NOTE unwrap is fine because the key is part of the ring
+        let index = canonical.iter().position(|k| k == key).unwrap();
+        let ring_digest = RingDigest::of_canonical(&canonical);
+
+        let (commitment_to_pk_x, commitment_to_pk_y) = commit_to_key(rng, &pedersen, pubkey);
+
+        // commit to the membership of the pubkey x coordinate
+        let base = optimal_base(canonical.len(), Optimize::ProverTime);
+        let membership_commitments =
+            MembershipProof::commit(rng, &pedersen.cycle(), index, &canonical, base)?;
+
+        Ok(ZkAttestPrecomputation {
+            pedersen,
+            pubkey: Point::from(pubkey),
+            commitment_to_pk_x,
+            commitment_to_pk_y,
+            membership_commitments,
+            ring_digest,
+            mode,
+        })
+    }
+
+    /// Verifies the proof on `ring`, which may be given in any order.
+    pub fn verify<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        ring: &ParsedRing<CC>,
+    ) -> Result<(), Error> {
+        let canonical = canonical_ring(ring);
+        if RingDigest::of_canonical(&canonical) != self.ring_digest {
+            return Err(MembershipError::RingDigestMismatch.into());
+        }
+        self.verify_on_canonical(rng, &canonical)
+    }
+
+    /// Verifies the proof on the ring registered under its digest.
+    pub fn verify_registered<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        registry: &RingRegistry<CC>,
+    ) -> Result<(), Error> {
+        let ring = registry
+            .get(&self.ring_digest)
+            .ok_or(MembershipError::UnknownRing)?;
+        self.verify_on_canonical(rng, ring.for_base(self.membership_proof.base()))
+    }
+
+    fn verify_on_canonical<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        ring: &[Scalar<CC>],
+    ) -> Result<(), Error> {
+        let q_point =
+            check_statement(self.r_point, self.msg_hash, &self.guild_id, &self.statement)?;
+
+        let mut hasher = Self::ring_hasher(
+            &self.ring_digest,
+            &self.exp_commitments.px,
+            self.signature_proof.mode(),
+            &self.guild_id,
+            &self.statement,
+        );
+        self.membership_proof.insert_into(&mut hasher);
+        self.membership_proof.verify_with_challenge(
+            rng,
+            &self.pedersen.cycle(),
+            &self.exp_commitments.px,
             ring,
+            Scalar::new(hasher.finalize()),
         )?;
 
-        // generate ECDSA proof on signature
-        let exp_secrets = ExpSecrets::new(s1, input.pubkey);
-        let exp_commitments = ExpCommitments {
-            px: commitment_to_pk_x,
-            py: commitment_to_pk_y,
-            exp: commitment_to_s1,
-        };
+        self.signature_proof.verify(
+            rng,
+            self.r_point,
+            q_point,
+            &self.pedersen,
+            &self.exp_commitments,
+        )
+    }
 
-        let signature_proof = ExpProof::construct(
-            r_point,
-            &pedersen,
-            &exp_secrets,
-            &exp_commitments,
-            Some(q_point),
+    // The membership challenge commits to the ring, to the commitment to the
+    // key shared with the signature proof and to everything the signature
+    // proof is checked against, so the membership proof cannot be moved to
+    // another proof.
+    fn ring_hasher(
+        ring_digest: &RingDigest,
+        commitment_to_pk_x: &Point<CC>,
+        mode: ProofMode,
+        guild_id: &str,
+        statement: &StatementMode,
+    ) -> PointHasher {
+        let mut hasher = PointHasher::new(Self::RING_HASH_ID);
+        hasher.insert_bytes(ring_digest.as_bytes());
+        hasher.insert_point(commitment_to_pk_x);
+        // NOTE serializing into a vector never fails
+        hasher.insert_bytes(&mode.try_to_vec().unwrap());
+        hasher.insert_bytes(&(guild_id.len() as u64).to_be_bytes());
+        hasher.insert_bytes(guild_id.as_bytes());
+        hasher.insert_bytes(&statement.try_to_vec().unwrap());
+        hasher
+    }
+}
+
+/// Signature independent part of a [`ZkAttestProof`], generated while the
+/// user is asked to sign the message.
+///
+/// Contains the openings of the commitments to the public key, so it must
+/// never leave the prover.
+///
+/// A precomputation is single-use: proofs finalized from copies of the same
+/// precomputation share the commitment to the key, which links them, and
+/// answer different challenges with the same membership commitments, which
+/// reveals the index of the key. [`finalize`](Self::finalize) consumes it, but a
+/// serialized precomputation can be deserialized again, so whoever stores it
+/// has to track its [`id`](Self::id) and refuse to finalize it twice.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ZkAttestPrecomputation<C: Curve, CC: Cycle<C>> {
+    pedersen: PedersenCycle<C, CC>,
+    pubkey: Point<C>,
+    commitment_to_pk_x: PedersenCommitment<CC>,
+    commitment_to_pk_y: PedersenCommitment<CC>,
+    membership_commitments: MembershipCommitments<CC>,
+    ring_digest: RingDigest,
+    mode: ProofMode,
+}
+
+impl<C: Curve, CC: Cycle<C>> ZkAttestPrecomputation<C, CC> {
+    const ID_HASH_ID: &'static [u8] = b"zk-attest-precomputation";
+
+    pub fn ring_digest(&self) -> RingDigest {
+        self.ring_digest
+    }
+
+    /// Identifier of the precomputation, unique because the commitment to the
+    /// key is blinded afresh by every precomputation.
+    pub fn id(&self) -> [u8; 32] {
+        let mut hasher = PointHasher::new(Self::ID_HASH_ID);
+        hasher.insert_point(&self.commitment_to_pk_x.commitment());
+        hasher.finalize().to_be_bytes()
+    }
+
+    /// Online phase of the prover: generates the ECDSA proof once the
+    /// signature is available and answers the challenge of the membership
+    /// proof.
+    pub fn finalize<R: RngCore + CryptoRng>(
+        self,
+        rng: &mut R,
+        msg_hash: Scalar<C>,
+        signature: &Signature<C>,
+        guild_id: String,
+        statement: StatementMode,
+    ) -> Result<ZkAttestProof<C, CC>, Error> {
+        self.finalize_with_progress(
+            rng,
+            msg_hash,
+            signature,
+            guild_id,
+            statement,
+            &mut |_, _| true,
+        )
+    }
+
+    /// Same as [`finalize`](Self::finalize), reporting the rounds of the
+    /// ECDSA proof to `progress`, which may cancel the proof.
+    ///
+    /// The signature is checked against the public key of the precomputation
+    /// before the proof is generated.
+    pub fn finalize_with_progress<R: RngCore + CryptoRng>(
+        self,
+        rng: &mut R,
+        msg_hash: Scalar<C>,
+        signature: &Signature<C>,
+        guild_id: String,
+        statement_mode: StatementMode,
+        progress: &mut dyn Progress,
+    ) -> Result<ZkAttestProof<C, CC>, Error> {
+        let pubkey = self.pubkey.to_affine();
+        ecdsa::verify(msg_hash, signature, &pubkey)?;
+
+        let mut hasher = ZkAttestProof::<C, CC>::ring_hasher(
+            &self.ring_digest,
+            &self.commitment_to_pk_x.commitment(),
+            self.mode,
+            &guild_id,
+            &statement_mode,
+        );
+        self.membership_commitments.insert_into(&mut hasher);
+        let membership_proof = self
+            .membership_commitments
+            .respond(&self.commitment_to_pk_x, Scalar::new(hasher.finalize()));
+
+        let statement = SignatureStatement::commit_signature(
+            rng,
+            &self.pedersen,
+            msg_hash,
+            signature,
+            &pubkey,
+            (self.commitment_to_pk_x, self.commitment_to_pk_y),
+        );
+        let signature_proof = statement.prove(rng, &self.pedersen, self.mode, progress)?;
+
+        Ok(ZkAttestProof {
+            pedersen: self.pedersen,
+            msg_hash,
+            r_point: statement.r_point,
+            exp_commitments: statement.exp_commitments.into_commitments(),
+            signature_proof,
+            membership_proof,
+            ring_digest: self.ring_digest,
+            statement: statement_mode,
+            guild_id,
+        })
+    }
+}
+
+/// Zero-knowledge proof consisting of an ECDSA proof and a membership proof
+/// over several rings combined by a [`RingFormula`].
+///
+/// Both the rings and the formula are supplied by the verifier, just like the
+/// ring of a [`ZkAttestProof`]. All membership proofs refer to the same
+/// commitment to the public key's x coordinate that the ECDSA proof is
+/// generated on.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ZkAttestCompoundProof<C: Curve, CC: Cycle<C>> {
+    pub pedersen: PedersenCycle<C, CC>,
+    pub msg_hash: Scalar<C>,
+    pub r_point: Point<C>,
+    pub exp_commitments: ExpCommitmentPoints<C, CC>, // s1, pkx, pxy
+    pub signature_proof: SignatureProof<C, CC>,
+    pub membership_proof: CompoundMembershipProof<CC>,
+    pub statement: StatementMode,
+    pub guild_id: String,
+}
+
+impl<C: Curve, CC: Cycle<C>> ZkAttestCompoundProof<C, CC> {
+    /// Generates the proof for `formula`.
+    ///
+    /// The position of the key in each ring is looked up, `input.index` has
+    /// to be its position in the first ring containing it, otherwise the
+    /// input is rejected with [`ParameterError::InvalidIndex`].
+    pub fn construct<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        pedersen: PedersenCycle<C, CC>,
+        input: ParsedProofInput<C>,
+        formula: &RingFormula,
+        rings: &[ParsedRing<CC>],
+    ) -> Result<Self, Error> {
+        ecdsa::verify(input.msg_hash, &input.signature, &input.pubkey)?;
+        let pk_x = input.pubkey.x().to_cycle_scalar::<CC>();
+        let indices = rings
+            .iter()
+            .map(|ring| ring.iter().position(|key| *key == pk_x))
+            .collect::<Vec<_>>();
+        if indices.iter().flatten().next() != Some(&input.index) {
+            return Err(ParameterError::InvalidIndex.into());
+        }
+        let ring_slices = rings.iter().map(Vec::as_slice).collect::<Vec<_>>();
+
+        let statement = SignatureStatement::commit(rng, &pedersen, &input);
+
+        let membership_proof = CompoundMembershipProof::construct(
+            rng,
+            &pedersen.cycle(),
+            &statement.exp_commitments.px,
+            formula,
+            &indices,
+            &ring_slices,
         )?;
 
+        let signature_proof = statement.prove(rng, &pedersen, ProofMode::Exp, &mut |_, _| true)?;
+
         Ok(Self {
             pedersen,
             msg_hash: input.msg_hash,
-            r_point,
-            exp_commitments: exp_commitments.into_commitments(),
+            r_point: statement.r_point,
+            exp_commitments: statement.exp_commitments.into_commitments(),
             signature_proof,
             membership_proof,
+            statement: input.statement,
             guild_id: input.guild_id,
         })
     }
@@ -107,53 +532,274 @@ impl<C: Curve, CC: Cycle<C>> ZkAttestProof<C, CC> {
     pub fn verify<R: RngCore + CryptoRng>(
         &self,
         rng: &mut R,
-        ring: &ParsedRing<CC>,
-    ) -> Result<(), String> {
-        let r_point_affine = self.r_point.to_affine();
-        if r_point_affine.is_identity() {
-            return Err("R is at infinity".to_string());
-        }
-
-        let expected_msg = JOIN_GUILD_MSG.to_string() + &self.guild_id;
-        let expected_msg_len = expected_msg.as_bytes().len().to_string();
-        let preimage = format!("{}{}{}", MSG_PREFIX, expected_msg_len, expected_msg);
-        let hasher = PointHasher::new(preimage.as_bytes());
-        let expected_hash = Scalar::<C>::new(hasher.finalize());
-        if expected_hash != self.msg_hash {
-            return Err("Signed message hash mismatch".to_string());
-        }
-
-        // NOTE weird: a field element Rx is converted
-        // directly into a scalar
-        let r_inv = Scalar::<C>::new(*r_point_affine.x().inner()).inverse();
-        let z1 = r_inv * self.msg_hash;
-        let q_point = Point::<C>::GENERATOR * z1;
+        formula: &RingFormula,
+        rings: &[ParsedRing<CC>],
+    ) -> Result<(), Error> {
+        let q_point =
+            check_statement(self.r_point, self.msg_hash, &self.guild_id, &self.statement)?;
+        let ring_slices = rings.iter().map(Vec::as_slice).collect::<Vec<_>>();
 
         self.membership_proof.verify(
             rng,
             &self.pedersen.cycle(),
             &self.exp_commitments.px,
-            ring,
+            formula,
+            &ring_slices,
         )?;
 
         self.signature_proof.verify(
+            rng,
             self.r_point,
+            q_point,
             &self.pedersen,
             &self.exp_commitments,
-            Some(q_point),
+        )
+    }
+}
+
+/// Zero-knowledge proof consisting of an ECDSA proof and a [`CurveTreeProof`]
+/// of membership in a curve tree.
+///
+/// The verifier supplies the root of the tree. Both proofs refer to the same
+/// commitment to the public key's x coordinate, which is blinded by the
+/// Pedersen generator of the [`CurveTreeParameters`] instead of a random one.
+/// The signature is proven in [`ProofMode::Exp`], because the circuit of a
+/// [`ScalarMulProof`] requires a generator of its own.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ZkAttestTreeProof<C: Curve, CC: Cycle<C>> {
+    pub pedersen: PedersenCycle<C, CC>,
+    pub msg_hash: Scalar<C>,
+    pub r_point: Point<C>,
+    pub exp_commitments: ExpCommitmentPoints<C, CC>, // s1, pkx, pxy
+    pub signature_proof: SignatureProof<C, CC>,
+    pub membership_proof: CurveTreeProof<C, CC>,
+    pub statement: StatementMode,
+    pub guild_id: String,
+}
+
+impl<C: Cycle<CC>, CC: Cycle<C>> ZkAttestTreeProof<C, CC> {
+    /// Generates the proof for the leaf at `input.index` of `tree`, which
+    /// has to be the x coordinate of the public key.
+    pub fn construct<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        pedersen: PedersenCycle<C, CC>,
+        input: ParsedProofInput<C>,
+        parameters: &CurveTreeParameters<C, CC>,
+        tree: &CurveTree<C, CC>,
+    ) -> Result<Self, Error> {
+        ecdsa::verify(input.msg_hash, &input.signature, &input.pubkey)?;
+        let leaf = tree
+            .leaves()
+            .get(input.index)
+            .ok_or(ParameterError::InvalidIndex)?;
+        if *leaf != input.pubkey.x().to_cycle_scalar::<CC>() {
+            return Err(MembershipError::KeyMismatch.into());
+        }
+        let pedersen = pedersen.with_cycle(parameters.pedersen_generator());
+
+        let statement = SignatureStatement::commit(rng, &pedersen, &input);
+
+        let membership_proof = CurveTreeProof::construct(
+            rng,
+            parameters,
+            tree,
+            input.index,
+            &statement.exp_commitments.px,
         )?;
 
-        Ok(())
+        let signature_proof = statement.prove(rng, &pedersen, ProofMode::Exp, &mut |_, _| true)?;
+
+        Ok(Self {
+            pedersen,
+            msg_hash: input.msg_hash,
+            r_point: statement.r_point,
+            exp_commitments: statement.exp_commitments.into_commitments(),
+            signature_proof,
+            membership_proof,
+            statement: input.statement,
+            guild_id: input.guild_id,
+        })
     }
+
+    pub fn verify<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        parameters: &CurveTreeParameters<C, CC>,
+        root: &CurveTreeRoot<C, CC>,
+    ) -> Result<(), Error> {
+        // NOTE the leaf layer opens the commitment with the generator of the
+        // tree, any other generator would let the two proofs disagree on the
+        // committed key
+        if self.pedersen.cycle().generator() != parameters.pedersen_generator().generator() {
+            return Err(CircuitError::InvalidGenerator.into());
+        }
+        let q_point =
+            check_statement(self.r_point, self.msg_hash, &self.guild_id, &self.statement)?;
+
+        self.membership_proof
+            .verify(rng, parameters, root, &self.exp_commitments.px)?;
+
+        self.signature_proof.verify(
+            rng,
+            self.r_point,
+            q_point,
+            &self.pedersen,
+            &self.exp_commitments,
+        )
+    }
+}
+
+// Commitments and secrets of the ECDSA part of the proof, shared by the
+// single, compound and curve tree membership variants.
+struct SignatureStatement<C: Curve, CC: Cycle<C>> {
+    r_point: Point<C>,
+    q_point: Point<C>,
+    exp_secrets: ExpSecrets<C>,
+    exp_commitments: ExpCommitments<C, CC>,
+}
+
+impl<C: Curve, CC: Cycle<C>> SignatureStatement<C, CC> {
+    fn commit<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        pedersen: &PedersenCycle<C, CC>,
+        input: &ParsedProofInput<C>,
+    ) -> Self {
+        let key_commitments = commit_to_key(rng, pedersen, &input.pubkey);
+        Self::commit_signature(
+            rng,
+            pedersen,
+            input.msg_hash,
+            &input.signature,
+            &input.pubkey,
+            key_commitments,
+        )
+    }
+
+    fn commit_signature<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        pedersen: &PedersenCycle<C, CC>,
+        msg_hash: Scalar<C>,
+        signature: &Signature<C>,
+        pubkey: &AffinePoint<C>,
+        (commitment_to_pk_x, commitment_to_pk_y): (PedersenCommitment<CC>, PedersenCommitment<CC>),
+    ) -> Self {
+        let s_inv = signature.s.inverse();
+        let r_inv = signature.r.inverse();
+        let u1 = s_inv * msg_hash;
+        let u2 = s_inv * signature.r;
+        let r_point = Point::<C>::GENERATOR.double_mul(&u1, &Point::from(pubkey), &u2);
+        let s1 = r_inv * signature.s;
+        let z1 = r_inv * msg_hash;
+        let q_point = Point::<C>::GENERATOR * z1;
+
+        let commitment_to_s1 = pedersen.base().commit_with_generator(rng, s1, r_point);
+
+        Self {
+            r_point,
+            q_point,
+            exp_secrets: ExpSecrets::new(s1, *pubkey),
+            exp_commitments: ExpCommitments {
+                px: commitment_to_pk_x,
+                py: commitment_to_pk_y,
+                exp: commitment_to_s1,
+            },
+        }
+    }
+
+    // generate ECDSA proof on signature
+    fn prove<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        pedersen: &PedersenCycle<C, CC>,
+        mode: ProofMode,
+        progress: &mut dyn Progress,
+    ) -> Result<SignatureProof<C, CC>, Error> {
+        match mode {
+            ProofMode::Exp => Ok(SignatureProof::Exp(ExpProof::construct_with_progress(
+                rng,
+                self.r_point,
+                pedersen,
+                &self.exp_secrets,
+                &self.exp_commitments,
+                Some(self.q_point),
+                progress,
+            )?)),
+            ProofMode::Circuit => {
+                // NOTE the circuit is proven at once, it can only be
+                // cancelled before it starts
+                if !progress.report(0, 1) {
+                    return Err(ExpError::Cancelled.into());
+                }
+                let proof = ScalarMulProof::construct(
+                    rng,
+                    ScalarMulParameters::shared(),
+                    &self.r_point,
+                    &self.q_point,
+                    self.exp_secrets.point(),
+                    self.exp_secrets.exp(),
+                    &self.exp_commitments.px,
+                )?;
+                progress.report(1, 1);
+                Ok(SignatureProof::Circuit(proof))
+            }
+        }
+    }
+}
+
+// Commits to the coordinates of the public key on the cycle curve.
+fn commit_to_key<R: RngCore + CryptoRng, C: Curve, CC: Cycle<C>>(
+    rng: &mut R,
+    pedersen: &PedersenCycle<C, CC>,
+    pubkey: &AffinePoint<C>,
+) -> (PedersenCommitment<CC>, PedersenCommitment<CC>) {
+    (
+        pedersen.cycle().commit(rng, pubkey.x().to_cycle_scalar()),
+        pedersen.cycle().commit(rng, pubkey.y().to_cycle_scalar()),
+    )
+}
+
+// Checks the public part of the statement and returns the Q point the ECDSA
+// proof is verified against.
+fn check_statement<C: Curve>(
+    r_point: Point<C>,
+    msg_hash: Scalar<C>,
+    guild_id: &str,
+    mode: &StatementMode,
+) -> Result<Point<C>, StatementError> {
+    let r_point_affine = r_point.to_affine();
+    if r_point_affine.is_identity() {
+        return Err(StatementError::RAtInfinity);
+    }
+
+    if mode.message_hash::<C>(guild_id) != msg_hash {
+        return Err(StatementError::MessageHashMismatch);
+    }
+
+    // NOTE weird: a field element Rx is converted
+    // directly into a scalar
+    let r_inv = Scalar::<C>::new(*r_point_affine.x().inner()).inverse();
+    let z1 = r_inv * msg_hash;
+    Ok(Point::<C>::GENERATOR * z1)
 }
 
 #[cfg(test)]
 mod test {
-    use super::ZkAttestProof;
+    use super::{
+        CurveTree, CurveTreeParameters, ProofMode, RingFormula, ZkAttestCompoundProof,
+        ZkAttestPrecomputation, ZkAttestProof, ZkAttestTreeProof, SEC_PARAM,
+    };
 
+    use crate::arithmetic::{Modular, Point, Scalar};
     use crate::curve::{Secp256k1, Tom256k1};
-    use crate::parse::{parse_ring, ParsedProofInput, ProofInput};
+    use crate::error::{
+        CircuitError, ExpError, MembershipError, ParameterError, SignatureError, StatementError,
+    };
+    use crate::parse::{parse_ring, ParsedProofInput, ProofInput, Signature};
     use crate::pedersen::PedersenCycle;
+    use crate::ring::RingRegistry;
+    use crate::statement::{Eip712Domain, StatementMode};
+
+    use borsh::{BorshDeserialize, BorshSerialize};
 
     use rand::rngs::StdRng;
     use rand_core::SeedableRng;
@@ -182,13 +828,15 @@ mod test {
 
         let proof_input = ProofInput {
             msg_hash,
-            pubkey,
+            pubkey: Some(pubkey),
             signature,
             index,
             guild_id: "almafa".to_string(),
+            domain: None,
+            normalize_s: false,
         };
 
-        let parsed_input: ParsedProofInput<Secp256k1> = proof_input.try_into().unwrap();
+        let parsed_input: ParsedProofInput<Secp256k1> = proof_input.clone().try_into().unwrap();
         let parsed_ring = parse_ring(ring).unwrap();
 
         let zkattest_proof = ZkAttestProof::<Secp256k1, Tom256k1>::construct(
@@ -199,5 +847,468 @@ mod test {
         )
         .unwrap();
         assert!(zkattest_proof.verify(&mut rng, &parsed_ring).is_ok());
+
+        // the membership proof can be generated before the signature is known
+        let parsed_input: ParsedProofInput<Secp256k1> = proof_input.try_into().unwrap();
+        let pedersen_cycle = PedersenCycle::new(&mut rng);
+        let precomputation = ZkAttestProof::<Secp256k1, Tom256k1>::precompute(
+            &mut rng,
+            pedersen_cycle,
+            &parsed_input.pubkey,
+            index,
+            &parsed_ring,
+        )
+        .unwrap();
+        let serialized = precomputation.try_to_vec().unwrap();
+        let precomputation =
+            ZkAttestPrecomputation::<Secp256k1, Tom256k1>::try_from_slice(&serialized).unwrap();
+        // copies of a precomputation share its id, fresh ones do not
+        let pedersen_cycle = PedersenCycle::new(&mut rng);
+        let other = ZkAttestProof::<Secp256k1, Tom256k1>::precompute(
+            &mut rng,
+            pedersen_cycle.clone(),
+            &parsed_input.pubkey,
+            index,
+            &parsed_ring,
+        )
+        .unwrap();
+        assert_eq!(
+            precomputation.id(),
+            ZkAttestPrecomputation::<Secp256k1, Tom256k1>::try_from_slice(&serialized)
+                .unwrap()
+                .id()
+        );
+        assert_ne!(precomputation.id(), other.id());
+        // the key has to be at the index
+        assert_eq!(
+            ZkAttestProof::<Secp256k1, Tom256k1>::precompute(
+                &mut rng,
+                pedersen_cycle,
+                &parsed_input.pubkey,
+                index + 1,
+                &parsed_ring,
+            )
+            .err(),
+            Some(MembershipError::KeyMismatch.into())
+        );
+        let mut reports = Vec::new();
+        let finalized = precomputation
+            .finalize_with_progress(
+                &mut rng,
+                parsed_input.msg_hash,
+                &parsed_input.signature,
+                parsed_input.guild_id.clone(),
+                StatementMode::PersonalSign,
+                &mut |done, total| {
+                    reports.push((done, total));
+                    true
+                },
+            )
+            .unwrap();
+        assert!(finalized.verify(&mut rng, &parsed_ring).is_ok());
+        assert_eq!(reports.last(), Some(&(2 * SEC_PARAM, 2 * SEC_PARAM)));
+
+        // the membership proof is bound to the proof it was finalized into
+        let mut moved = ZkAttestProof::<Secp256k1, Tom256k1>::try_from_slice(
+            &zkattest_proof.try_to_vec().unwrap(),
+        )
+        .unwrap();
+        moved.membership_proof = finalized.membership_proof.clone();
+        assert_eq!(
+            moved.verify(&mut rng, &parsed_ring).err(),
+            Some(MembershipError::VerificationFailed.into())
+        );
+        assert!(reports.windows(2).all(|pair| pair[0].0 < pair[1].0));
+
+        // signatures of other messages are rejected before proving
+        let precomputation =
+            ZkAttestPrecomputation::<Secp256k1, Tom256k1>::try_from_slice(&serialized).unwrap();
+        let result = precomputation.finalize(
+            &mut rng,
+            parsed_input.msg_hash + Scalar::ONE,
+            &parsed_input.signature,
+            parsed_input.guild_id.clone(),
+            StatementMode::PersonalSign,
+        );
+        assert_eq!(result.err(), Some(SignatureError::InvalidSignature.into()));
+
+        // the prover stops at the first report it is asked to stop at
+        let precomputation =
+            ZkAttestPrecomputation::<Secp256k1, Tom256k1>::try_from_slice(&serialized).unwrap();
+        let mut calls = 0;
+        let result = precomputation.finalize_with_progress(
+            &mut rng,
+            parsed_input.msg_hash,
+            &parsed_input.signature,
+            parsed_input.guild_id,
+            StatementMode::PersonalSign,
+            &mut |done, _| {
+                calls += 1;
+                done < SEC_PARAM
+            },
+        );
+        assert_eq!(result.err(), Some(ExpError::Cancelled.into()));
+        assert!(calls <= reports.len());
+
+        // the order of the ring does not matter, but its keys do
+        let mut reordered = parsed_ring.clone();
+        reordered.reverse();
+        assert!(zkattest_proof.verify(&mut rng, &reordered).is_ok());
+        assert!(zkattest_proof
+            .verify(&mut rng, &parsed_ring[..6].to_vec())
+            .is_err());
+
+        let mut registry = RingRegistry::new();
+        assert!(zkattest_proof
+            .verify_registered(&mut rng, &registry)
+            .is_err());
+        let digest = registry.insert(&reordered).unwrap();
+        assert_eq!(digest, zkattest_proof.ring_digest);
+        assert!(zkattest_proof
+            .verify_registered(&mut rng, &registry)
+            .is_ok());
+    }
+
+    #[test]
+    fn zkp_attest_finalize_from_bytes() {
+        let proof_input = ProofInput {
+            msg_hash: "0x9788117298a1450f6002d25f0c21d83bc6001681a2e5e31c748c0f55504b11e9"
+                .to_string(),
+            pubkey: Some("0454e32170dd5a0b7b641aa77daa1f3f31b8df17e51aaba6cfcb310848d26351180b6ac0399d21460443d10072700b64b454d70bfba5e93601536c740bbd099682".to_string()),
+            signature: "0xd2943d5fa0ba2733bcbbd58853c6c1be65388d9198dcb5228e117f49409612a46394afb97a7610d16e7bea0062e71afc2a3039324c80df8ef38d3668164fad2c1c".to_string(),
+            index: 1,
+            guild_id: "almafa".to_string(),
+            domain: None,
+            normalize_s: false,
+        };
+        let ring = vec![
+            "c2ef144b59081382387f0ebf5d96b3a194f8c28961fa443000ea793ce534dac2".to_string(),
+            "54e32170dd5a0b7b641aa77daa1f3f31b8df17e51aaba6cfcb310848d2635118".to_string(),
+            "ddd40afe39c280d2f43f05c070988dae7fbae9cdfd5fb6461acd7657e765e172".to_string(),
+        ];
+        let parsed_ring = parse_ring::<Tom256k1>(ring).unwrap();
+
+        let serialized = {
+            let mut rng = StdRng::from_seed([15; 32]);
+            let pedersen_cycle = PedersenCycle::new(&mut rng);
+            let parsed_input: ParsedProofInput<Secp256k1> = proof_input.clone().try_into().unwrap();
+            ZkAttestProof::<Secp256k1, Tom256k1>::precompute(
+                &mut rng,
+                pedersen_cycle,
+                &parsed_input.pubkey,
+                parsed_input.index,
+                &parsed_ring,
+            )
+            .unwrap()
+            .try_to_vec()
+            .unwrap()
+        };
+
+        // nothing but the bytes is needed to finalize, e.g. in another worker
+        let finalizer_ring = parsed_ring.clone();
+        let proof = std::thread::spawn(move || {
+            let mut rng = StdRng::from_seed([16; 32]);
+            let parsed_input: ParsedProofInput<Secp256k1> = proof_input.try_into().unwrap();
+            ZkAttestPrecomputation::<Secp256k1, Tom256k1>::try_from_slice(&serialized)
+                .unwrap()
+                .finalize(
+                    &mut rng,
+                    parsed_input.msg_hash,
+                    &parsed_input.signature,
+                    parsed_input.guild_id,
+                    StatementMode::PersonalSign,
+                )
+                .unwrap()
+        })
+        .join()
+        .unwrap();
+        let mut rng = StdRng::from_seed([17; 32]);
+        assert!(proof.verify(&mut rng, &finalizer_ring).is_ok());
+    }
+
+    #[test]
+    fn zkp_attest_typed_data_valid() {
+        let mut rng = StdRng::from_seed([35; 32]);
+        let statement = StatementMode::TypedData(Eip712Domain {
+            name: "Guild".to_string(),
+            version: None,
+            chain_id: 1,
+            verifying_contract: [0x42; 20],
+        });
+        let msg_hash = statement.message_hash::<Secp256k1>("almafa");
+
+        // sign the typed message with a fresh key
+        let secret = Scalar::<Secp256k1>::random(&mut rng);
+        let nonce = Scalar::<Secp256k1>::random(&mut rng);
+        let pubkey = (Point::<Secp256k1>::GENERATOR * secret).to_affine();
+        let r = Scalar::new(
+            *(Point::<Secp256k1>::GENERATOR * nonce)
+                .to_affine()
+                .x()
+                .inner(),
+        );
+        let s = nonce.inverse() * (msg_hash + r * secret);
+
+        let mut ring = (0..4)
+            .map(|_| Scalar::<Tom256k1>::random(&mut rng))
+            .collect::<Vec<_>>();
+        ring[2] = pubkey.x().to_cycle_scalar();
+        let input = ParsedProofInput {
+            msg_hash,
+            pubkey,
+            signature: Signature {
+                r,
+                s,
+                recovery_id: None,
+            },
+            index: 2,
+            guild_id: "almafa".to_string(),
+            statement: statement.clone(),
+        };
+
+        let pedersen_cycle = PedersenCycle::<Secp256k1, Tom256k1>::new(&mut rng);
+        let mut proof =
+            ZkAttestProof::<Secp256k1, Tom256k1>::construct(&mut rng, pedersen_cycle, input, &ring)
+                .unwrap();
+        assert_eq!(proof.statement, statement);
+        assert!(proof.verify(&mut rng, &ring).is_ok());
+
+        // the same signature does not prove a personal sign statement
+        proof.statement = StatementMode::PersonalSign;
+        assert_eq!(
+            proof.verify(&mut rng, &ring),
+            Err(StatementError::MessageHashMismatch.into())
+        );
+    }
+
+    #[test]
+    fn zkp_attest_circuit_valid() {
+        let mut rng = StdRng::from_seed([38; 32]);
+        let proof_input = ProofInput {
+            msg_hash: "0x9788117298a1450f6002d25f0c21d83bc6001681a2e5e31c748c0f55504b11e9"
+                .to_string(),
+            pubkey: None,
+            signature: "0xd2943d5fa0ba2733bcbbd58853c6c1be65388d9198dcb5228e117f49409612a46394afb97a7610d16e7bea0062e71afc2a3039324c80df8ef38d3668164fad2c1c".to_string(),
+            index: 1,
+            guild_id: "almafa".to_string(),
+            domain: None,
+            normalize_s: false,
+        };
+        let parsed_ring = parse_ring(vec![
+            "c2ef144b59081382387f0ebf5d96b3a194f8c28961fa443000ea793ce534dac2".to_string(),
+            "54e32170dd5a0b7b641aa77daa1f3f31b8df17e51aaba6cfcb310848d2635118".to_string(), // our pubkey x
+            "ddd40afe39c280d2f43f05c070988dae7fbae9cdfd5fb6461acd7657e765e172".to_string(),
+        ])
+        .unwrap();
+
+        let parsed_input: ParsedProofInput<Secp256k1> = proof_input.try_into().unwrap();
+        let pedersen_cycle = PedersenCycle::new(&mut rng);
+        let proof = ZkAttestProof::<Secp256k1, Tom256k1>::construct_with_mode(
+            &mut rng,
+            pedersen_cycle,
+            parsed_input,
+            &parsed_ring,
+            ProofMode::Circuit,
+        )
+        .unwrap();
+        assert_eq!(proof.signature_proof.mode(), ProofMode::Circuit);
+        let serialized = proof.try_to_vec().unwrap();
+        let mut proof = ZkAttestProof::<Secp256k1, Tom256k1>::try_from_slice(&serialized).unwrap();
+        assert!(proof.verify(&mut rng, &parsed_ring).is_ok());
+
+        // commitments blinded by a generator of known discrete logarithm
+        // are not binding
+        proof.pedersen = PedersenCycle::new(&mut rng);
+        assert!(proof.verify(&mut rng, &parsed_ring).is_err());
+    }
+
+    #[test]
+    fn zkp_attest_deterministic() {
+        let mut rng = StdRng::from_seed([39; 32]);
+        let input = ProofInput {
+            msg_hash: "0x9788117298a1450f6002d25f0c21d83bc6001681a2e5e31c748c0f55504b11e9"
+                .to_string(),
+            pubkey: None,
+            signature: "0xd2943d5fa0ba2733bcbbd58853c6c1be65388d9198dcb5228e117f49409612a46394afb97a7610d16e7bea0062e71afc2a3039324c80df8ef38d3668164fad2c1c".to_string(),
+            index: 0,
+            guild_id: "almafa".to_string(),
+            domain: None,
+            normalize_s: false,
+        };
+        let ring = parse_ring(vec![
+            "54e32170dd5a0b7b641aa77daa1f3f31b8df17e51aaba6cfcb310848d2635118".to_string(),
+            "c2ef144b59081382387f0ebf5d96b3a194f8c28961fa443000ea793ce534dac2".to_string(),
+        ])
+        .unwrap();
+
+        let prove = |seed: &[u8]| {
+            ZkAttestProof::<Secp256k1, Tom256k1>::construct_deterministic(
+                seed,
+                input.clone().try_into().unwrap(),
+                &ring,
+                ProofMode::Exp,
+            )
+            .unwrap()
+        };
+        let proof = prove(b"seed");
+        assert!(proof.verify(&mut rng, &ring).is_ok());
+        let bytes = proof.try_to_vec().unwrap();
+        assert_eq!(prove(b"seed").try_to_vec().unwrap(), bytes);
+        assert_ne!(prove(b"other seed").try_to_vec().unwrap(), bytes);
+    }
+
+    #[test]
+    fn zkp_attest_compound_valid() {
+        let mut rng = StdRng::from_seed([26; 32]);
+        let pedersen_cycle = PedersenCycle::<Secp256k1, Tom256k1>::new(&mut rng);
+
+        let proof_input = ProofInput {
+            msg_hash: "0x9788117298a1450f6002d25f0c21d83bc6001681a2e5e31c748c0f55504b11e9"
+                .to_string(),
+            pubkey: Some("0454e32170dd5a0b7b641aa77daa1f3f31b8df17e51aaba6cfcb310848d26351180b6ac0399d21460443d10072700b64b454d70bfba5e93601536c740bbd099682".to_string()),
+            signature: "0xd2943d5fa0ba2733bcbbd58853c6c1be65388d9198dcb5228e117f49409612a46394afb97a7610d16e7bea0062e71afc2a3039324c80df8ef38d3668164fad2c1c".to_string(),
+            index: 1,
+            guild_id: "almafa".to_string(),
+            domain: None,
+            normalize_s: false,
+        };
+
+        let our_x = "54e32170dd5a0b7b641aa77daa1f3f31b8df17e51aaba6cfcb310848d2635118".to_string();
+        let rings = vec![
+            vec![
+                "c2ef144b59081382387f0ebf5d96b3a194f8c28961fa443000ea793ce534dac2".to_string(),
+                our_x.clone(),
+                "ddd40afe39c280d2f43f05c070988dae7fbae9cdfd5fb6461acd7657e765e172".to_string(),
+            ],
+            vec![
+                "1296d6ed4e96bc378b8a460de783cdfbf58afbe04b355f1c225fb3e0b92cdc6e".to_string(),
+                "aaa70afe39c280d2f43f05c070988dae7fbae9cdfd5fb6461acd7657e765e172".to_string(),
+            ],
+            vec![
+                "bbb80afe39c280d2f43f05c070988dae7fbae9cdfd5fb6461acd7657e765e172".to_string(),
+                "ccc50afe39c280d2f43f05c070988dae7fbae9cdfd5fb6461acd7657e765e172".to_string(),
+                our_x,
+            ],
+        ];
+        let parsed_rings = rings
+            .into_iter()
+            .map(|ring| parse_ring(ring).unwrap())
+            .collect::<Vec<_>>();
+
+        // holds NFT A AND (token B OR token C)
+        let formula = RingFormula::And(vec![
+            RingFormula::Ring(0),
+            RingFormula::Or(vec![RingFormula::Ring(1), RingFormula::Ring(2)]),
+        ]);
+
+        // the index has to point at the key in the first ring containing it
+        let mut wrong_index: ParsedProofInput<Secp256k1> = proof_input.clone().try_into().unwrap();
+        wrong_index.index = 2;
+        assert_eq!(
+            ZkAttestCompoundProof::<Secp256k1, Tom256k1>::construct(
+                &mut rng,
+                pedersen_cycle.clone(),
+                wrong_index,
+                &formula,
+                &parsed_rings,
+            )
+            .err(),
+            Some(ParameterError::InvalidIndex.into())
+        );
+
+        let parsed_input: ParsedProofInput<Secp256k1> = proof_input.try_into().unwrap();
+        let proof = ZkAttestCompoundProof::<Secp256k1, Tom256k1>::construct(
+            &mut rng,
+            pedersen_cycle,
+            parsed_input,
+            &formula,
+            &parsed_rings,
+        )
+        .unwrap();
+        assert!(proof.verify(&mut rng, &formula, &parsed_rings).is_ok());
+
+        let unsatisfied = RingFormula::And(vec![RingFormula::Ring(0), RingFormula::Ring(1)]);
+        assert!(proof.verify(&mut rng, &unsatisfied, &parsed_rings).is_err());
+    }
+
+    #[test]
+    fn zkp_attest_tree_valid() {
+        let mut rng = StdRng::from_seed([28; 32]);
+        let pedersen_cycle = PedersenCycle::<Secp256k1, Tom256k1>::new(&mut rng);
+
+        let proof_input = ProofInput {
+            msg_hash: "0x9788117298a1450f6002d25f0c21d83bc6001681a2e5e31c748c0f55504b11e9"
+                .to_string(),
+            pubkey: Some("0454e32170dd5a0b7b641aa77daa1f3f31b8df17e51aaba6cfcb310848d26351180b6ac0399d21460443d10072700b64b454d70bfba5e93601536c740bbd099682".to_string()),
+            signature: "0xd2943d5fa0ba2733bcbbd58853c6c1be65388d9198dcb5228e117f49409612a46394afb97a7610d16e7bea0062e71afc2a3039324c80df8ef38d3668164fad2c1c".to_string(),
+            index: 3,
+            guild_id: "almafa".to_string(),
+            domain: None,
+            normalize_s: false,
+        };
+
+        let ring = vec![
+            "c2ef144b59081382387f0ebf5d96b3a194f8c28961fa443000ea793ce534dac2".to_string(),
+            "ddd40afe39c280d2f43f05c070988dae7fbae9cdfd5fb6461acd7657e765e172".to_string(),
+            "1296d6ed4e96bc378b8a460de783cdfbf58afbe04b355f1c225fb3e0b92cdc6e".to_string(),
+            "54e32170dd5a0b7b641aa77daa1f3f31b8df17e51aaba6cfcb310848d2635118".to_string(), // our pubkey x
+            "aaa70afe39c280d2f43f05c070988dae7fbae9cdfd5fb6461acd7657e765e172".to_string(),
+        ];
+        let parameters = CurveTreeParameters::<Secp256k1, Tom256k1>::new(2).unwrap();
+        let tree = CurveTree::new(&parameters, &parse_ring(ring).unwrap()).unwrap();
+        let root = tree.root();
+
+        // the index has to point at the key
+        let mut wrong_index: ParsedProofInput<Secp256k1> = proof_input.clone().try_into().unwrap();
+        wrong_index.index = 2;
+        assert_eq!(
+            ZkAttestTreeProof::construct(
+                &mut rng,
+                pedersen_cycle.clone(),
+                wrong_index,
+                &parameters,
+                &tree,
+            )
+            .err(),
+            Some(MembershipError::KeyMismatch.into())
+        );
+
+        let parsed_input: ParsedProofInput<Secp256k1> = proof_input.try_into().unwrap();
+        let proof = ZkAttestTreeProof::construct(
+            &mut rng,
+            pedersen_cycle,
+            parsed_input,
+            &parameters,
+            &tree,
+        )
+        .unwrap();
+        assert!(proof.verify(&mut rng, &parameters, &root).is_ok());
+
+        let proof = ZkAttestTreeProof::<Secp256k1, Tom256k1>::deserialize(
+            &mut proof.try_to_vec().unwrap().as_slice(),
+        )
+        .unwrap();
+        assert!(proof.verify(&mut rng, &parameters, &root).is_ok());
+
+        // another tree
+        let other_ring = vec![
+            "bbb80afe39c280d2f43f05c070988dae7fbae9cdfd5fb6461acd7657e765e172".to_string(),
+            "ccc50afe39c280d2f43f05c070988dae7fbae9cdfd5fb6461acd7657e765e172".to_string(),
+            "c2ef144b59081382387f0ebf5d96b3a194f8c28961fa443000ea793ce534dac2".to_string(),
+            "ddd40afe39c280d2f43f05c070988dae7fbae9cdfd5fb6461acd7657e765e172".to_string(),
+            "1296d6ed4e96bc378b8a460de783cdfbf58afbe04b355f1c225fb3e0b92cdc6e".to_string(),
+        ];
+        let other_tree = CurveTree::new(&parameters, &parse_ring(other_ring).unwrap()).unwrap();
+        assert!(proof
+            .verify(&mut rng, &parameters, &other_tree.root())
+            .is_err());
+
+        // the key has to be committed under the generator of the tree
+        let mut proof = proof;
+        proof.pedersen = PedersenCycle::new(&mut rng);
+        assert_eq!(
+            proof.verify(&mut rng, &parameters, &root).err(),
+            Some(CircuitError::InvalidGenerator.into())
+        );
     }
 }
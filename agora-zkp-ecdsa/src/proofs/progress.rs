@@ -0,0 +1,12 @@
+/// Observer of a long running proof generation.
+pub trait Progress {
+    /// Called after `done` out of `total` steps are completed. Returning
+    /// `false` cancels the proof generation.
+    fn report(&mut self, done: usize, total: usize) -> bool;
+}
+
+impl<F: FnMut(usize, usize) -> bool> Progress for F {
+    fn report(&mut self, done: usize, total: usize) -> bool {
+        self(done, total)
+    }
+}
@@ -0,0 +1,280 @@
+//! Elliptic curve gadgets over the other curve of a cycle.
+//!
+//! The coordinates of points of the curve `Ch` are scalars of the curve `P`
+//! the circuit is proven on, so curve arithmetic on `Ch` is native in the
+//! circuit.
+use super::{ConstraintSystem, LinearCombination, Variable};
+use crate::arithmetic::{AffinePoint, Modular, Point, Scalar};
+use crate::curve::{Curve, Cycle};
+use crate::error::CircuitError;
+use crate::U256;
+
+/// Number of 2-bit windows of a scalar.
+const WINDOWS: usize = 128;
+
+/// Number of multiplication gates used by [`allocate_point`].
+pub const POINT_GATES: usize = 4;
+
+/// Number of multiplication gates used by [`fixed_base_mul`].
+pub const SCALAR_MUL_GATES: usize = 7 * WINDOWS;
+
+pub type Coordinates<C> = (Scalar<C>, Scalar<C>);
+
+/// Maps the affine coordinates of a point into the scalar field of the
+/// other curve of the cycle.
+pub fn coordinates<P: Cycle<Ch>, Ch: Curve>(point: &AffinePoint<Ch>) -> Coordinates<P> {
+    (point.x().to_cycle_scalar(), point.y().to_cycle_scalar())
+}
+
+/// Points `K_j + d * 4^j * B` for every window `j` and digit `d` of a fixed
+/// base `B` with their coordinates mapped into the scalar field of the
+/// circuit.
+///
+/// The offsets `K_j` are hashed to the curve so that none of the partial
+/// sums is the point at infinity and the incomplete addition formula can be
+/// used.
+#[derive(Clone, Debug)]
+pub struct FixedBaseTable<P: Curve, Ch: Curve> {
+    offset: Point<Ch>,
+    windows: Vec<[Coordinates<P>; 4]>,
+}
+
+impl<P: Cycle<Ch>, Ch: Curve> FixedBaseTable<P, Ch> {
+    pub fn new(label: &[u8], base: &Point<Ch>) -> Self {
+        let mut offset = Point::<Ch>::IDENTITY;
+        let mut windows = Vec::with_capacity(WINDOWS);
+        let mut power = *base;
+        for j in 0..WINDOWS {
+            let mut input = label.to_vec();
+            input.extend_from_slice(&(j as u64).to_be_bytes());
+            let window_offset = Point::<Ch>::hash_to_curve(&input);
+            offset += &window_offset;
+
+            let mut entry = window_offset;
+            let mut window = [(Scalar::ZERO, Scalar::ZERO); 4];
+            for coords in window.iter_mut() {
+                *coords = coordinates(&entry.to_affine());
+                entry += &power;
+            }
+            windows.push(window);
+            power = power.double().double();
+        }
+        Self { offset, windows }
+    }
+}
+
+/// Allocates the coordinates of a point and constrains them to satisfy the
+/// curve equation.
+pub fn allocate_point<P, Ch, CS>(
+    cs: &mut CS,
+    point: Option<&AffinePoint<Ch>>,
+) -> Result<(Variable, Variable), CircuitError>
+where
+    P: Cycle<Ch>,
+    Ch: Curve,
+    CS: ConstraintSystem<P>,
+{
+    let (x, y, _) = cs.allocate_multiplier(point.map(coordinates::<P, Ch>))?;
+
+    // y^2 = x^3 + a * x + b
+    let (_, _, x_sq) = cs.multiply(x.into(), x.into());
+    let (_, _, x_cube) = cs.multiply(x_sq.into(), x.into());
+    let (_, _, y_sq) = cs.multiply(y.into(), y.into());
+    let coeff_a = Scalar::<P>::new(Ch::COEFF_A);
+    let coeff_b = Scalar::<P>::new(Ch::COEFF_B);
+    cs.constrain(
+        LinearCombination::from(y_sq) - x_cube - LinearCombination::from(x) * coeff_a - coeff_b,
+    );
+    Ok((x, y))
+}
+
+/// Proves that `target = start + scalar * B` where `B` is the base of the
+/// table and `start` is given by its allocated coordinates.
+///
+/// The scalar multiplication is computed in 2-bit windows: starting from
+/// `start`, the table entry of each digit of the scalar is added using
+/// incomplete affine addition, and the result is checked against
+/// `target + K`.
+pub fn fixed_base_mul<P, Ch, CS>(
+    cs: &mut CS,
+    table: &FixedBaseTable<P, Ch>,
+    (x, y): (Variable, Variable),
+    witness: Option<(AffinePoint<Ch>, Scalar<Ch>)>,
+    target: &Point<Ch>,
+) -> Result<(), CircuitError>
+where
+    P: Cycle<Ch>,
+    Ch: Curve,
+    CS: ConstraintSystem<P>,
+{
+    let one = Scalar::<P>::ONE;
+    let scalar = witness.as_ref().map(|(_, scalar)| *scalar.inner());
+    let mut acc_x = LinearCombination::from(x);
+    let mut acc_y = LinearCombination::from(y);
+    let mut acc = witness
+        .as_ref()
+        .map(|(point, _)| coordinates::<P, Ch>(point));
+    for (j, window) in table.windows.iter().enumerate() {
+        let digit = scalar.map(|s| (s.bit_vartime(2 * j) == 1, s.bit_vartime(2 * j + 1) == 1));
+        let bit_value = |bit: bool| Scalar::<P>::new(U256::from_u64(bit as u64));
+        let (b_0, not_b_0, check_0) =
+            cs.allocate_multiplier(digit.map(|(b, _)| (bit_value(b), one - bit_value(b))))?;
+        let (b_1, not_b_1, check_1) =
+            cs.allocate_multiplier(digit.map(|(_, b)| (bit_value(b), one - bit_value(b))))?;
+        cs.constrain(LinearCombination::from(b_0) + not_b_0 - one);
+        cs.constrain(LinearCombination::from(b_1) + not_b_1 - one);
+        cs.constrain(check_0.into());
+        cs.constrain(check_1.into());
+        let (_, _, b_01) = cs.multiply(b_0.into(), b_1.into());
+
+        // multilinear interpolation of the table entries
+        let lookup = |coord: fn(&Coordinates<P>) -> Scalar<P>| {
+            let t = [
+                coord(&window[0]),
+                coord(&window[1]),
+                coord(&window[2]),
+                coord(&window[3]),
+            ];
+            LinearCombination::from(t[0])
+                + LinearCombination::from(b_0) * (t[1] - t[0])
+                + LinearCombination::from(b_1) * (t[2] - t[0])
+                + LinearCombination::from(b_01) * (t[3] - t[2] - t[1] + t[0])
+        };
+        let s_x = lookup(|c| c.0);
+        let s_y = lookup(|c| c.1);
+        let s = digit.map(|(b_0, b_1)| window[b_0 as usize + 2 * b_1 as usize]);
+
+        // incomplete addition of acc and s
+        let lambda = match acc.zip(s) {
+            Some(((a_x, a_y), (s_x, s_y))) => {
+                if s_x == a_x {
+                    return Err(CircuitError::DegenerateRerandomization);
+                }
+                let d_x = s_x - a_x;
+                let d_x_inv = d_x.inverse();
+                Some(((s_y - a_y) * d_x_inv, d_x, d_x_inv))
+            }
+            None => None,
+        };
+        (acc_x, acc_y) = incomplete_add(cs, (acc_x, acc_y), (s_x, s_y), lambda)?;
+
+        acc = match (acc, s, lambda) {
+            (Some((a_x, a_y)), Some((s_x, _)), Some((lambda, _, _))) => {
+                let x = lambda * lambda - a_x - s_x;
+                Some((x, lambda * (a_x - x) - a_y))
+            }
+            _ => None,
+        };
+    }
+
+    let (target_x, target_y) = coordinates::<P, Ch>(&(target + &table.offset).to_affine());
+    cs.constrain(acc_x - target_x);
+    cs.constrain(acc_y - target_y);
+    Ok(())
+}
+
+// Adds `s` to `acc` with the incomplete affine addition formula and returns
+// the coordinates of the sum. The witness is the slope `λ`, the difference
+// `d_x = s_x - acc_x` and its inverse, which proves that `d_x` is not zero:
+// otherwise `acc = ±s` and `λ` would be unconstrained.
+#[allow(clippy::type_complexity)]
+fn incomplete_add<P, CS>(
+    cs: &mut CS,
+    (acc_x, acc_y): (LinearCombination<P>, LinearCombination<P>),
+    (s_x, s_y): (LinearCombination<P>, LinearCombination<P>),
+    witness: Option<(Scalar<P>, Scalar<P>, Scalar<P>)>,
+) -> Result<(LinearCombination<P>, LinearCombination<P>), CircuitError>
+where
+    P: Curve,
+    CS: ConstraintSystem<P>,
+{
+    let one = Scalar::<P>::ONE;
+    let (l, d_x, d_y) = cs.allocate_multiplier(witness.map(|(l, d_x, _)| (l, d_x)))?;
+    cs.constrain(LinearCombination::from(d_x) - s_x.clone() + acc_x);
+    cs.constrain(LinearCombination::from(d_y) - s_y.clone() + acc_y);
+    let (d_x_copy, _, d_x_inv) = cs.allocate_multiplier(witness.map(|(_, d_x, inv)| (d_x, inv)))?;
+    cs.constrain(LinearCombination::from(d_x_copy) - d_x);
+    cs.constrain(LinearCombination::from(d_x_inv) - one);
+    // from here on acc is expressed as s - d, which keeps the linear
+    // combinations short
+    let (_, _, l_sq) = cs.multiply(l.into(), l.into());
+    let new_x = LinearCombination::from(l_sq) + d_x - s_x.clone() - s_x.clone();
+    let (_, _, product) = cs.multiply(l.into(), s_x - d_x - new_x.clone());
+    Ok((new_x, LinearCombination::from(product) - s_y + d_y))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::curve::{Secp256k1, Tom256k1};
+    use crate::error::Error;
+    use crate::hasher::Transcript;
+    use crate::proofs::r1cs::{Prover, R1csGenerators, Verifier};
+    use rand::rngs::StdRng;
+    use rand_core::SeedableRng;
+
+    type TomScalar = Scalar<Tom256k1>;
+
+    // adds the public point `s` to the allocated point `acc` and constrains
+    // the sum to `sum`
+    fn gadget<CS: ConstraintSystem<Tom256k1>>(
+        cs: &mut CS,
+        acc: Option<&AffinePoint<Secp256k1>>,
+        s: Coordinates<Tom256k1>,
+        witness: Option<(TomScalar, TomScalar, TomScalar)>,
+        sum: Coordinates<Tom256k1>,
+    ) -> Result<(), CircuitError> {
+        let (x, y) = allocate_point(cs, acc)?;
+        let (sum_x, sum_y) =
+            incomplete_add(cs, (x.into(), y.into()), (s.0.into(), s.1.into()), witness)?;
+        cs.constrain(sum_x - sum.0);
+        cs.constrain(sum_y - sum.1);
+        Ok(())
+    }
+
+    fn prove_and_verify(
+        acc: AffinePoint<Secp256k1>,
+        s: AffinePoint<Secp256k1>,
+        witness: (TomScalar, TomScalar, TomScalar),
+        sum: Coordinates<Tom256k1>,
+    ) -> Result<(), Error> {
+        let mut rng = StdRng::from_seed([38; 32]);
+        let generators = R1csGenerators::<Tom256k1>::new(b"gadgets-test", 8);
+        let s = coordinates(&s);
+
+        let mut prover = Prover::new(&generators, Transcript::new(b"gadgets-test"));
+        gadget(&mut prover, Some(&acc), s, Some(witness), sum)?;
+        let proof = prover.prove(&mut rng)?;
+
+        let mut verifier = Verifier::new(&generators, Transcript::new(b"gadgets-test"));
+        gadget(&mut verifier, None, s, None, sum)?;
+        verifier.verify(&mut rng, &proof)
+    }
+
+    #[test]
+    fn exceptional_addition() {
+        let mut rng = StdRng::from_seed([38; 32]);
+        let acc = (Point::<Secp256k1>::GENERATOR * Scalar::random(&mut rng)).to_affine();
+        let s = (Point::<Secp256k1>::GENERATOR * Scalar::random(&mut rng)).to_affine();
+
+        let (a_x, a_y) = coordinates::<Tom256k1, _>(&acc);
+        let (s_x, s_y) = coordinates::<Tom256k1, _>(&s);
+        let d_x = s_x - a_x;
+        let lambda = (s_y - a_y) * d_x.inverse();
+        let sum = coordinates(&(Point::from(&acc) + s).to_affine());
+        assert!(prove_and_verify(acc, s, (lambda, d_x, d_x.inverse()), sum).is_ok());
+
+        // adding a point to itself leaves the slope unconstrained, any point
+        // could be claimed as the sum unless the difference of the
+        // x-coordinates is proven to be invertible
+        let lambda = TomScalar::random(&mut rng);
+        let x = lambda * lambda - s_x - s_x;
+        let forged = (x, lambda * (s_x - x) - s_y);
+        for inverse in [TomScalar::ZERO, TomScalar::ONE] {
+            assert_eq!(
+                prove_and_verify(s, s, (lambda, TomScalar::ZERO, inverse), forged),
+                Err(CircuitError::VerificationFailed.into())
+            );
+        }
+    }
+}
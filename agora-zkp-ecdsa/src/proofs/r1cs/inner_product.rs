@@ -0,0 +1,249 @@
+use crate::arithmetic::multimult::MultiMult;
+use crate::arithmetic::{Modular, Point, Scalar};
+use crate::curve::Curve;
+use crate::error::CircuitError;
+use crate::hasher::Transcript;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// Logarithmic size argument of knowledge of `a` and `b` such that
+/// `P = <a, G> + <b, H'> + <a, b> * Q` where `H'_i = h_factors[i] * H_i`.
+#[derive(Clone, Debug, BorshDeserialize, BorshSerialize)]
+pub struct InnerProductProof<C: Curve> {
+    l_vec: Vec<Point<C>>,
+    r_vec: Vec<Point<C>>,
+    a: Scalar<C>,
+    b: Scalar<C>,
+}
+
+/// Scalars of the final check of an inner product proof.
+pub(super) struct VerificationScalars<C: Curve> {
+    /// Coefficients of the original `G` generators.
+    pub s: Vec<Scalar<C>>,
+    /// Coefficients of the original `H` generators (without `h_factors`).
+    pub s_inv: Vec<Scalar<C>>,
+    /// Coefficients of the `L` and `R` points.
+    pub challenges_sq: Vec<(Scalar<C>, Scalar<C>)>,
+}
+
+impl<C: Curve> InnerProductProof<C> {
+    /// Constructs the proof. The length of the vectors has to be a power of
+    /// two.
+    ///
+    /// The generators are never folded explicitly, that would cost a scalar
+    /// multiplication per generator and round. Instead, the cross terms of
+    /// each round are computed as a multi-scalar multiplication over the
+    /// original generators.
+    pub fn construct(
+        transcript: &mut Transcript,
+        q: &Point<C>,
+        g_vec: &[Point<C>],
+        h_vec: &[Point<C>],
+        h_factors: Vec<Scalar<C>>,
+        mut a: Vec<Scalar<C>>,
+        mut b: Vec<Scalar<C>>,
+    ) -> Self {
+        let n = a.len();
+        debug_assert!(n.is_power_of_two());
+        let mut g_coeffs = vec![Scalar::<C>::ONE; n];
+        let mut h_coeffs = h_factors;
+        let mut l_vec = Vec::new();
+        let mut r_vec = Vec::new();
+
+        let mut len = n;
+        while len > 1 {
+            let half = len / 2;
+            let (a_lo, a_hi) = a.split_at(half);
+            let (b_lo, b_hi) = b.split_at(half);
+
+            let mut l_multimult = MultiMult::new();
+            let mut r_multimult = MultiMult::new();
+            let insert = |multimult: &mut MultiMult<C>, point: &Point<C>, scalar: Scalar<C>| {
+                if scalar != Scalar::ZERO {
+                    multimult.insert(*point, scalar);
+                }
+            };
+            for t in 0..n {
+                let k = t % len;
+                if k < half {
+                    insert(&mut r_multimult, &g_vec[t], a_hi[k] * g_coeffs[t]);
+                    insert(&mut l_multimult, &h_vec[t], b_hi[k] * h_coeffs[t]);
+                } else {
+                    insert(&mut l_multimult, &g_vec[t], a_lo[k - half] * g_coeffs[t]);
+                    insert(&mut r_multimult, &h_vec[t], b_lo[k - half] * h_coeffs[t]);
+                }
+            }
+            insert(&mut l_multimult, q, inner_product(a_lo, b_hi));
+            insert(&mut r_multimult, q, inner_product(a_hi, b_lo));
+            let l = l_multimult.evaluate();
+            let r = r_multimult.evaluate();
+
+            transcript.append_point(&l);
+            transcript.append_point(&r);
+            let u = transcript.challenge::<C>();
+            let u_inv = u.inverse();
+
+            let folded_a = (0..half).map(|i| a_lo[i] * u + a_hi[i] * u_inv).collect();
+            let folded_b = (0..half).map(|i| b_lo[i] * u_inv + b_hi[i] * u).collect();
+            a = folded_a;
+            b = folded_b;
+            for t in 0..n {
+                if t % len < half {
+                    g_coeffs[t] *= u_inv;
+                    h_coeffs[t] *= u;
+                } else {
+                    g_coeffs[t] *= u;
+                    h_coeffs[t] *= u_inv;
+                }
+            }
+
+            l_vec.push(l);
+            r_vec.push(r);
+            len = half;
+        }
+
+        Self {
+            l_vec,
+            r_vec,
+            a: a[0],
+            b: b[0],
+        }
+    }
+
+    pub fn a(&self) -> Scalar<C> {
+        self.a
+    }
+
+    pub fn b(&self) -> Scalar<C> {
+        self.b
+    }
+
+    pub fn l_vec(&self) -> &[Point<C>] {
+        &self.l_vec
+    }
+
+    pub fn r_vec(&self) -> &[Point<C>] {
+        &self.r_vec
+    }
+
+    /// Replays the transcript and computes the scalars of the final check
+    ///
+    /// `P + sum_j (u_j^2 * L_j + u_j^-2 * R_j) = a * <s, G> + b * <s^-1, H'> + a * b * Q`
+    pub(super) fn verification_scalars(
+        &self,
+        transcript: &mut Transcript,
+        n: usize,
+    ) -> Result<VerificationScalars<C>, CircuitError> {
+        let rounds = n.trailing_zeros() as usize;
+        if !n.is_power_of_two() || self.l_vec.len() != rounds || self.r_vec.len() != rounds {
+            return Err(CircuitError::InvalidProofLengths);
+        }
+
+        let mut challenges = Vec::with_capacity(rounds);
+        for (l, r) in self.l_vec.iter().zip(&self.r_vec) {
+            transcript.append_point(l);
+            transcript.append_point(r);
+            let u = transcript.challenge::<C>();
+            challenges.push((u, u.inverse()));
+        }
+
+        let mut s = vec![Scalar::<C>::ONE; n];
+        let mut s_inv = vec![Scalar::<C>::ONE; n];
+        for (j, (u, u_inv)) in challenges.iter().enumerate() {
+            let len = n >> j;
+            let half = len / 2;
+            for t in 0..n {
+                if t % len < half {
+                    s[t] *= *u_inv;
+                    s_inv[t] *= *u;
+                } else {
+                    s[t] *= *u;
+                    s_inv[t] *= *u_inv;
+                }
+            }
+        }
+
+        let challenges_sq = challenges
+            .iter()
+            .map(|(u, u_inv)| (*u * *u, *u_inv * *u_inv))
+            .collect();
+
+        Ok(VerificationScalars {
+            s,
+            s_inv,
+            challenges_sq,
+        })
+    }
+}
+
+pub fn inner_product<C: Curve>(a: &[Scalar<C>], b: &[Scalar<C>]) -> Scalar<C> {
+    a.iter()
+        .zip(b)
+        .fold(Scalar::ZERO, |acc, (a, b)| acc + *a * *b)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::curve::Tom256k1;
+    use rand::rngs::StdRng;
+    use rand_core::SeedableRng;
+
+    type TomPoint = Point<Tom256k1>;
+    type TomScalar = Scalar<Tom256k1>;
+
+    #[test]
+    fn valid_inner_product_proof() {
+        let mut rng = StdRng::from_seed([3; 32]);
+        let n = 8;
+        let g_vec: Vec<TomPoint> = (0..n)
+            .map(|_| TomPoint::GENERATOR * TomScalar::random(&mut rng))
+            .collect();
+        let h_vec: Vec<TomPoint> = (0..n)
+            .map(|_| TomPoint::GENERATOR * TomScalar::random(&mut rng))
+            .collect();
+        let q = TomPoint::GENERATOR * TomScalar::random(&mut rng);
+        let h_factors: Vec<TomScalar> = (0..n).map(|_| TomScalar::random(&mut rng)).collect();
+        let a: Vec<TomScalar> = (0..n).map(|_| TomScalar::random(&mut rng)).collect();
+        let b: Vec<TomScalar> = (0..n).map(|_| TomScalar::random(&mut rng)).collect();
+
+        let mut commitment = q * inner_product(&a, &b);
+        for i in 0..n {
+            commitment += &(g_vec[i] * a[i] + h_vec[i] * (b[i] * h_factors[i]));
+        }
+
+        let proof = InnerProductProof::construct(
+            &mut Transcript::new(b"test"),
+            &q,
+            &g_vec,
+            &h_vec,
+            h_factors.clone(),
+            a,
+            b,
+        );
+        assert_eq!(proof.l_vec().len(), 3);
+
+        let scalars = proof
+            .verification_scalars(&mut Transcript::new(b"test"), n)
+            .unwrap();
+        let mut expected = q * (proof.a() * proof.b());
+        for i in 0..n {
+            expected += &(g_vec[i] * (proof.a() * scalars.s[i])
+                + h_vec[i] * (proof.b() * scalars.s_inv[i] * h_factors[i]));
+        }
+        let mut folded = commitment;
+        for ((l, r), (u_sq, u_inv_sq)) in proof
+            .l_vec()
+            .iter()
+            .zip(proof.r_vec())
+            .zip(&scalars.challenges_sq)
+        {
+            folded += &(*l * *u_sq + *r * *u_inv_sq);
+        }
+        assert_eq!(folded, expected);
+
+        assert!(proof
+            .verification_scalars(&mut Transcript::new(b"test"), 16)
+            .is_err());
+    }
+}
@@ -0,0 +1,276 @@
+//! Bulletproofs arithmetic circuit (rank-1 constraint system) proofs.
+//!
+//! A circuit consists of multiplication gates `a_L[i] * a_R[i] = a_O[i]`
+//! and linear constraints over the gate wires, Pedersen-committed values
+//! and constants. Gadgets are written once against the [`ConstraintSystem`]
+//! trait and run by both the [`Prover`] and the [`Verifier`].
+//!
+//! Besides single committed values, a proof can be bound to a vector
+//! commitment `sum_i x_i * G_i + r * H` whose entries become the left wires
+//! of the first multiplication gates.
+pub mod gadgets;
+mod inner_product;
+mod prover;
+mod verifier;
+
+pub use inner_product::InnerProductProof;
+pub use prover::Prover;
+pub use verifier::Verifier;
+
+use crate::arithmetic::{Point, Scalar};
+use crate::curve::Curve;
+use crate::error::CircuitError;
+use crate::pedersen::PedersenGenerator;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+/// A wire of the circuit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Variable {
+    Committed(usize),
+    MultiplierLeft(usize),
+    MultiplierRight(usize),
+    MultiplierOutput(usize),
+    One,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct LinearCombination<C: Curve> {
+    terms: Vec<(Variable, Scalar<C>)>,
+}
+
+impl<C: Curve> LinearCombination<C> {
+    pub fn zero() -> Self {
+        Self { terms: Vec::new() }
+    }
+
+    pub fn terms(&self) -> &[(Variable, Scalar<C>)] {
+        &self.terms
+    }
+}
+
+impl<C: Curve> From<Variable> for LinearCombination<C> {
+    fn from(variable: Variable) -> Self {
+        Self {
+            terms: vec![(variable, Scalar::ONE)],
+        }
+    }
+}
+
+impl<C: Curve> From<Scalar<C>> for LinearCombination<C> {
+    fn from(constant: Scalar<C>) -> Self {
+        Self {
+            terms: vec![(Variable::One, constant)],
+        }
+    }
+}
+
+impl<C: Curve, L: Into<LinearCombination<C>>> std::ops::Add<L> for LinearCombination<C> {
+    type Output = Self;
+    fn add(mut self, rhs: L) -> Self::Output {
+        self.terms.extend(rhs.into().terms);
+        self
+    }
+}
+
+impl<C: Curve, L: Into<LinearCombination<C>>> std::ops::Sub<L> for LinearCombination<C> {
+    type Output = Self;
+    fn sub(mut self, rhs: L) -> Self::Output {
+        self.terms.extend(
+            rhs.into()
+                .terms
+                .into_iter()
+                .map(|(variable, coeff)| (variable, -coeff)),
+        );
+        self
+    }
+}
+
+impl<C: Curve> std::ops::Neg for LinearCombination<C> {
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        Self::zero() - self
+    }
+}
+
+impl<C: Curve> std::ops::Mul<Scalar<C>> for LinearCombination<C> {
+    type Output = Self;
+    fn mul(mut self, rhs: Scalar<C>) -> Self::Output {
+        for (_, coeff) in self.terms.iter_mut() {
+            *coeff *= rhs;
+        }
+        self
+    }
+}
+
+/// Common interface of the prover and the verifier for building circuits.
+///
+/// Prover-side assignments are passed as `Option`s and are ignored by the
+/// verifier.
+pub trait ConstraintSystem<C: Curve> {
+    /// Allocates the `index`-th gate bound to the vector commitment. Its
+    /// left wire is the `index`-th entry of the committed vector. These
+    /// gates have to be allocated first and in order.
+    fn input_multiplier(
+        &mut self,
+        index: usize,
+        right: Option<Scalar<C>>,
+    ) -> Result<(Variable, Variable, Variable), CircuitError>;
+
+    /// Allocates a gate with the given left and right wire assignments.
+    fn allocate_multiplier(
+        &mut self,
+        assignment: Option<(Scalar<C>, Scalar<C>)>,
+    ) -> Result<(Variable, Variable, Variable), CircuitError>;
+
+    /// Allocates a gate whose left and right wires equal the given linear
+    /// combinations.
+    fn multiply(
+        &mut self,
+        left: LinearCombination<C>,
+        right: LinearCombination<C>,
+    ) -> (Variable, Variable, Variable);
+
+    /// Constrains the linear combination to zero.
+    fn constrain(&mut self, lc: LinearCombination<C>);
+}
+
+/// Generators of arithmetic circuit proofs.
+///
+/// All generators are derived by hashing to the curve, so no party knows
+/// discrete logarithm relations between them. Committed values use the
+/// curve's generator and the blinding generator as a regular Pedersen
+/// commitment.
+#[derive(Clone, Debug)]
+pub struct R1csGenerators<C: Curve> {
+    g_vec: Vec<Point<C>>,
+    h_vec: Vec<Point<C>>,
+    blinding: PedersenGenerator<C>,
+}
+
+impl<C: Curve> R1csGenerators<C> {
+    /// Derives generators for circuits with at most `capacity` gates.
+    pub fn new(label: &[u8], capacity: usize) -> Self {
+        let capacity = capacity.next_power_of_two();
+        Self {
+            g_vec: (0..capacity).map(|i| derive(label, b"/g/", i)).collect(),
+            h_vec: (0..capacity).map(|i| derive(label, b"/h/", i)).collect(),
+            blinding: Self::blinding_generator(label),
+        }
+    }
+
+    /// The blinding generator of the generators derived from `label`, which
+    /// is much cheaper to derive than all generators.
+    pub fn blinding_generator(label: &[u8]) -> PedersenGenerator<C> {
+        PedersenGenerator::from_point(derive(label, b"/blinding/", 0))
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.g_vec.len()
+    }
+
+    pub fn g_vec(&self) -> &[Point<C>] {
+        &self.g_vec
+    }
+
+    pub fn blinding(&self) -> PedersenGenerator<C> {
+        self.blinding
+    }
+}
+
+fn derive<C: Curve>(label: &[u8], tag: &[u8], index: usize) -> Point<C> {
+    let mut input = label.to_vec();
+    input.extend_from_slice(tag);
+    input.extend_from_slice(&(index as u64).to_be_bytes());
+    Point::hash_to_curve(&input)
+}
+
+#[derive(Clone, Debug, BorshDeserialize, BorshSerialize)]
+pub struct R1csProof<C: Curve> {
+    a_i: Point<C>,
+    a_o: Point<C>,
+    s: Point<C>,
+    t_1: Point<C>,
+    t_3: Point<C>,
+    t_4: Point<C>,
+    t_5: Point<C>,
+    t_6: Point<C>,
+    t_x: Scalar<C>,
+    t_x_blinding: Scalar<C>,
+    e_blinding: Scalar<C>,
+    ipp: InnerProductProof<C>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::arithmetic::Modular;
+    use crate::curve::Tom256k1;
+    use crate::error::Error;
+    use crate::hasher::Transcript;
+    use crate::U256;
+    use rand::rngs::StdRng;
+    use rand_core::SeedableRng;
+
+    type TomScalar = Scalar<Tom256k1>;
+
+    // proves knowledge of a committed `x` and a vector entry `y` such that
+    // `x * y = z` and `x + y = s` for public `z` and `s`
+    fn gadget<CS: ConstraintSystem<Tom256k1>>(
+        cs: &mut CS,
+        x: Variable,
+        y: Option<TomScalar>,
+        z: TomScalar,
+        s: TomScalar,
+    ) -> Result<(), CircuitError> {
+        let (y_var, one, _) = cs.input_multiplier(0, y.map(|_| TomScalar::ONE))?;
+        cs.constrain(LinearCombination::from(one) - Scalar::ONE);
+        let (_, _, product) = cs.multiply(x.into(), y_var.into());
+        cs.constrain(LinearCombination::from(product) - z);
+        cs.constrain(LinearCombination::from(x) + y_var - s);
+        Ok(())
+    }
+
+    fn prove_and_verify(x: u32, y: u32, z: u32, s: u32) -> Result<(), Error> {
+        let mut rng = StdRng::from_seed([13; 32]);
+        let generators = R1csGenerators::<Tom256k1>::new(b"r1cs-test", 8);
+        let x = TomScalar::new(U256::from_u32(x));
+        let y = TomScalar::new(U256::from_u32(y));
+        let z = TomScalar::new(U256::from_u32(z));
+        let s = TomScalar::new(U256::from_u32(s));
+
+        let input_blinding = TomScalar::random(&mut rng);
+        let input = generators.g_vec()[0] * y + generators.blinding().generator() * input_blinding;
+
+        let mut prover = Prover::new(&generators, Transcript::new(b"r1cs-test"));
+        let x_blinding = TomScalar::random(&mut rng);
+        let (commitment, x_var) = prover.commit(x, x_blinding);
+        prover.vector_input(input, vec![y], input_blinding);
+        gadget(&mut prover, x_var, Some(y), z, s)?;
+        let proof = prover.prove(&mut rng)?;
+
+        let mut verifier = Verifier::new(&generators, Transcript::new(b"r1cs-test"));
+        let x_var = verifier.commit(commitment);
+        verifier.vector_input(input, 1);
+        gadget(&mut verifier, x_var, None, z, s)?;
+        verifier.verify(&mut rng, &proof)
+    }
+
+    #[test]
+    fn valid_circuit_proof() {
+        assert!(prove_and_verify(3, 5, 15, 8).is_ok());
+        assert!(prove_and_verify(0, 7, 0, 7).is_ok());
+    }
+
+    #[test]
+    fn invalid_circuit_proof() {
+        assert_eq!(
+            prove_and_verify(3, 5, 16, 8),
+            Err(CircuitError::VerificationFailed.into())
+        );
+        assert_eq!(
+            prove_and_verify(3, 5, 15, 9),
+            Err(CircuitError::VerificationFailed.into())
+        );
+    }
+}
@@ -0,0 +1,321 @@
+use super::inner_product::{inner_product, InnerProductProof};
+use super::{ConstraintSystem, LinearCombination, R1csGenerators, R1csProof, Variable};
+use crate::arithmetic::multimult::MultiMult;
+use crate::arithmetic::{Modular, Point, Scalar};
+use crate::curve::Curve;
+use crate::error::{CircuitError, Error, ParameterError};
+use crate::hasher::Transcript;
+
+use rand_core::{CryptoRng, RngCore};
+
+pub struct Prover<'a, C: Curve> {
+    generators: &'a R1csGenerators<C>,
+    transcript: Transcript,
+    constraints: Vec<LinearCombination<C>>,
+    a_l: Vec<Scalar<C>>,
+    a_r: Vec<Scalar<C>>,
+    a_o: Vec<Scalar<C>>,
+    v: Vec<Scalar<C>>,
+    v_blinding: Vec<Scalar<C>>,
+    input: Vec<Scalar<C>>,
+    input_blinding: Scalar<C>,
+}
+
+impl<'a, C: Curve> Prover<'a, C> {
+    pub fn new(generators: &'a R1csGenerators<C>, transcript: Transcript) -> Self {
+        Self {
+            generators,
+            transcript,
+            constraints: Vec::new(),
+            a_l: Vec::new(),
+            a_r: Vec::new(),
+            a_o: Vec::new(),
+            v: Vec::new(),
+            v_blinding: Vec::new(),
+            input: Vec::new(),
+            input_blinding: Scalar::ZERO,
+        }
+    }
+
+    /// Commits to `value` with the curve generator and the blinding
+    /// generator and returns the commitment and its variable.
+    pub fn commit(&mut self, value: Scalar<C>, blinding: Scalar<C>) -> (Point<C>, Variable) {
+        let commitment = self
+            .generators
+            .blinding()
+            .commit_with_randomness(value, blinding)
+            .commitment();
+        self.transcript.append_point(&commitment);
+        self.v.push(value);
+        self.v_blinding.push(blinding);
+        (commitment, Variable::Committed(self.v.len() - 1))
+    }
+
+    /// Binds the proof to the vector commitment
+    /// `sum_i values[i] * G_i + blinding * H`.
+    pub fn vector_input(
+        &mut self,
+        commitment: Point<C>,
+        values: Vec<Scalar<C>>,
+        blinding: Scalar<C>,
+    ) {
+        self.transcript.append_u64(values.len() as u64);
+        self.transcript.append_point(&commitment);
+        self.input = values;
+        self.input_blinding = blinding;
+    }
+
+    fn eval(&self, lc: &LinearCombination<C>) -> Scalar<C> {
+        lc.terms()
+            .iter()
+            .fold(Scalar::ZERO, |acc, (variable, coeff)| {
+                let value = match variable {
+                    Variable::Committed(i) => self.v[*i],
+                    Variable::MultiplierLeft(i) => self.a_l[*i],
+                    Variable::MultiplierRight(i) => self.a_r[*i],
+                    Variable::MultiplierOutput(i) => self.a_o[*i],
+                    Variable::One => Scalar::ONE,
+                };
+                acc + value * *coeff
+            })
+    }
+
+    fn push_gate(&mut self, left: Scalar<C>, right: Scalar<C>) -> (Variable, Variable, Variable) {
+        let index = self.a_l.len();
+        self.a_l.push(left);
+        self.a_r.push(right);
+        self.a_o.push(left * right);
+        (
+            Variable::MultiplierLeft(index),
+            Variable::MultiplierRight(index),
+            Variable::MultiplierOutput(index),
+        )
+    }
+
+    pub fn prove<R: CryptoRng + RngCore>(mut self, rng: &mut R) -> Result<R1csProof<C>, Error> {
+        let n = self.a_l.len();
+        let padded_n = n.next_power_of_two();
+        if padded_n > self.generators.capacity() || self.input.len() > n {
+            return Err(ParameterError::NotEnoughGenerators.into());
+        }
+        let g_vec = &self.generators.g_vec[..padded_n];
+        let h_vec = &self.generators.h_vec[..padded_n];
+        let blinding_generator = self.generators.blinding().generator();
+
+        let i_blinding = Scalar::random(rng);
+        let o_blinding = Scalar::random(rng);
+        let s_blinding = Scalar::random(rng);
+        let s_l: Vec<Scalar<C>> = (0..n).map(|_| Scalar::random(rng)).collect();
+        let s_r: Vec<Scalar<C>> = (0..n).map(|_| Scalar::random(rng)).collect();
+
+        // the left wires bound to the vector commitment are left out of A_I,
+        // the verifier adds the vector commitment itself
+        let mut a_i = MultiMult::new();
+        let mut a_o = MultiMult::new();
+        let mut s = MultiMult::new();
+        a_i.insert(blinding_generator, i_blinding);
+        a_o.insert(blinding_generator, o_blinding);
+        s.insert(blinding_generator, s_blinding);
+        for i in 0..n {
+            if i >= self.input.len() && self.a_l[i] != Scalar::ZERO {
+                a_i.insert(g_vec[i], self.a_l[i]);
+            }
+            if self.a_r[i] != Scalar::ZERO {
+                a_i.insert(h_vec[i], self.a_r[i]);
+            }
+            if self.a_o[i] != Scalar::ZERO {
+                a_o.insert(g_vec[i], self.a_o[i]);
+            }
+            s.insert(g_vec[i], s_l[i]);
+            s.insert(h_vec[i], s_r[i]);
+        }
+        let a_i = a_i.evaluate();
+        let a_o = a_o.evaluate();
+        let s = s.evaluate();
+
+        self.transcript.append_point(&a_i);
+        self.transcript.append_point(&a_o);
+        self.transcript.append_point(&s);
+        let y = self.transcript.challenge::<C>();
+        let z = self.transcript.challenge::<C>();
+
+        let (w_l, w_r, w_o, w_v, _) = flatten(&self.constraints, n, self.v.len(), z);
+
+        let y_inv = y.inverse();
+        let mut exp_y = Scalar::<C>::ONE;
+        let mut exp_y_inv = Scalar::<C>::ONE;
+        let mut l_1 = Vec::with_capacity(n);
+        let mut r_0 = Vec::with_capacity(padded_n);
+        let mut r_1 = Vec::with_capacity(n);
+        let mut r_3 = Vec::with_capacity(n);
+        let mut h_factors = Vec::with_capacity(padded_n);
+        for i in 0..padded_n {
+            if i < n {
+                l_1.push(self.a_l[i] + exp_y_inv * w_r[i]);
+                r_0.push(w_o[i] - exp_y);
+                r_1.push(exp_y * self.a_r[i] + w_l[i]);
+                r_3.push(exp_y * s_r[i]);
+            } else {
+                r_0.push(-exp_y);
+            }
+            h_factors.push(exp_y_inv);
+            exp_y *= y;
+            exp_y_inv *= y_inv;
+        }
+        let l_2 = &self.a_o;
+        let l_3 = &s_l;
+
+        let t_1 = inner_product(&l_1, &r_0);
+        let t_2 = inner_product(&l_1, &r_1) + inner_product(l_2, &r_0);
+        let t_3 = inner_product(l_2, &r_1) + inner_product(l_3, &r_0);
+        let t_4 = inner_product(l_3, &r_1) + inner_product(&l_1, &r_3);
+        let t_5 = inner_product(l_2, &r_3);
+        let t_6 = inner_product(l_3, &r_3);
+
+        let t_blindings: Vec<Scalar<C>> = (0..5).map(|_| Scalar::random(rng)).collect();
+        let t_points: Vec<Point<C>> = [t_1, t_3, t_4, t_5, t_6]
+            .iter()
+            .zip(&t_blindings)
+            .map(|(t, blinding)| {
+                self.generators
+                    .blinding()
+                    .commit_with_randomness(*t, *blinding)
+                    .commitment()
+            })
+            .collect();
+        for point in &t_points {
+            self.transcript.append_point(point);
+        }
+        let x = self.transcript.challenge::<C>();
+
+        let x2 = x * x;
+        let x3 = x2 * x;
+        let x4 = x3 * x;
+        let x5 = x4 * x;
+        let x6 = x5 * x;
+        let t_x = t_1 * x + t_2 * x2 + t_3 * x3 + t_4 * x4 + t_5 * x5 + t_6 * x6;
+        let t_x_blinding = t_blindings[0] * x
+            + t_blindings[1] * x3
+            + t_blindings[2] * x4
+            + t_blindings[3] * x5
+            + t_blindings[4] * x6
+            + x2 * inner_product(&w_v, &self.v_blinding);
+        let e_blinding = (i_blinding + self.input_blinding) * x + o_blinding * x2 + s_blinding * x3;
+
+        let mut l_vec = vec![Scalar::<C>::ZERO; padded_n];
+        let mut r_vec = r_0;
+        for i in 0..n {
+            l_vec[i] = l_1[i] * x + l_2[i] * x2 + l_3[i] * x3;
+            r_vec[i] += r_1[i] * x + r_3[i] * x3;
+        }
+
+        self.transcript.append_scalar(&t_x);
+        self.transcript.append_scalar(&t_x_blinding);
+        self.transcript.append_scalar(&e_blinding);
+        let w = self.transcript.challenge::<C>();
+        let q = Point::<C>::GENERATOR * w;
+
+        let ipp = InnerProductProof::construct(
+            &mut self.transcript,
+            &q,
+            g_vec,
+            h_vec,
+            h_factors,
+            l_vec,
+            r_vec,
+        );
+
+        Ok(R1csProof {
+            a_i,
+            a_o,
+            s,
+            t_1: t_points[0],
+            t_3: t_points[1],
+            t_4: t_points[2],
+            t_5: t_points[3],
+            t_6: t_points[4],
+            t_x,
+            t_x_blinding,
+            e_blinding,
+            ipp,
+        })
+    }
+}
+
+impl<'a, C: Curve> ConstraintSystem<C> for Prover<'a, C> {
+    fn input_multiplier(
+        &mut self,
+        index: usize,
+        right: Option<Scalar<C>>,
+    ) -> Result<(Variable, Variable, Variable), CircuitError> {
+        if index != self.a_l.len() || index >= self.input.len() {
+            return Err(CircuitError::InvalidInputGate);
+        }
+        let right = right.ok_or(CircuitError::MissingAssignment)?;
+        Ok(self.push_gate(self.input[index], right))
+    }
+
+    fn allocate_multiplier(
+        &mut self,
+        assignment: Option<(Scalar<C>, Scalar<C>)>,
+    ) -> Result<(Variable, Variable, Variable), CircuitError> {
+        let (left, right) = assignment.ok_or(CircuitError::MissingAssignment)?;
+        Ok(self.push_gate(left, right))
+    }
+
+    fn multiply(
+        &mut self,
+        left: LinearCombination<C>,
+        right: LinearCombination<C>,
+    ) -> (Variable, Variable, Variable) {
+        let gate = self.push_gate(self.eval(&left), self.eval(&right));
+        self.constrain(left - gate.0);
+        self.constrain(right - gate.1);
+        gate
+    }
+
+    fn constrain(&mut self, lc: LinearCombination<C>) {
+        self.constraints.push(lc);
+    }
+}
+
+/// Flattens the constraints into the weight vectors `w_L`, `w_R`, `w_O`,
+/// `w_V` and the constant `w_c`, weighting the `q`-th constraint with
+/// `z^(q + 1)`, so that the constraints are satisfied iff (with high
+/// probability) `<w_L, a_L> + <w_R, a_R> + <w_O, a_O> = <w_V, v> + w_c`.
+#[allow(clippy::type_complexity)]
+pub(super) fn flatten<C: Curve>(
+    constraints: &[LinearCombination<C>],
+    n: usize,
+    m: usize,
+    z: Scalar<C>,
+) -> (
+    Vec<Scalar<C>>,
+    Vec<Scalar<C>>,
+    Vec<Scalar<C>>,
+    Vec<Scalar<C>>,
+    Scalar<C>,
+) {
+    let mut w_l = vec![Scalar::<C>::ZERO; n];
+    let mut w_r = vec![Scalar::<C>::ZERO; n];
+    let mut w_o = vec![Scalar::<C>::ZERO; n];
+    let mut w_v = vec![Scalar::<C>::ZERO; m];
+    let mut w_c = Scalar::<C>::ZERO;
+
+    let mut exp_z = z;
+    for lc in constraints {
+        for (variable, coeff) in lc.terms() {
+            let weight = exp_z * *coeff;
+            match variable {
+                Variable::MultiplierLeft(i) => w_l[*i] += weight,
+                Variable::MultiplierRight(i) => w_r[*i] += weight,
+                Variable::MultiplierOutput(i) => w_o[*i] += weight,
+                Variable::Committed(i) => w_v[*i] -= weight,
+                Variable::One => w_c -= weight,
+            }
+        }
+        exp_z *= z;
+    }
+
+    (w_l, w_r, w_o, w_v, w_c)
+}
@@ -0,0 +1,191 @@
+use super::prover::flatten;
+use super::{ConstraintSystem, LinearCombination, R1csGenerators, R1csProof, Variable};
+use crate::arithmetic::multimult::{MultiMult, Relation};
+use crate::arithmetic::{Modular, Point, Scalar};
+use crate::curve::Curve;
+use crate::error::{CircuitError, Error, ParameterError};
+use crate::hasher::Transcript;
+
+use rand_core::{CryptoRng, RngCore};
+
+pub struct Verifier<'a, C: Curve> {
+    generators: &'a R1csGenerators<C>,
+    transcript: Transcript,
+    constraints: Vec<LinearCombination<C>>,
+    num_gates: usize,
+    v: Vec<Point<C>>,
+    input: Point<C>,
+    input_len: usize,
+}
+
+impl<'a, C: Curve> Verifier<'a, C> {
+    pub fn new(generators: &'a R1csGenerators<C>, transcript: Transcript) -> Self {
+        Self {
+            generators,
+            transcript,
+            constraints: Vec::new(),
+            num_gates: 0,
+            v: Vec::new(),
+            input: Point::IDENTITY,
+            input_len: 0,
+        }
+    }
+
+    pub fn commit(&mut self, commitment: Point<C>) -> Variable {
+        self.transcript.append_point(&commitment);
+        self.v.push(commitment);
+        Variable::Committed(self.v.len() - 1)
+    }
+
+    pub fn vector_input(&mut self, commitment: Point<C>, len: usize) {
+        self.transcript.append_u64(len as u64);
+        self.transcript.append_point(&commitment);
+        self.input = commitment;
+        self.input_len = len;
+    }
+
+    fn push_gate(&mut self) -> (Variable, Variable, Variable) {
+        let index = self.num_gates;
+        self.num_gates += 1;
+        (
+            Variable::MultiplierLeft(index),
+            Variable::MultiplierRight(index),
+            Variable::MultiplierOutput(index),
+        )
+    }
+
+    pub fn verify<R: CryptoRng + RngCore>(
+        mut self,
+        rng: &mut R,
+        proof: &R1csProof<C>,
+    ) -> Result<(), Error> {
+        let n = self.num_gates;
+        let padded_n = n.next_power_of_two();
+        if padded_n > self.generators.capacity() || self.input_len > n {
+            return Err(ParameterError::NotEnoughGenerators.into());
+        }
+        let blinding_generator = self.generators.blinding().generator();
+
+        self.transcript.append_point(&proof.a_i);
+        self.transcript.append_point(&proof.a_o);
+        self.transcript.append_point(&proof.s);
+        let y = self.transcript.challenge::<C>();
+        let z = self.transcript.challenge::<C>();
+
+        let (w_l, w_r, w_o, w_v, w_c) = flatten(&self.constraints, n, self.v.len(), z);
+
+        for point in [&proof.t_1, &proof.t_3, &proof.t_4, &proof.t_5, &proof.t_6] {
+            self.transcript.append_point(point);
+        }
+        let x = self.transcript.challenge::<C>();
+
+        self.transcript.append_scalar(&proof.t_x);
+        self.transcript.append_scalar(&proof.t_x_blinding);
+        self.transcript.append_scalar(&proof.e_blinding);
+        let w = self.transcript.challenge::<C>();
+
+        let ipp = &proof.ipp;
+        let scalars = ipp.verification_scalars(&mut self.transcript, padded_n)?;
+
+        let x2 = x * x;
+        let x3 = x2 * x;
+        let y_inv = y.inverse();
+
+        // P + t_x * Q + sum_j (u_j^2 * L_j + u_j^-2 * R_j)
+        //     = a * <s, G> + b * <s^-1, H'> + a * b * Q
+        let mut ipp_relation = Relation::new();
+        ipp_relation.insert(proof.a_i, x);
+        ipp_relation.insert(self.input, x);
+        ipp_relation.insert(proof.a_o, x2);
+        ipp_relation.insert(proof.s, x3);
+        ipp_relation.insert(blinding_generator, -proof.e_blinding);
+        ipp_relation.insert(Point::GENERATOR, w * (proof.t_x - ipp.a() * ipp.b()));
+        let mut exp_y_inv = Scalar::<C>::ONE;
+        let mut delta = Scalar::<C>::ZERO;
+        for i in 0..padded_n {
+            let (w_l, w_r, w_o) = if i < n {
+                (w_l[i], w_r[i], w_o[i])
+            } else {
+                (Scalar::ZERO, Scalar::ZERO, Scalar::ZERO)
+            };
+            delta += exp_y_inv * w_r * w_l;
+            ipp_relation.insert(
+                self.generators.g_vec[i],
+                x * exp_y_inv * w_r - ipp.a() * scalars.s[i],
+            );
+            ipp_relation.insert(
+                self.generators.h_vec[i],
+                exp_y_inv * (w_o + x * w_l - ipp.b() * scalars.s_inv[i]) - Scalar::ONE,
+            );
+            exp_y_inv *= y_inv;
+        }
+        for ((l, r), (u_sq, u_inv_sq)) in ipp
+            .l_vec()
+            .iter()
+            .zip(ipp.r_vec())
+            .zip(&scalars.challenges_sq)
+        {
+            ipp_relation.insert(*l, *u_sq);
+            ipp_relation.insert(*r, *u_inv_sq);
+        }
+
+        // t_x * B + t_x_blinding * H
+        //     = x^2 * (<w_V, V> + (w_c + delta) * B) + sum_i x^i * T_i
+        let mut t_relation = Relation::new();
+        for (commitment, weight) in self.v.iter().zip(&w_v) {
+            t_relation.insert(*commitment, x2 * *weight);
+        }
+        t_relation.insert(Point::GENERATOR, x2 * (w_c + delta) - proof.t_x);
+        t_relation.insert(blinding_generator, -proof.t_x_blinding);
+        t_relation.insert(proof.t_1, x);
+        t_relation.insert(proof.t_3, x3);
+        t_relation.insert(proof.t_4, x3 * x);
+        t_relation.insert(proof.t_5, x3 * x2);
+        t_relation.insert(proof.t_6, x3 * x3);
+
+        let mut multimult = MultiMult::new();
+        ipp_relation.drain(rng, &mut multimult);
+        t_relation.drain(rng, &mut multimult);
+
+        if multimult.evaluate() == Point::IDENTITY {
+            Ok(())
+        } else {
+            Err(CircuitError::VerificationFailed.into())
+        }
+    }
+}
+
+impl<'a, C: Curve> ConstraintSystem<C> for Verifier<'a, C> {
+    fn input_multiplier(
+        &mut self,
+        index: usize,
+        _right: Option<Scalar<C>>,
+    ) -> Result<(Variable, Variable, Variable), CircuitError> {
+        if index != self.num_gates || index >= self.input_len {
+            return Err(CircuitError::InvalidInputGate);
+        }
+        Ok(self.push_gate())
+    }
+
+    fn allocate_multiplier(
+        &mut self,
+        _assignment: Option<(Scalar<C>, Scalar<C>)>,
+    ) -> Result<(Variable, Variable, Variable), CircuitError> {
+        Ok(self.push_gate())
+    }
+
+    fn multiply(
+        &mut self,
+        left: LinearCombination<C>,
+        right: LinearCombination<C>,
+    ) -> (Variable, Variable, Variable) {
+        let gate = self.push_gate();
+        self.constrain(left - gate.0);
+        self.constrain(right - gate.1);
+        gate
+    }
+
+    fn constrain(&mut self, lc: LinearCombination<C>) {
+        self.constraints.push(lc);
+    }
+}
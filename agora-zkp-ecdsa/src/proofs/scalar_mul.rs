@@ -0,0 +1,248 @@
+//! Succinct alternative of the [`ExpProof`](super::ExpProof).
+//!
+//! Proves knowledge of `s` such that `P = s * R - Q` for public points `R`
+//! and `Q`, where the x-coordinate of `P` is committed on the cycle curve.
+//! Instead of repeating a cut-and-choose protocol, the scalar multiplication
+//! is computed in a single arithmetic circuit over the cycle curve, so the
+//! size of the proof is logarithmic in the number of gates.
+use super::r1cs::gadgets::{
+    allocate_point, fixed_base_mul, FixedBaseTable, POINT_GATES, SCALAR_MUL_GATES,
+};
+use super::r1cs::{ConstraintSystem, LinearCombination, Prover, R1csGenerators, R1csProof};
+use super::r1cs::{Variable, Verifier};
+use crate::arithmetic::{AffinePoint, Point, Scalar};
+use crate::curve::{Curve, Cycle};
+use crate::error::{CircuitError, Error};
+use crate::hasher::Transcript;
+use crate::pedersen::{PedersenCommitment, PedersenGenerator};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use rand_core::{CryptoRng, RngCore};
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+const GENERATOR_CAPACITY: usize = POINT_GATES + SCALAR_MUL_GATES;
+
+/// Public parameters of [`ScalarMulProof`]s.
+///
+/// All generators are derived deterministically, so the parameters can be
+/// regenerated by anyone.
+pub struct ScalarMulParameters<CC: Curve> {
+    generators: R1csGenerators<CC>,
+}
+
+impl<CC: Curve> ScalarMulParameters<CC> {
+    const LABEL: &'static [u8] = b"scalar-mul";
+
+    pub fn new() -> Self {
+        Self {
+            generators: R1csGenerators::new(Self::LABEL, GENERATOR_CAPACITY),
+        }
+    }
+
+    /// Parameters derived once per curve and shared by every proof, as
+    /// hashing the generators to the curve is slow.
+    pub fn shared() -> &'static Self {
+        type Shared = HashMap<TypeId, &'static (dyn Any + Send + Sync)>;
+        static SHARED: OnceLock<Mutex<Shared>> = OnceLock::new();
+        let mut shared = SHARED
+            .get_or_init(Default::default)
+            .lock()
+            .expect("poisoned parameters");
+        let parameters = *shared
+            .entry(TypeId::of::<CC>())
+            .or_insert_with(|| Box::leak(Box::new(Self::new())));
+        parameters
+            .downcast_ref()
+            .expect("parameters are keyed by their curve")
+    }
+
+    /// Pedersen generator that has to blind the commitment to the
+    /// x-coordinate. It is derived without the rest of the parameters.
+    pub fn pedersen_generator() -> PedersenGenerator<CC> {
+        R1csGenerators::blinding_generator(Self::LABEL)
+    }
+}
+
+impl<CC: Curve> Default for ScalarMulParameters<CC> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Debug, BorshDeserialize, BorshSerialize)]
+pub struct ScalarMulProof<CC: Curve> {
+    proof: R1csProof<CC>,
+}
+
+impl<CC: Curve> ScalarMulProof<CC> {
+    const TABLE_LABEL: &'static [u8] = b"scalar-mul-offset";
+
+    /// Proves that `point = exp * base - q_point` and that `commitment_to_x`
+    /// commits to the x-coordinate of `point`.
+    pub fn construct<R, C>(
+        rng: &mut R,
+        parameters: &ScalarMulParameters<CC>,
+        base: &Point<C>,
+        q_point: &Point<C>,
+        point: AffinePoint<C>,
+        exp: Scalar<C>,
+        commitment_to_x: &PedersenCommitment<CC>,
+    ) -> Result<Self, Error>
+    where
+        R: CryptoRng + RngCore,
+        C: Curve,
+        CC: Cycle<C>,
+    {
+        let table = FixedBaseTable::new(Self::TABLE_LABEL, base);
+        let mut prover = Prover::new(&parameters.generators, transcript(base, q_point));
+        let (_, x) = prover.commit(point.x().to_cycle_scalar(), commitment_to_x.randomness());
+        gadget(&mut prover, &table, x, Some((point, exp)), q_point)?;
+        let proof = prover.prove(rng)?;
+        Ok(Self { proof })
+    }
+
+    pub fn verify<R, C>(
+        &self,
+        rng: &mut R,
+        parameters: &ScalarMulParameters<CC>,
+        base: &Point<C>,
+        q_point: &Point<C>,
+        commitment_to_x: &Point<CC>,
+    ) -> Result<(), Error>
+    where
+        R: CryptoRng + RngCore,
+        C: Curve,
+        CC: Cycle<C>,
+    {
+        let table = FixedBaseTable::new(Self::TABLE_LABEL, base);
+        let mut verifier = Verifier::new(&parameters.generators, transcript(base, q_point));
+        let x = verifier.commit(*commitment_to_x);
+        gadget(&mut verifier, &table, x, None, q_point)?;
+        verifier.verify(rng, &self.proof)
+    }
+}
+
+fn transcript<C: Curve>(base: &Point<C>, q_point: &Point<C>) -> Transcript {
+    let mut transcript = Transcript::new(b"scalar-mul");
+    transcript.append_point(base);
+    transcript.append_point(q_point);
+    transcript
+}
+
+// Starts from -P, whose x-coordinate is the committed one, and adds s * R,
+// which has to result in Q.
+fn gadget<P, Ch, CS>(
+    cs: &mut CS,
+    table: &FixedBaseTable<P, Ch>,
+    x: Variable,
+    witness: Option<(AffinePoint<Ch>, Scalar<Ch>)>,
+    q_point: &Point<Ch>,
+) -> Result<(), CircuitError>
+where
+    P: Cycle<Ch>,
+    Ch: Curve,
+    CS: ConstraintSystem<P>,
+{
+    let start = witness.map(|(point, exp)| (-point, exp));
+    let (start_x, start_y) = allocate_point(cs, start.as_ref().map(|(point, _)| point))?;
+    cs.constrain(LinearCombination::from(start_x) - x);
+    fixed_base_mul(cs, table, (start_x, start_y), start, q_point)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::arithmetic::Modular;
+    use crate::curve::{Secp256k1, Tom256k1};
+    use crate::U256;
+    use rand::rngs::StdRng;
+    use rand_core::SeedableRng;
+
+    #[test]
+    fn scalar_mul_proof() {
+        let mut rng = StdRng::from_seed([38; 32]);
+        let parameters = ScalarMulParameters::<Tom256k1>::new();
+        let base = Point::<Secp256k1>::GENERATOR * Scalar::random(&mut rng);
+        let q_point = Point::<Secp256k1>::GENERATOR * Scalar::random(&mut rng);
+        let exp = Scalar::<Secp256k1>::random(&mut rng);
+        let point = (&(base * exp) - &q_point.to_affine()).to_affine();
+
+        let commitment_to_x =
+            ScalarMulParameters::pedersen_generator().commit(&mut rng, point.x().to_cycle_scalar());
+        let proof = ScalarMulProof::construct(
+            &mut rng,
+            &parameters,
+            &base,
+            &q_point,
+            point,
+            exp,
+            &commitment_to_x,
+        )
+        .unwrap();
+        assert!(proof
+            .verify(
+                &mut rng,
+                &parameters,
+                &base,
+                &q_point,
+                &commitment_to_x.commitment()
+            )
+            .is_ok());
+
+        // a commitment to another x-coordinate
+        let other =
+            ScalarMulParameters::pedersen_generator().commit(&mut rng, point.y().to_cycle_scalar());
+        assert_eq!(
+            proof.verify(&mut rng, &parameters, &base, &q_point, &other.commitment()),
+            Err(CircuitError::VerificationFailed.into())
+        );
+        // another Q
+        assert_eq!(
+            proof.verify(
+                &mut rng,
+                &parameters,
+                &base,
+                &(q_point + base.to_affine()),
+                &commitment_to_x.commitment()
+            ),
+            Err(CircuitError::VerificationFailed.into())
+        );
+    }
+
+    #[test]
+    fn exceptional_base() {
+        let mut rng = StdRng::from_seed([39; 32]);
+        let q_point = Point::<Secp256k1>::GENERATOR * Scalar::random(&mut rng);
+        let exp = Scalar::<Secp256k1>::random(&mut rng);
+        // R is chosen by the prover, so it can make the start point -P equal
+        // to the table entry of the first window: with `d` the first digit of
+        // the exponent, `Q - exp * R = K_0 + d * R`
+        let mut input = ScalarMulProof::<Tom256k1>::TABLE_LABEL.to_vec();
+        input.extend_from_slice(&0u64.to_be_bytes());
+        let offset = Point::<Secp256k1>::hash_to_curve(&input);
+        let digit = Scalar::<Secp256k1>::new(exp.inner() & U256::from_u8(3));
+        let base = (&q_point - &offset.to_affine()) * (exp + digit).inverse();
+        let point = (&(base * exp) - &q_point.to_affine()).to_affine();
+        // the honest prover cannot add the points and the circuit rejects
+        // any slope for them, see `exceptional_addition`
+        let parameters = ScalarMulParameters::<Tom256k1>::shared();
+        let commitment_to_x =
+            ScalarMulParameters::pedersen_generator().commit(&mut rng, point.x().to_cycle_scalar());
+        assert_eq!(
+            ScalarMulProof::construct(
+                &mut rng,
+                parameters,
+                &base,
+                &q_point,
+                point,
+                exp,
+                &commitment_to_x,
+            )
+            .err(),
+            Some(CircuitError::DegenerateRerandomization.into())
+        );
+    }
+}
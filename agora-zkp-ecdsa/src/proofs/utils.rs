@@ -1,24 +1,80 @@
 use crate::arithmetic::Scalar;
 use crate::curve::Curve;
+use crate::error::ParameterError;
 
-pub fn pad_ring_to_2n<C: Curve>(ring: &mut Vec<Scalar<C>>) -> Result<usize, String> {
-    // TODO ensure that the ring is not empty
-    if ring.is_empty() {
-        Err("empty ring".to_string())
+/// Largest base accepted for the n-ary membership proof.
+pub const MAX_BASE: usize = 16;
+
+// rough cost of a scalar multiplication measured in field multiplications
+const SCALAR_MUL_COST: usize = 4000;
+const POINT_SIZE: usize = 96;
+const SCALAR_SIZE: usize = 32;
+
+/// The quantity the base of a membership proof is chosen to minimise.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Optimize {
+    ProofSize,
+    ProverTime,
+}
+
+pub fn check_base(base: usize) -> Result<(), ParameterError> {
+    if (2..=MAX_BASE).contains(&base) {
+        Ok(())
     } else {
-        let log_2_ring_len = ring.len().ilog2();
-        let pow_2_ring_len = 2usize.pow(log_2_ring_len);
-        // pow_2_ring_len is always less than or equal to keys.len()
-        // because log2 always rounds down
-        if ring.len() == pow_2_ring_len {
-            Ok(log_2_ring_len as usize)
-        } else {
-            for _ in 0..pow_2_ring_len * 2 - ring.len() {
-                ring.push(ring[0]);
-            }
-            Ok((log_2_ring_len + 1) as usize)
-        }
+        Err(ParameterError::InvalidBase)
+    }
+}
+
+/// Number of base `base` digits required to index a ring of `ring_len` keys.
+pub fn digit_count(ring_len: usize, base: usize) -> usize {
+    let mut digits = 0;
+    let mut capacity = 1usize;
+    while capacity < ring_len {
+        capacity = capacity.saturating_mul(base);
+        digits += 1;
+    }
+    digits
+}
+
+/// Pads the ring to `base^m` elements by repeating its first element and
+/// returns `m`.
+pub fn pad_ring_to_nm<C: Curve>(
+    ring: &mut Vec<Scalar<C>>,
+    base: usize,
+) -> Result<usize, ParameterError> {
+    if ring.is_empty() {
+        return Err(ParameterError::EmptyRing);
     }
+    check_base(base)?;
+    let digits = digit_count(ring.len(), base);
+    let padded_len = base.pow(digits as u32);
+    ring.resize(padded_len, ring[0]);
+    Ok(digits)
+}
+
+/// Selects the base of the n-ary membership proof for a ring of `ring_len`
+/// keys.
+///
+/// A larger base means less padding and fewer digits, but more commitments
+/// per digit. Ties are broken in favour of the smaller base.
+pub fn optimal_base(ring_len: usize, optimize: Optimize) -> usize {
+    // NOTE unwrap is fine because the range is not empty
+    (2..=MAX_BASE)
+        .min_by_key(|&base| {
+            let digits = digit_count(ring_len, base);
+            let bit_checks = digits * (base - 1) + if base > 2 { digits } else { 0 };
+            let points = 2 * digits * (base - 1) + bit_checks + digits;
+            let scalars = 2 * digits * (base - 1) + bit_checks + 1;
+            match optimize {
+                Optimize::ProofSize => points * POINT_SIZE + scalars * SCALAR_SIZE,
+                Optimize::ProverTime => {
+                    // the polynomial is evaluated over the padded ring at
+                    // `digits` points
+                    2 * digits * base.pow(digits as u32) + points * SCALAR_MUL_COST
+                }
+            }
+        })
+        .unwrap()
 }
 
 #[cfg(test)]
@@ -31,23 +87,45 @@ mod test {
     #[test]
     fn pad_ring() {
         let mut ring = Vec::<TomScalar>::new();
-        assert!(pad_ring_to_2n(&mut ring).is_err());
+        assert!(pad_ring_to_nm(&mut ring, 2).is_err());
         ring.push(TomScalar::ONE);
-        assert_eq!(pad_ring_to_2n(&mut ring), Ok(0));
+        assert_eq!(pad_ring_to_nm(&mut ring, 2), Ok(0));
         assert_eq!(ring.len(), 1);
         ring.push(TomScalar::ZERO);
-        assert_eq!(pad_ring_to_2n(&mut ring), Ok(1));
+        assert_eq!(pad_ring_to_nm(&mut ring, 2), Ok(1));
         assert_eq!(ring.len(), 2);
         ring.push(TomScalar::ZERO);
-        assert_eq!(pad_ring_to_2n(&mut ring), Ok(2));
+        assert_eq!(pad_ring_to_nm(&mut ring, 2), Ok(2));
         assert_eq!(ring.len(), 4);
         assert_eq!(ring[3], TomScalar::ONE);
         for _ in 0..5 {
             ring.push(TomScalar::ZERO);
         }
         assert_eq!(ring.len(), 9);
-        assert_eq!(pad_ring_to_2n(&mut ring), Ok(4));
+        assert_eq!(pad_ring_to_nm(&mut ring.clone(), 3), Ok(2));
+        assert_eq!(pad_ring_to_nm(&mut ring, 2), Ok(4));
         assert_eq!(ring.len(), 16);
         assert_eq!(ring[15], TomScalar::ONE);
+
+        ring.push(TomScalar::ZERO);
+        assert_eq!(pad_ring_to_nm(&mut ring, 3), Ok(3));
+        assert_eq!(ring.len(), 27);
+        assert_eq!(ring[26], TomScalar::ONE);
+
+        assert!(pad_ring_to_nm(&mut ring, 1).is_err());
+        assert!(pad_ring_to_nm(&mut ring, MAX_BASE + 1).is_err());
+    }
+
+    #[test]
+    fn base_selection() {
+        assert_eq!(digit_count(1, 2), 0);
+        assert_eq!(digit_count(1025, 2), 11);
+        assert_eq!(digit_count(1025, 4), 6);
+        assert_eq!(digit_count(32769, 8), 6);
+
+        assert_eq!(optimal_base(1, Optimize::ProverTime), 2);
+        assert_eq!(optimal_base(7, Optimize::ProverTime), 2);
+        assert_eq!(optimal_base(5000, Optimize::ProofSize), 2);
+        assert!(optimal_base(32769, Optimize::ProverTime) > 2);
     }
 }
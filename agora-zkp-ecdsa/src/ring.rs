@@ -0,0 +1,232 @@
+//! Canonical ring digests and a registry of preprocessed rings.
+//!
+//! The digest of a ring is the Keccak Merkle root of its sorted and
+//! deduplicated keys, so it does not depend on the order in which the keys
+//! were collected. Membership proofs are generated on the canonical order of
+//! the ring and commit to its digest, which lets a verifier look the ring up
+//! by digest instead of trusting the prover to supply the right one.
+use crate::arithmetic::{Modular, Scalar};
+use crate::curve::Curve;
+use crate::error::{ParameterError, ParseError};
+use crate::parse::to_hex;
+use crate::proofs::{optimal_base, pad_ring_to_nm, Optimize};
+
+use bigint::Encoding;
+use borsh::{BorshDeserialize, BorshSerialize};
+use sha3::{Digest, Keccak256};
+
+use std::collections::hash_map::{Entry, HashMap};
+
+const LEAF_PREFIX: u8 = 0x00;
+const NODE_PREFIX: u8 = 0x01;
+
+/// Keccak Merkle root of a canonical ring.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, BorshDeserialize, BorshSerialize)]
+pub struct RingDigest([u8; 32]);
+
+impl RingDigest {
+    /// Computes the digest of an arbitrarily ordered ring.
+    pub fn new<C: Curve>(ring: &[Scalar<C>]) -> Self {
+        Self::of_canonical(&canonical_ring(ring))
+    }
+
+    /// Computes the digest of a ring that is already sorted and deduplicated.
+    ///
+    /// Leaves and inner nodes are hashed with different prefixes, an odd node
+    /// at the end of a level is carried to the next level unchanged.
+    pub fn of_canonical<C: Curve>(ring: &[Scalar<C>]) -> Self {
+        let mut level: Vec<[u8; 32]> = ring
+            .iter()
+            .map(|key| {
+                let mut hasher = Keccak256::new();
+                hasher.update([LEAF_PREFIX]);
+                hasher.update(key.inner().to_be_bytes());
+                hasher.finalize().into()
+            })
+            .collect();
+        if level.is_empty() {
+            return Self(Keccak256::digest([LEAF_PREFIX]).into());
+        }
+        while level.len() > 1 {
+            level = level
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => {
+                        let mut hasher = Keccak256::new();
+                        hasher.update([NODE_PREFIX]);
+                        hasher.update(left);
+                        hasher.update(right);
+                        hasher.finalize().into()
+                    }
+                    [single] => *single,
+                    _ => unreachable!(),
+                })
+                .collect();
+        }
+        Self(level[0])
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    pub fn to_hex(&self) -> String {
+        to_hex(&self.0)
+    }
+
+    pub fn from_hex(hex: &str) -> Result<Self, ParseError> {
+        let stripped = hex.trim_start_matches("0x");
+        if stripped.len() != 64 || !stripped.is_ascii() {
+            return Err(ParseError::InvalidRingDigest);
+        }
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&stripped[2 * i..2 * i + 2], 16)
+                .map_err(|_| ParseError::InvalidRingDigest)?;
+        }
+        Ok(Self(bytes))
+    }
+}
+
+/// Sorts the keys of the ring in ascending order and removes duplicates.
+pub fn canonical_ring<C: Curve>(ring: &[Scalar<C>]) -> Vec<Scalar<C>> {
+    let mut canonical = ring.to_vec();
+    canonical.sort_by(|a, b| a.inner().cmp(b.inner()));
+    canonical.dedup();
+    canonical
+}
+
+/// A ring in canonical order together with its padded form for the base
+/// that proofs generated on it use by default.
+#[derive(Clone, Debug)]
+pub struct RegisteredRing<C: Curve> {
+    keys: Vec<Scalar<C>>,
+    base: usize,
+    padded: Vec<Scalar<C>>,
+}
+
+impl<C: Curve> RegisteredRing<C> {
+    fn new(keys: Vec<Scalar<C>>) -> Result<Self, ParameterError> {
+        let base = optimal_base(keys.len(), Optimize::ProverTime);
+        let mut padded = keys.clone();
+        pad_ring_to_nm(&mut padded, base)?;
+        Ok(Self { keys, base, padded })
+    }
+
+    pub fn keys(&self) -> &[Scalar<C>] {
+        &self.keys
+    }
+
+    /// Returns the ring to verify a membership proof with the given base on.
+    ///
+    /// Padding is idempotent for the same base, so the preprocessed ring is
+    /// returned when the base matches.
+    pub fn for_base(&self, base: usize) -> &[Scalar<C>] {
+        if base == self.base {
+            &self.padded
+        } else {
+            &self.keys
+        }
+    }
+}
+
+/// Rings known to the verifier, indexed by their digest.
+#[derive(Clone, Debug)]
+pub struct RingRegistry<C: Curve> {
+    rings: HashMap<RingDigest, RegisteredRing<C>>,
+}
+
+impl<C: Curve> Default for RingRegistry<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: Curve> RingRegistry<C> {
+    pub fn new() -> Self {
+        Self {
+            rings: HashMap::new(),
+        }
+    }
+
+    /// Registers the ring and returns its digest.
+    pub fn insert(&mut self, ring: &[Scalar<C>]) -> Result<RingDigest, ParameterError> {
+        let keys = canonical_ring(ring);
+        let digest = RingDigest::of_canonical(&keys);
+        if let Entry::Vacant(entry) = self.rings.entry(digest) {
+            entry.insert(RegisteredRing::new(keys)?);
+        }
+        Ok(digest)
+    }
+
+    pub fn get(&self, digest: &RingDigest) -> Option<&RegisteredRing<C>> {
+        self.rings.get(digest)
+    }
+
+    pub fn contains(&self, digest: &RingDigest) -> bool {
+        self.rings.contains_key(digest)
+    }
+
+    pub fn remove(&mut self, digest: &RingDigest) -> Option<RegisteredRing<C>> {
+        self.rings.remove(digest)
+    }
+
+    pub fn len(&self) -> usize {
+        self.rings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rings.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::curve::Tom256k1;
+    use crate::U256;
+
+    fn ring(values: &[u64]) -> Vec<Scalar<Tom256k1>> {
+        values
+            .iter()
+            .map(|v| Scalar::new(U256::from_u64(*v)))
+            .collect()
+    }
+
+    #[test]
+    fn digest_is_canonical() {
+        let digest = RingDigest::new(&ring(&[3, 1, 2]));
+        assert_eq!(digest, RingDigest::new(&ring(&[1, 2, 3])));
+        assert_eq!(digest, RingDigest::new(&ring(&[2, 3, 1, 3])));
+        assert_ne!(digest, RingDigest::new(&ring(&[1, 2])));
+        assert_ne!(digest, RingDigest::new(&ring(&[1, 2, 4])));
+        assert_ne!(RingDigest::new(&ring(&[1])), RingDigest::new(&ring(&[])));
+
+        let hex = digest.to_hex();
+        assert_eq!(RingDigest::from_hex(&hex).unwrap(), digest);
+        assert_eq!(RingDigest::from_hex(&format!("0x{}", hex)).unwrap(), digest);
+        assert!(RingDigest::from_hex(&hex[2..]).is_err());
+        assert!(RingDigest::from_hex(&hex.replace(&hex[..2], "zz")).is_err());
+    }
+
+    #[test]
+    fn ring_registry() {
+        let mut registry = RingRegistry::<Tom256k1>::new();
+        assert!(registry.is_empty());
+        assert!(registry.insert(&[]).is_err());
+
+        let digest = registry.insert(&ring(&[5, 4, 3, 2, 1])).unwrap();
+        assert_eq!(registry.insert(&ring(&[1, 2, 3, 4, 5])).unwrap(), digest);
+        assert_eq!(registry.len(), 1);
+
+        let registered = registry.get(&digest).unwrap();
+        assert_eq!(registered.keys(), ring(&[1, 2, 3, 4, 5]).as_slice());
+        let base = optimal_base(5, Optimize::ProverTime);
+        let mut padded = ring(&[1, 2, 3, 4, 5]);
+        pad_ring_to_nm(&mut padded, base).unwrap();
+        assert_eq!(registered.for_base(base), padded.as_slice());
+
+        assert!(registry.remove(&digest).is_some());
+        assert!(!registry.contains(&digest));
+    }
+}
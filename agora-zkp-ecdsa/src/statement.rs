@@ -0,0 +1,131 @@
+//! Messages a guild join is signed in.
+//!
+//! The signed message is never part of a proof, only its hash. Verifiers
+//! rebuild the message from the guild id and the [`StatementMode`] of the
+//! proof and check that its hash matches.
+use crate::arithmetic::{Modular, Scalar};
+use crate::curve::Curve;
+use crate::U256;
+
+use bigint::Encoding;
+use borsh::{BorshDeserialize, BorshSerialize};
+use sha3::{Digest, Keccak256};
+
+const MSG_PREFIX: &str = "\x19Ethereum Signed Message:\n";
+const JOIN_GUILD_MSG: &str = "#zkp/join.guild.xyz/";
+
+const JOIN_GUILD_TYPE: &str = "JoinGuild(string guildId)";
+
+/// EIP-712 domain of the typed `JoinGuild` message.
+///
+/// `version` is optional, the domain type only contains the fields that are
+/// present.
+#[derive(Clone, Debug, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub struct Eip712Domain {
+    pub name: String,
+    pub version: Option<String>,
+    pub chain_id: u64,
+    pub verifying_contract: [u8; 20],
+}
+
+impl Eip712Domain {
+    fn type_string(&self) -> &'static str {
+        if self.version.is_some() {
+            "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)"
+        } else {
+            "EIP712Domain(string name,uint256 chainId,address verifyingContract)"
+        }
+    }
+
+    pub fn separator(&self) -> [u8; 32] {
+        let mut hasher = Keccak256::new();
+        hasher.update(keccak(self.type_string().as_bytes()));
+        hasher.update(keccak(self.name.as_bytes()));
+        if let Some(version) = &self.version {
+            hasher.update(keccak(version.as_bytes()));
+        }
+        hasher.update(U256::from_u64(self.chain_id).to_be_bytes());
+        hasher.update([0u8; 12]);
+        hasher.update(self.verifying_contract);
+        hasher.finalize().into()
+    }
+}
+
+/// The way the join message is encoded and hashed before signing.
+#[derive(Clone, Debug, Default, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
+pub enum StatementMode {
+    /// EIP-191 `personal_sign` of `#zkp/join.guild.xyz/<guild id>`.
+    #[default]
+    PersonalSign,
+    /// EIP-712 `signTypedData` of `JoinGuild { guildId }` in the domain.
+    TypedData(Eip712Domain),
+}
+
+impl StatementMode {
+    /// The hash of the join message of the guild that is signed.
+    pub fn message_hash<C: Curve>(&self, guild_id: &str) -> Scalar<C> {
+        let hash = match self {
+            Self::PersonalSign => {
+                let msg = JOIN_GUILD_MSG.to_string() + guild_id;
+                let preimage = format!("{}{}{}", MSG_PREFIX, msg.len(), msg);
+                keccak(preimage.as_bytes())
+            }
+            Self::TypedData(domain) => {
+                let mut struct_hasher = Keccak256::new();
+                struct_hasher.update(keccak(JOIN_GUILD_TYPE.as_bytes()));
+                struct_hasher.update(keccak(guild_id.as_bytes()));
+                let struct_hash: [u8; 32] = struct_hasher.finalize().into();
+
+                let mut hasher = Keccak256::new();
+                hasher.update([0x19, 0x01]);
+                hasher.update(domain.separator());
+                hasher.update(struct_hash);
+                hasher.finalize().into()
+            }
+        };
+        Scalar::new(U256::from_be_bytes(hash))
+    }
+}
+
+fn keccak(bytes: &[u8]) -> [u8; 32] {
+    Keccak256::digest(bytes).into()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::curve::Secp256k1;
+    use crate::parse::{parse_hex, parse_msg_hash, to_hex};
+
+    #[test]
+    fn message_hashes() {
+        // domain of the example in EIP-712
+        let domain = Eip712Domain {
+            name: "Ether Mail".to_string(),
+            version: Some("1".to_string()),
+            chain_id: 1,
+            verifying_contract: parse_hex("0xCcCCccccCCCCcCCCCCCcCcCccCcCCCcCcccccccC")
+                .unwrap()
+                .try_into()
+                .unwrap(),
+        };
+        assert_eq!(
+            to_hex(&domain.separator()),
+            "f2cee375fa42b42143804025fc449deafd50cc031ca257e0b194a650a912090f"
+        );
+
+        assert_eq!(
+            StatementMode::PersonalSign.message_hash::<Secp256k1>("almafa"),
+            parse_msg_hash("0x9788117298a1450f6002d25f0c21d83bc6001681a2e5e31c748c0f55504b11e9")
+                .unwrap()
+        );
+
+        let typed = StatementMode::TypedData(Eip712Domain {
+            version: None,
+            ..domain
+        });
+        let hash = typed.message_hash::<Secp256k1>("almafa");
+        assert_ne!(hash, StatementMode::PersonalSign.message_hash("almafa"));
+        assert_ne!(hash, typed.message_hash("almafa2"));
+    }
+}
@@ -0,0 +1,262 @@
+//! Known-answer test vectors of [`ZkAttestProof`]s.
+//!
+//! Every vector is generated by
+//! [`construct_deterministic`](ZkAttestProof::construct_deterministic), so
+//! implementations of the prover can check their intermediate commitments and
+//! serialized proofs byte by byte, and implementations of the verifier can
+//! check that they accept every encoding of the proof. `agora-zkp vectors`
+//! writes the [`standard`] set, which is published in `vectors/zk_attest.json`.
+use crate::arithmetic::{Modular, Point, Scalar};
+use crate::curve::{Curve, Secp256k1, Tom256k1};
+use crate::error::{Error, ParseError};
+use crate::parse::{parse_hex, parse_ring, to_hex, ProofInput, Ring, Signature, TypedDataDomain};
+use crate::proofs::{ProofMode, ZkAttestProof};
+use crate::statement::StatementMode;
+use crate::U256;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use bigint::Encoding;
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+
+pub type Proof = ZkAttestProof<Secp256k1, Tom256k1>;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestVector {
+    pub name: String,
+    /// Hex encoded seed of the deterministic prover.
+    pub seed: String,
+    /// `exp` or `circuit`.
+    pub mode: String,
+    pub input: ProofInput,
+    pub ring: Ring,
+    pub intermediate: Intermediate,
+    pub encodings: ProofEncodings,
+}
+
+/// Public values computed while proving, all points are uncompressed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Intermediate {
+    pub pedersen_base_generator: String,
+    pub pedersen_cycle_generator: String,
+    pub r_point: String,
+    pub commitment_to_exp: String,
+    pub commitment_to_pk_x: String,
+    pub commitment_to_pk_y: String,
+    pub ring_digest: String,
+}
+
+/// The encodings accepted by `agora-zkp verify`, the JSON one is also the
+/// request body of `zkp-verifier`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofEncodings {
+    pub hex: String,
+    pub base64: String,
+    pub json: JsonProof,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsonProof {
+    pub proof: String,
+    pub guild_id: String,
+    pub ring_digest: String,
+}
+
+impl ProofEncodings {
+    pub fn new(proof: &Proof) -> Self {
+        // NOTE serializing into a vector never fails
+        let bytes = proof.try_to_vec().unwrap();
+        let hex = format!("0x{}", to_hex(&bytes));
+        Self {
+            base64: BASE64.encode(&bytes),
+            json: JsonProof {
+                proof: hex.clone(),
+                guild_id: proof.guild_id.clone(),
+                ring_digest: proof.ring_digest.to_hex(),
+            },
+            hex,
+        }
+    }
+
+    /// Decodes every encoding, failing unless they hold the same bytes.
+    pub fn decode(&self) -> Result<Proof, ParseError> {
+        let bytes = parse_hex(&self.hex)?;
+        let base64 = BASE64
+            .decode(&self.base64)
+            .map_err(|e| ParseError::InvalidEncoding(e.to_string()))?;
+        let json = parse_hex(&self.json.proof)?;
+        if base64 != bytes || json != bytes {
+            return Err(ParseError::InvalidEncoding(
+                "encodings of different proofs".to_string(),
+            ));
+        }
+        Proof::try_from_slice(&bytes).map_err(|e| ParseError::InvalidEncoding(e.to_string()))
+    }
+}
+
+impl TestVector {
+    /// Proves `input` on `ring` deterministically from `seed`.
+    pub fn generate(
+        name: &str,
+        seed: &[u8],
+        input: ProofInput,
+        ring: Ring,
+        mode: ProofMode,
+    ) -> Result<Self, Error> {
+        let parsed_ring = parse_ring(ring.clone())?;
+        let proof =
+            Proof::construct_deterministic(seed, input.clone().try_into()?, &parsed_ring, mode)?;
+        Ok(Self {
+            name: name.to_string(),
+            seed: to_hex(seed),
+            mode: mode.to_string(),
+            input,
+            ring,
+            intermediate: Intermediate {
+                pedersen_base_generator: point_hex(&proof.pedersen.base().generator()),
+                pedersen_cycle_generator: point_hex(&proof.pedersen.cycle().generator()),
+                r_point: point_hex(&proof.r_point),
+                commitment_to_exp: point_hex(proof.exp_commitments.exp()),
+                commitment_to_pk_x: point_hex(proof.exp_commitments.px()),
+                commitment_to_pk_y: point_hex(proof.exp_commitments.py()),
+                ring_digest: proof.ring_digest.to_hex(),
+            },
+            encodings: ProofEncodings::new(&proof),
+        })
+    }
+
+    /// Generates the vector again from its inputs.
+    pub fn regenerate(&self) -> Result<Self, Error> {
+        Self::generate(
+            &self.name,
+            &parse_hex(&self.seed)?,
+            self.input.clone(),
+            self.ring.clone(),
+            self.mode.parse()?,
+        )
+    }
+}
+
+/// The published vectors: a personal sign statement signed by a wallet, a
+/// typed data statement with the key recovered from the signature, a
+/// high-s signature to be normalized, and a proof in circuit mode.
+pub fn standard() -> Result<Vec<TestVector>, Error> {
+    let ring = (1..=4u64).map(test_pubkey).collect::<Vec<_>>();
+    let wallet_key = "0x0454e32170dd5a0b7b641aa77daa1f3f31b8df17e51aaba6cfcb310848d26351180b6ac0399d21460443d10072700b64b454d70bfba5e93601536c740bbd099682";
+    let mut wallet_ring = ring.clone();
+    wallet_ring.insert(1, wallet_key.to_string());
+    let personal_sign = ProofInput {
+        msg_hash: "0x9788117298a1450f6002d25f0c21d83bc6001681a2e5e31c748c0f55504b11e9"
+            .to_string(),
+        pubkey: Some(wallet_key.to_string()),
+        signature: "0xd2943d5fa0ba2733bcbbd58853c6c1be65388d9198dcb5228e117f49409612a46394afb97a7610d16e7bea0062e71afc2a3039324c80df8ef38d3668164fad2c1c".to_string(),
+        index: 1,
+        guild_id: "almafa".to_string(),
+        domain: None,
+        normalize_s: false,
+    };
+
+    let domain = TypedDataDomain {
+        name: "Guild".to_string(),
+        version: Some("1".to_string()),
+        chain_id: 1,
+        verifying_contract: "0x4242424242424242424242424242424242424242".to_string(),
+    };
+    let statement = StatementMode::TypedData(domain.clone().try_into()?);
+    let guild_id = "guild-42";
+    let (msg_hash, signature) = test_signature(3, statement.message_hash(guild_id), false);
+    let typed_data = ProofInput {
+        msg_hash,
+        pubkey: None,
+        signature,
+        index: 2,
+        guild_id: guild_id.to_string(),
+        domain: Some(domain),
+        normalize_s: false,
+    };
+
+    let statement = StatementMode::PersonalSign;
+    let (msg_hash, signature) = test_signature(2, statement.message_hash(guild_id), true);
+    let high_s = ProofInput {
+        msg_hash,
+        pubkey: Some(test_pubkey(2)),
+        signature,
+        index: 1,
+        guild_id: guild_id.to_string(),
+        domain: None,
+        normalize_s: true,
+    };
+
+    Ok(vec![
+        TestVector::generate(
+            "personal-sign",
+            b"personal-sign",
+            personal_sign.clone(),
+            wallet_ring.clone(),
+            ProofMode::Exp,
+        )?,
+        TestVector::generate(
+            "typed-data",
+            b"typed-data",
+            typed_data,
+            ring.clone(),
+            ProofMode::Exp,
+        )?,
+        TestVector::generate("high-s", b"high-s", high_s, ring, ProofMode::Exp)?,
+        TestVector::generate(
+            "circuit",
+            b"circuit",
+            personal_sign,
+            wallet_ring,
+            ProofMode::Circuit,
+        )?,
+    ])
+}
+
+fn point_hex<C: Curve>(point: &Point<C>) -> String {
+    let affine = point.to_affine();
+    format!("0x04{}{}", affine.x(), affine.y()).to_lowercase()
+}
+
+fn test_secret(index: u64) -> Scalar<Secp256k1> {
+    Scalar::new(U256::from_u64(0x6b6174 + index))
+}
+
+fn test_pubkey(index: u64) -> String {
+    point_hex(&(Point::<Secp256k1>::GENERATOR * test_secret(index)))
+}
+
+// Signs with the key of `index` using a nonce derived from the message,
+// returning the message hash and the 65 byte signature in hex.
+fn test_signature(index: u64, msg_hash: Scalar<Secp256k1>, high_s: bool) -> (String, String) {
+    let secret = test_secret(index);
+    let nonce = Scalar::<Secp256k1>::new(U256::from_u64(0x6e6f6e6365)) + msg_hash;
+    let r_point = (Point::<Secp256k1>::GENERATOR * nonce).to_affine();
+    let r = Scalar::<Secp256k1>::new(*r_point.x().inner());
+    let mut signature = Signature {
+        r,
+        s: nonce.inverse() * (msg_hash + r * secret),
+        recovery_id: Some(r_point.y().inner().bit_vartime(0) as u8),
+    };
+    signature.normalize_s();
+    if high_s {
+        signature.s = -signature.s;
+        signature.recovery_id = signature.recovery_id.map(|id| id ^ 1);
+    }
+    let signature = format!(
+        "0x{}{}{:02x}",
+        to_hex(&signature.r.inner().to_be_bytes()),
+        to_hex(&signature.s.inner().to_be_bytes()),
+        // NOTE the recovery id is set above
+        27 + signature.recovery_id.unwrap()
+    );
+    (
+        format!("0x{}", to_hex(&msg_hash.inner().to_be_bytes())),
+        signature,
+    )
+}
@@ -0,0 +1,450 @@
+//! HTTP service verifying [`ZkAttestProof`]s against rings stored on disk.
+//!
+//! Rings are JSON arrays of public keys, as written by the `generate_ring`
+//! example, stored as `<ring digest>.json` in a single directory. A ring is
+//! read the first time a proof refers to its digest and is kept in a
+//! [`RingRegistry`] afterwards, so new rings can be added without restarting
+//! the service.
+//!
+//! The ring each guild accepts proofs on is set by `guilds.json` in the same
+//! directory, a JSON object mapping guild ids to ring digests. It is read for
+//! every request, so guilds can be moved to new rings at any time.
+//!
+//! `POST /verify` accepts either
+//! - a JSON body `{"proof": ..., "guildId": "..."}` where the proof is the
+//!   Borsh encoding as a hex string or as an array of bytes, or
+//! - the Borsh encoded proof itself with the guild id in the `guildId` query
+//!   parameter.
+//!
+//! The response is a JSON [`Verdict`], bodies larger than [`MAX_BODY_SIZE`]
+//! are answered with `413 Payload Too Large`.
+use crate::curve::{Secp256k1, Tom256k1};
+use crate::error::{Error, MembershipError, ParseError, StatementError};
+use crate::parse::{parse_hex, parse_ring, Ring};
+use crate::proofs::ZkAttestProof;
+use crate::ring::{RingDigest, RingRegistry};
+
+use serde::{Deserialize, Serialize};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// Upper bound of the accepted request bodies in bytes.
+pub const MAX_BODY_SIZE: u64 = 1 << 20;
+
+/// File of the ring directory mapping guild ids to ring digests.
+pub const GUILDS_FILE: &str = "guilds.json";
+
+pub type Proof = ZkAttestProof<Secp256k1, Tom256k1>;
+
+/// Outcome of a verification request.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Verdict {
+    pub valid: bool,
+    pub guild_id: Option<String>,
+    pub ring_digest: Option<String>,
+    pub error: Option<VerdictError>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerdictError {
+    pub code: String,
+    pub category: String,
+    pub message: String,
+}
+
+impl From<&Error> for VerdictError {
+    fn from(error: &Error) -> Self {
+        Self {
+            code: error.code().to_string(),
+            category: error.category().to_string(),
+            message: error.to_string(),
+        }
+    }
+}
+
+impl Verdict {
+    fn rejected(error: &Error) -> Self {
+        Self {
+            valid: false,
+            guild_id: None,
+            ring_digest: None,
+            error: Some(error.into()),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ProofBytes {
+    Hex(String),
+    Bytes(Vec<u8>),
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VerifyRequest {
+    proof: ProofBytes,
+    guild_id: String,
+}
+
+/// Directory of rings named after their digest.
+pub struct RingDirectory {
+    path: PathBuf,
+    registry: RwLock<RingRegistry<Tom256k1>>,
+}
+
+impl RingDirectory {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            registry: RwLock::new(RingRegistry::new()),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Verifies that `proof` is valid for `guild_id` on the ring of the
+    /// guild.
+    pub fn verify(&self, proof: &Proof, guild_id: &str) -> Result<(), Error> {
+        if proof.guild_id != guild_id {
+            return Err(StatementError::GuildIdMismatch.into());
+        }
+        if proof.ring_digest != self.guild_ring(guild_id)? {
+            return Err(MembershipError::RingDigestMismatch.into());
+        }
+        self.load(&proof.ring_digest)?;
+        let registry = self.registry.read().expect("poisoned ring registry");
+        proof.verify_registered(&mut rand_core::OsRng, &registry)
+    }
+
+    /// Answers the request, the verdict is returned with `200 OK` unless the
+    /// request could not be parsed.
+    pub fn handle(&self, mut request: Request) -> std::io::Result<()> {
+        let (path, query) = match request.url().split_once('?') {
+            Some((path, query)) => (path.to_string(), query.to_string()),
+            None => (request.url().to_string(), String::new()),
+        };
+        if path != "/verify" {
+            return request.respond(Response::empty(404));
+        }
+        if *request.method() != Method::Post {
+            return request.respond(Response::empty(405));
+        }
+
+        let is_json = request.headers().iter().any(|header| {
+            header.field.equiv("Content-Type")
+                && header.value.as_str().starts_with("application/json")
+        });
+        let mut body = Vec::new();
+        request
+            .as_reader()
+            .take(MAX_BODY_SIZE + 1)
+            .read_to_end(&mut body)?;
+        if body.len() as u64 > MAX_BODY_SIZE {
+            return request.respond(Response::empty(413));
+        }
+
+        let (status, verdict) = match self.verify_request(is_json, &query, &body) {
+            Ok(verdict) => (200, verdict),
+            Err(error) => (400, Verdict::rejected(&error)),
+        };
+        let body = serde_json::to_vec(&verdict).expect("verdicts are serializable");
+        let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..])
+            .expect("valid header");
+        request.respond(
+            Response::from_data(body)
+                .with_status_code(status)
+                .with_header(content_type),
+        )
+    }
+
+    /// Handles incoming requests until the server is unblocked.
+    pub fn serve(&self, server: &Server) {
+        for request in server.incoming_requests() {
+            // NOTE the client hung up, nothing left to respond to
+            let _ = self.handle(request);
+        }
+    }
+
+    fn verify_request(&self, is_json: bool, query: &str, body: &[u8]) -> Result<Verdict, Error> {
+        let (proof_bytes, guild_id) = if is_json {
+            let request: VerifyRequest = serde_json::from_slice(body)
+                .map_err(|e| ParseError::InvalidEncoding(e.to_string()))?;
+            let bytes = match request.proof {
+                ProofBytes::Hex(hex) => parse_hex(&hex)?,
+                ProofBytes::Bytes(bytes) => bytes,
+            };
+            (bytes, request.guild_id)
+        } else {
+            let guild_id = query_param(query, "guildId").ok_or_else(|| {
+                ParseError::InvalidEncoding("missing guildId query parameter".to_string())
+            })?;
+            (body.to_vec(), guild_id)
+        };
+
+        let proof: Proof = borsh::BorshDeserialize::try_from_slice(&proof_bytes)
+            .map_err(|e| ParseError::InvalidEncoding(e.to_string()))?;
+        let result = self.verify(&proof, &guild_id);
+        Ok(Verdict {
+            valid: result.is_ok(),
+            guild_id: Some(guild_id),
+            ring_digest: Some(proof.ring_digest.to_hex()),
+            error: result.err().as_ref().map(VerdictError::from),
+        })
+    }
+
+    // Reads the digest of the ring of the guild from the guilds file.
+    fn guild_ring(&self, guild_id: &str) -> Result<RingDigest, Error> {
+        let contents =
+            std::fs::read(self.path.join(GUILDS_FILE)).map_err(|_| StatementError::UnknownGuild)?;
+        let guilds: HashMap<String, String> = serde_json::from_slice(&contents)
+            .map_err(|e| ParseError::InvalidEncoding(e.to_string()))?;
+        let digest = guilds.get(guild_id).ok_or(StatementError::UnknownGuild)?;
+        Ok(RingDigest::from_hex(digest)?)
+    }
+
+    // Reads the ring with the given digest from the directory unless it is
+    // already registered.
+    fn load(&self, digest: &RingDigest) -> Result<(), Error> {
+        if self
+            .registry
+            .read()
+            .expect("poisoned ring registry")
+            .contains(digest)
+        {
+            return Ok(());
+        }
+
+        let file = self.path.join(format!("{}.json", digest.to_hex()));
+        let contents = std::fs::read(file).map_err(|_| MembershipError::UnknownRing)?;
+        let ring: Ring = serde_json::from_slice(&contents)
+            .map_err(|e| ParseError::InvalidEncoding(e.to_string()))?;
+        let ring = parse_ring::<Tom256k1>(ring)?;
+        // a file that is not named after its digest is never served
+        if RingDigest::new(&ring) != *digest {
+            return Err(MembershipError::UnknownRing.into());
+        }
+        self.registry
+            .write()
+            .expect("poisoned ring registry")
+            .insert(&ring)?;
+        Ok(())
+    }
+}
+
+fn query_param(query: &str, name: &str) -> Option<String> {
+    query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)
+        .and_then(|(_, value)| percent_decode(value))
+}
+
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+                decoded.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            byte => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse::{to_hex, ParsedProofInput, ProofInput};
+    use crate::pedersen::PedersenCycle;
+
+    use borsh::BorshSerialize;
+    use rand::rngs::StdRng;
+    use rand_core::SeedableRng;
+
+    use std::io::Write;
+    use std::net::TcpStream;
+    use std::sync::Arc;
+
+    fn post(port: u16, url: &str, content_type: &str, body: &[u8]) -> (u16, Option<Verdict>) {
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        write!(
+            stream,
+            "POST {} HTTP/1.1\r\nHost: localhost\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            url,
+            content_type,
+            body.len()
+        )
+        .unwrap();
+        stream.write_all(body).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+
+        let status = response[9..12].parse().unwrap();
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        (status, serde_json::from_str(body).ok())
+    }
+
+    #[test]
+    fn verifier_service() {
+        let mut rng = StdRng::from_seed([33; 32]);
+        let ring: Ring = vec![
+            "c2ef144b59081382387f0ebf5d96b3a194f8c28961fa443000ea793ce534dac2".to_string(),
+            "54e32170dd5a0b7b641aa77daa1f3f31b8df17e51aaba6cfcb310848d2635118".to_string(),
+            "ddd40afe39c280d2f43f05c070988dae7fbae9cdfd5fb6461acd7657e765e172".to_string(),
+            "1296d6ed4e96bc378b8a460de783cdfbf58afbe04b355f1c225fb3e0b92cdc6e".to_string(),
+        ];
+        let input: ParsedProofInput<Secp256k1> = ProofInput {
+            msg_hash: "0x9788117298a1450f6002d25f0c21d83bc6001681a2e5e31c748c0f55504b11e9".to_string(),
+            pubkey: Some("0454e32170dd5a0b7b641aa77daa1f3f31b8df17e51aaba6cfcb310848d26351180b6ac0399d21460443d10072700b64b454d70bfba5e93601536c740bbd099682".to_string()),
+            signature: "0xd2943d5fa0ba2733bcbbd58853c6c1be65388d9198dcb5228e117f49409612a46394afb97a7610d16e7bea0062e71afc2a3039324c80df8ef38d3668164fad2c1c".to_string(),
+            index: 1,
+            guild_id: "almafa".to_string(),
+            domain: None,
+            normalize_s: false,
+        }
+        .try_into()
+        .unwrap();
+        let parsed_ring = parse_ring(ring.clone()).unwrap();
+        let pedersen = PedersenCycle::new(&mut rng);
+        let proof = Proof::construct(&mut rng, pedersen, input, &parsed_ring).unwrap();
+        let proof_bytes = proof.try_to_vec().unwrap();
+        let digest = proof.ring_digest.to_hex();
+
+        // proofs are only accepted on the ring of the guild
+        let dir = std::env::temp_dir().join(format!("zkp-verifier-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let empty = RingDirectory::new(&dir);
+        assert_eq!(
+            empty.verify(&proof, "almafa"),
+            Err(StatementError::UnknownGuild.into())
+        );
+        let other_ring = parse_ring::<Tom256k1>(ring[1..].to_vec()).unwrap();
+        let guilds = serde_json::json!({
+            "almafa": digest,
+            "kortefa": RingDigest::new(&other_ring).to_hex(),
+        });
+        std::fs::write(dir.join(GUILDS_FILE), guilds.to_string()).unwrap();
+
+        // the ring fixture is only found once it is named after its digest
+        assert_eq!(
+            empty.verify(&proof, "almafa"),
+            Err(MembershipError::UnknownRing.into())
+        );
+        std::fs::write(
+            dir.join(format!("{}.json", digest)),
+            serde_json::to_vec(&ring).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(empty.verify(&proof, "almafa"), Ok(()));
+
+        // a valid proof of a guild member on the ring of another guild
+        let other_guild: ParsedProofInput<Secp256k1> = ProofInput {
+            msg_hash: "0x9788117298a1450f6002d25f0c21d83bc6001681a2e5e31c748c0f55504b11e9".to_string(),
+            pubkey: Some("0454e32170dd5a0b7b641aa77daa1f3f31b8df17e51aaba6cfcb310848d26351180b6ac0399d21460443d10072700b64b454d70bfba5e93601536c740bbd099682".to_string()),
+            signature: "0xd2943d5fa0ba2733bcbbd58853c6c1be65388d9198dcb5228e117f49409612a46394afb97a7610d16e7bea0062e71afc2a3039324c80df8ef38d3668164fad2c1c".to_string(),
+            index: 1,
+            guild_id: "kortefa".to_string(),
+            domain: None,
+            normalize_s: false,
+        }
+        .try_into()
+        .unwrap();
+        let pedersen = PedersenCycle::new(&mut rng);
+        let other_proof = Proof::construct(&mut rng, pedersen, other_guild, &parsed_ring).unwrap();
+        assert_eq!(
+            empty.verify(&other_proof, "kortefa"),
+            Err(MembershipError::RingDigestMismatch.into())
+        );
+
+        let rings = Arc::new(RingDirectory::new(&dir));
+        let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+        let port = server.server_addr().to_ip().unwrap().port();
+        let handle = {
+            let (rings, server) = (Arc::clone(&rings), Arc::clone(&server));
+            std::thread::spawn(move || rings.serve(&server))
+        };
+
+        let json = serde_json::json!({ "proof": format!("0x{}", to_hex(&proof_bytes)), "guildId": "almafa" });
+        let (status, verdict) = post(
+            port,
+            "/verify",
+            "application/json",
+            json.to_string().as_bytes(),
+        );
+        assert_eq!(status, 200);
+        let verdict = verdict.unwrap();
+        assert!(verdict.valid);
+        assert_eq!(verdict.ring_digest, Some(digest));
+        assert_eq!(verdict.error, None);
+
+        let json = serde_json::json!({ "proof": proof_bytes, "guildId": "almafa" });
+        let (_, verdict) = post(
+            port,
+            "/verify",
+            "application/json",
+            json.to_string().as_bytes(),
+        );
+        assert!(verdict.unwrap().valid);
+
+        let (status, verdict) = post(
+            port,
+            "/verify?guildId=alma%20fa",
+            "application/octet-stream",
+            &proof_bytes,
+        );
+        assert_eq!(status, 200);
+        let verdict = verdict.unwrap();
+        assert!(!verdict.valid);
+        assert_eq!(verdict.guild_id.as_deref(), Some("alma fa"));
+        assert_eq!(verdict.error.unwrap().code, "STATEMENT_GUILD_ID_MISMATCH");
+
+        let (status, verdict) = post(
+            port,
+            "/verify?guildId=almafa",
+            "application/octet-stream",
+            &proof_bytes[..100],
+        );
+        assert_eq!(status, 400);
+        assert_eq!(
+            verdict.unwrap().error.unwrap().code,
+            "PARSE_INVALID_ENCODING"
+        );
+
+        let (status, _) = post(port, "/proofs", "application/json", b"{}");
+        assert_eq!(status, 404);
+
+        let oversized = vec![0u8; MAX_BODY_SIZE as usize + 1];
+        let (status, verdict) = post(
+            port,
+            "/verify?guildId=almafa",
+            "application/octet-stream",
+            &oversized,
+        );
+        assert_eq!(status, 413);
+        assert_eq!(verdict, None);
+
+        server.unblock();
+        handle.join().unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
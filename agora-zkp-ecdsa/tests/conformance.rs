@@ -0,0 +1,61 @@
+//! Checks the deterministic prover and every proof encoding against the
+//! published known-answer vectors.
+//!
+//! NOTE these run outside of the unit tests, whose proofs have fewer
+//! rounds than the published ones.
+use agora_zkp_ecdsa::parse::parse_ring;
+use agora_zkp_ecdsa::vectors::{self, TestVector};
+
+use rand_core::OsRng;
+
+fn published() -> Vec<TestVector> {
+    serde_json::from_str(include_str!("../vectors/zk_attest.json")).unwrap()
+}
+
+#[test]
+fn vectors_are_reproducible() {
+    let published = published();
+    let names = published
+        .iter()
+        .map(|v| v.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["personal-sign", "typed-data", "high-s", "circuit"]);
+    assert_eq!(vectors::standard().unwrap(), published);
+}
+
+#[test]
+fn encodings_verify() {
+    for vector in published() {
+        let proof = vector.encodings.decode().unwrap();
+        assert_eq!(vector.mode, proof.signature_proof.mode().to_string());
+        assert_eq!(vector.encodings.json.guild_id, proof.guild_id);
+        assert_eq!(
+            vector.encodings.json.ring_digest,
+            proof.ring_digest.to_hex()
+        );
+        assert_eq!(vector.intermediate.ring_digest, proof.ring_digest.to_hex());
+        // re-encoding yields the same bytes
+        assert_eq!(
+            vectors::ProofEncodings::new(&proof),
+            vector.encodings,
+            "{}",
+            vector.name
+        );
+
+        let ring = parse_ring(vector.ring.clone()).unwrap();
+        assert!(proof.verify(&mut OsRng, &ring).is_ok(), "{}", vector.name);
+    }
+}
+
+#[test]
+fn tampered_encodings_are_rejected() {
+    let vector = &published()[0];
+    let mut encodings = vector.encodings.clone();
+    encodings.base64 = published()[1].encodings.base64.clone();
+    assert!(encodings.decode().is_err());
+
+    // the proof of another input does not verify on this ring
+    let proof = published()[1].encodings.decode().unwrap();
+    let ring = parse_ring(vector.ring.clone()).unwrap();
+    assert!(proof.verify(&mut OsRng, &ring).is_err());
+}
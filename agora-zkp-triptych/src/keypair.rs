@@ -1,17 +1,17 @@
-use k256::elliptic_curve::Field;
-use k256::{AffinePoint, Scalar};
+use crate::signing_key::SigningKey;
+use k256::AffinePoint;
 use rand_core::OsRng;
 
 pub struct Keypair {
     pub public: AffinePoint,
-    pub private: Scalar,
+    pub private: SigningKey,
 }
 
 impl Keypair {
     pub fn random() -> Self {
-        let private = Scalar::random(OsRng);
+        let private = SigningKey::random(&mut OsRng);
         Self {
-            public: (AffinePoint::GENERATOR * private).to_affine(),
+            public: private.public_key(),
             private,
         }
     }
@@ -0,0 +1,84 @@
+//! Multiscalar multiplication using Pippenger's bucket method.
+use k256::elliptic_curve::PrimeField;
+use k256::{FieldBytes, ProjectivePoint, Scalar};
+
+/// Computes `sum(scalars[i] * points[i])`.
+///
+/// Every scalar is split into windows of `c` bits. For each window the points
+/// are sorted into buckets by their digit, and the buckets are summed with
+/// running sums, so a window costs about `n + 2^c` additions instead of `n`
+/// scalar multiplications.
+pub fn multiscalar_mul(scalars: &[Scalar], points: &[ProjectivePoint]) -> ProjectivePoint {
+    debug_assert_eq!(scalars.len(), points.len());
+    let c = window_size(points.len());
+    let digits = scalars.iter().map(|s| s.to_repr()).collect::<Vec<_>>();
+
+    let mut result = ProjectivePoint::IDENTITY;
+    let mut buckets = vec![ProjectivePoint::IDENTITY; (1 << c) - 1];
+    for window in (0..(Scalar::NUM_BITS as usize).div_ceil(c)).rev() {
+        for _ in 0..c {
+            result = result.double();
+        }
+        buckets.fill(ProjectivePoint::IDENTITY);
+        for (scalar, point) in digits.iter().zip(points) {
+            let digit = digit(scalar, window * c, c);
+            if digit != 0 {
+                buckets[digit - 1] += point;
+            }
+        }
+        // sum_d d * B_d as the sum of the running sums from the top
+        let mut running = ProjectivePoint::IDENTITY;
+        for bucket in buckets.iter().rev() {
+            running += bucket;
+            result += running;
+        }
+    }
+    result
+}
+
+fn window_size(n: usize) -> usize {
+    match n {
+        0..=3 => 2,
+        4..=31 => 4,
+        32..=255 => 6,
+        256..=4095 => 8,
+        _ => 11,
+    }
+}
+
+// Bits `[offset, offset + width)` of a big-endian scalar.
+fn digit(bytes: &FieldBytes, offset: usize, width: usize) -> usize {
+    (offset..offset + width)
+        .take_while(|&bit| bit < 8 * bytes.len())
+        .enumerate()
+        .fold(0, |digit, (i, bit)| {
+            let byte = bytes[bytes.len() - 1 - bit / 8];
+            digit | (((byte >> (bit % 8)) & 1) as usize) << i
+        })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use k256::elliptic_curve::Field;
+    use rand_core::OsRng;
+
+    #[test]
+    fn matches_naive_sum() {
+        for n in [0, 1, 3, 17, 100, 300] {
+            let scalars = (0..n).map(|_| Scalar::random(OsRng)).collect::<Vec<_>>();
+            let points = (0..n)
+                .map(|_| ProjectivePoint::GENERATOR * Scalar::random(OsRng))
+                .collect::<Vec<_>>();
+            let naive = scalars
+                .iter()
+                .zip(&points)
+                .fold(ProjectivePoint::IDENTITY, |acc, (s, p)| acc + p * s);
+            assert_eq!(multiscalar_mul(&scalars, &points), naive);
+        }
+
+        let edge = [Scalar::ZERO, Scalar::ONE, -Scalar::ONE];
+        let points = [ProjectivePoint::GENERATOR; 3];
+        assert_eq!(multiscalar_mul(&edge, &points), ProjectivePoint::IDENTITY);
+    }
+}
@@ -2,18 +2,51 @@ use generic_array::GenericArray;
 use k256::elliptic_curve::sec1::FromEncodedPoint;
 use k256::{AffinePoint, EncodedPoint};
 
+use std::ops::Deref;
+
 pub type FrontendRing = Vec<String>;
-pub type Ring = Vec<AffinePoint>;
 
-pub fn parse_ring(frontend_ring: FrontendRing) -> Result<Ring, String> {
-    if frontend_ring.len() >= 2usize.pow(crate::MAX_2_EXPONENT as u32) {
-        return Err("ring too long".to_string());
+/// Public keys of the members of a ring, in the order signers index them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Ring(Vec<AffinePoint>);
+
+impl Ring {
+    pub fn new(keys: Vec<AffinePoint>) -> Result<Self, String> {
+        if keys.len() >= 2usize.pow(crate::MAX_2_EXPONENT as u32) {
+            return Err("ring too long".to_string());
+        }
+        Ok(Self(keys))
     }
-    let mut ring = Ring::with_capacity(frontend_ring.len());
-    for pk in frontend_ring.iter() {
-        ring.push(parse_pubkey(pk)?)
+
+    /// Parses hex encoded uncompressed public keys.
+    pub fn parse<S: AsRef<str>>(keys: &[S]) -> Result<Self, String> {
+        let keys = keys
+            .iter()
+            .map(|pk| parse_pubkey(pk.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new(keys)
+    }
+
+    pub fn keys(&self) -> &[AffinePoint] {
+        &self.0
+    }
+
+    /// Index of `key` in the ring, if it is a member.
+    pub fn position(&self, key: &AffinePoint) -> Option<usize> {
+        self.0.iter().position(|pk| pk == key)
     }
-    Ok(ring)
+}
+
+impl Deref for Ring {
+    type Target = [AffinePoint];
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+pub fn parse_ring(frontend_ring: FrontendRing) -> Result<Ring, String> {
+    Ring::parse(&frontend_ring)
 }
 
 fn parse_pubkey(pk_string: &str) -> Result<AffinePoint, String> {
@@ -36,23 +69,39 @@ fn parse_pubkey(pk_string: &str) -> Result<AffinePoint, String> {
     }
 }
 
-pub fn pad_ring_to_2n(ring: &mut Ring) -> Result<usize, String> {
-    if ring.is_empty() {
-        Err("empty ring".to_string())
+/// Largest base of the n-ary decomposition of the signer's index.
+pub const MAX_BASE: usize = 16;
+
+pub fn check_base(base: usize) -> Result<(), String> {
+    if (2..=MAX_BASE).contains(&base) {
+        Ok(())
     } else {
-        let log_2_ring_len = ring.len().ilog2();
-        let pow_2_ring_len = 2usize.pow(log_2_ring_len);
-        // pow_2_ring_len is always less than or equal to keys.len()
-        // because log2 always rounds down
-        if ring.len() != pow_2_ring_len {
-            for _ in 0..pow_2_ring_len * 2 - ring.len() {
-                ring.push(ring[0])
-            }
-            Ok((log_2_ring_len + 1) as usize)
-        } else {
-            Ok(log_2_ring_len as usize)
-        }
+        Err("invalid base".to_string())
+    }
+}
+
+/// Number of base `base` digits required to index a ring of `ring_len` keys.
+pub fn digit_count(ring_len: usize, base: usize) -> usize {
+    let mut digits = 0;
+    let mut capacity = 1usize;
+    while capacity < ring_len {
+        capacity = capacity.saturating_mul(base);
+        digits += 1;
+    }
+    digits
+}
+
+/// Pads the ring to `base^m` elements by repeating its first element and
+/// returns `m`.
+pub fn pad_ring_to_nm(ring: &mut Vec<AffinePoint>, base: usize) -> Result<usize, String> {
+    if ring.is_empty() {
+        return Err("empty ring".to_string());
     }
+    check_base(base)?;
+    let digits = digit_count(ring.len(), base);
+    let padded_len = base.pow(digits as u32);
+    ring.resize(padded_len, ring[0]);
+    Ok(digits)
 }
 
 #[cfg(test)]
@@ -65,24 +114,51 @@ mod test {
     #[test]
     fn pad_ring() {
         let mut ring = Vec::<AffinePoint>::new();
-        assert!(pad_ring_to_2n(&mut ring).is_err());
+        assert!(pad_ring_to_nm(&mut ring, 2).is_err());
         ring.push(AffinePoint::GENERATOR);
-        assert_eq!(pad_ring_to_2n(&mut ring), Ok(0));
+        assert_eq!(pad_ring_to_nm(&mut ring, 2), Ok(0));
         assert_eq!(ring.len(), 1);
         ring.push(AffinePoint::GENERATOR);
-        assert_eq!(pad_ring_to_2n(&mut ring), Ok(1));
+        assert_eq!(pad_ring_to_nm(&mut ring, 2), Ok(1));
         assert_eq!(ring.len(), 2);
         ring.push(AffinePoint::GENERATOR);
-        assert_eq!(pad_ring_to_2n(&mut ring), Ok(2));
+        assert_eq!(pad_ring_to_nm(&mut ring, 2), Ok(2));
         assert_eq!(ring.len(), 4);
         assert_eq!(ring[3], AffinePoint::GENERATOR);
         for _ in 0..5 {
             ring.push(AffinePoint::GENERATOR);
         }
         assert_eq!(ring.len(), 9);
-        assert_eq!(pad_ring_to_2n(&mut ring), Ok(4));
+        assert_eq!(pad_ring_to_nm(&mut ring.clone(), 3), Ok(2));
+        assert_eq!(pad_ring_to_nm(&mut ring, 2), Ok(4));
         assert_eq!(ring.len(), 16);
         assert_eq!(ring[15], AffinePoint::GENERATOR);
+
+        ring.push(AffinePoint::GENERATOR);
+        assert_eq!(pad_ring_to_nm(&mut ring, 4), Ok(3));
+        assert_eq!(ring.len(), 64);
+
+        assert!(pad_ring_to_nm(&mut ring, 1).is_err());
+        assert!(pad_ring_to_nm(&mut ring, MAX_BASE + 1).is_err());
+    }
+
+    #[test]
+    fn ring_members() {
+        let encoded = [
+            "0x04b9bc295e1319841c63cbd252e6431ad46611d1b9f5538732cfc61e3fcd9cd8a8ffe625780401f028e7060e978ff755e423cad1fed4a27938545a76ffd427cc78",
+            "0x043c19ca75eed7cad6146b9f69d62b5da48c9b5757b518c008b5927e7ba59c68cd13d0c11c0927cacc9903c1de89f51448b6fa5636b073c50533f7a9aa08d28351",
+            "0x047f8ede0d99eebe71094e888808b027c902bcaf7b0e42802cfe5cc72c8f87da3ebdbc9f9aeb13922056c99cb6cafc44c9380b17bf084cdef262dbfabf36e66717",
+        ];
+        let ring = Ring::parse(&encoded).unwrap();
+        assert_eq!(ring.len(), 3);
+        for (i, pk) in encoded.iter().enumerate() {
+            let key = parse_pubkey(pk).unwrap();
+            assert_eq!(ring.keys()[i], key);
+            assert_eq!(ring.position(&key), Some(i));
+        }
+        assert_eq!(ring.position(&Keypair::random().public), None);
+        assert_eq!(parse_ring(encoded.map(String::from).to_vec()), Ok(ring));
+        assert!(Ring::parse(&["0x04nothex"]).is_err());
     }
 
     #[test]
@@ -1,405 +1,973 @@
+use crate::msm::multiscalar_mul;
 use crate::ring::*;
-use agora_interpolate::Polynomial;
+use crate::signing_key::SigningKey;
+use crate::tag_store::TagStore;
 use k256::elliptic_curve::group::GroupEncoding;
-use k256::elliptic_curve::ops::Reduce;
 use k256::elliptic_curve::{Field, PrimeField};
-use k256::{AffinePoint, ProjectivePoint, Scalar, U256};
-use rand_core::OsRng;
+use k256::{AffinePoint, CompressedPoint, ProjectivePoint, Scalar};
+use rand_core::{CryptoRng, OsRng, RngCore};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use sha3::{Digest, Keccak256};
-use zeroize::Zeroize;
 
-// NOTE this is a public "private key" that determines the U point which is used
-// for tag generation
-const U_SCALAR_U256: U256 =
-    U256::from_le_hex("7c81a9587b8da43a9519bd50d96191fd8f2c4f66b8f1550e366e3c7f9ed18897");
-
-#[derive(Clone, Serialize, Deserialize)]
+const GENERATOR_LABEL: &[u8] = b"agora-triptych-generator";
+const H_POINT_LABEL: &[u8] = b"agora-triptych-h-point";
+const U_POINT_LABEL: &[u8] = b"agora-triptych-u-point";
+
+/// Version of the binary encoding produced by [`Signature::to_bytes`].
+pub const ENCODING_VERSION: u8 = 2;
+const POINT_SIZE: usize = 33;
+const SCALAR_SIZE: usize = 32;
+
+/// Commitment generators, derived by hashing fixed labels to the curve.
+///
+/// Nobody knows the discrete logarithms of the generators, which the
+/// soundness of the membership proof relies on, so the verifier derives them
+/// itself instead of accepting them from the signer.
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Parameters {
-    generators: Vec<VecElem<AffinePoint>>,
+    base: usize,
+    generators: Vec<Vec<AffinePoint>>,
     h_point: AffinePoint,
 }
 
 impl Parameters {
-    pub fn new(n_generators: usize) -> Self {
-        let mut generators = Vec::<VecElem<AffinePoint>>::with_capacity(n_generators);
-        for _ in 0..n_generators {
-            let pt_0 = AffinePoint::GENERATOR * Scalar::random(OsRng);
-            let pt_1 = AffinePoint::GENERATOR * Scalar::random(OsRng);
-            generators.push(VecElem {
-                i_0: pt_0.to_affine(),
-                i_1: pt_1.to_affine(),
-            });
-        }
+    /// Derives `n_generators` rows of `base` generators, enough for rings of
+    /// up to `base^n_generators` keys.
+    pub fn new(base: usize, n_generators: usize) -> Result<Self, String> {
+        check_base(base)?;
+        let generators = (0..n_generators as u64)
+            .map(|j| {
+                (0..base as u8)
+                    .map(|i| hash_to_curve(&[GENERATOR_LABEL, &j.to_be_bytes(), &[i]].concat()))
+                    .collect()
+            })
+            .collect();
 
-        Self {
+        Ok(Self {
+            base,
             generators,
-            h_point: (AffinePoint::GENERATOR * Scalar::random(OsRng)).to_affine(),
+            h_point: hash_to_curve(H_POINT_LABEL),
+        })
+    }
+
+    pub fn base(&self) -> usize {
+        self.base
+    }
+
+    // Pedersen commitment to a matrix of `m` rows of `base` scalars.
+    fn commit(&self, rows: &[Vec<Scalar>], blinding: Scalar) -> AffinePoint {
+        let mut scalars = vec![blinding];
+        let mut points = vec![ProjectivePoint::from(self.h_point)];
+        for (gens, row) in self.generators.iter().zip(rows) {
+            scalars.extend(row);
+            points.extend(gens.iter().map(ProjectivePoint::from));
         }
+        multiscalar_mul(&scalars, &points).to_affine()
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
-struct VecElem<T: Clone + Copy + std::fmt::Debug + PartialEq + Eq> {
-    i_0: T,
-    i_1: T,
+/// Base point of the linking tags `J = U * sk^-1` in `scope`.
+///
+/// Signatures of the same key are linkable within a scope, e.g. a guild, but
+/// not across scopes.
+pub fn u_point(scope: &[u8]) -> AffinePoint {
+    hash_to_curve(&[U_POINT_LABEL, scope].concat())
 }
 
-#[derive(Clone, Serialize, Deserialize)]
-pub struct Signature {
+// Maps `label` to the point with even y-coordinate whose x-coordinate is the
+// first hash of `label || counter` that is on the curve (try-and-increment).
+fn hash_to_curve(label: &[u8]) -> AffinePoint {
+    let mut counter = 0u32;
+    loop {
+        let mut hasher = Keccak256::new();
+        hasher.update(label);
+        hasher.update(counter.to_be_bytes());
+        let mut encoded = CompressedPoint::default();
+        encoded[0] = 0x02;
+        encoded[1..].copy_from_slice(&hasher.finalize());
+        let point = AffinePoint::from_bytes(&encoded);
+        if point.is_some().into() {
+            return point.unwrap();
+        }
+        counter += 1;
+    }
+}
+
+/// Commitments to the digits of the indices of the signers and the
+/// responses opening them, shared by the members of a proof.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct DigitCommitments {
     a_commitment: AffinePoint,
     b_commitment: AffinePoint,
     c_commitment: AffinePoint,
     d_commitment: AffinePoint,
-    x_points: Vec<AffinePoint>,
-    y_points: Vec<AffinePoint>,
-    f_scalars: Vec<Scalar>,
     z_a_scalar: Scalar,
     z_c_scalar: Scalar,
+}
+
+/// Proof that a member knows the key at the index committed to in the rows
+/// of the digit commitments belonging to it, and that its tag belongs to
+/// the same key.
+///
+/// The index is written as `m` digits in base `n`, and for every digit `j`
+/// the proof holds `f_j,1..f_j,n-1`, `f_j,0` being implied by the challenge.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(crate) struct MemberProof {
+    x_points: Vec<AffinePoint>,
+    y_points: Vec<AffinePoint>,
+    f_scalars: Vec<Vec<Scalar>>,
     z_scalar: Scalar,
     tag: AffinePoint,
 }
 
-// NOTE n = 2, i.e. N = 2^m
-// N - number of elements in the ring (should be padded to 2^m)
+impl MemberProof {
+    pub(crate) fn tag(&self) -> AffinePoint {
+        self.tag
+    }
+}
+
+/// Triptych signature of a member of a ring of up to `n^m` keys.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Signature {
+    base: usize,
+    commitments: DigitCommitments,
+    member: MemberProof,
+}
+
+// NOTE N - number of elements in the ring (padded to n^m)
 impl Signature {
-    pub fn new(
+    /// Signs `message_hash` as the member at `index` of `ring`.
+    pub fn new<R: RngCore + CryptoRng>(
+        rng: &mut R,
         index: usize,
+        ring: &[AffinePoint],
+        message_hash: &[u8],
+        scope: &[u8],
+        signing_key: &SigningKey,
+        parameters: &Parameters,
+    ) -> Result<Self, String> {
+        let (commitments, mut members) = prove(
+            rng,
+            &[(index, signing_key)],
+            ring,
+            message_hash,
+            scope,
+            parameters,
+        )?;
+        Ok(Self {
+            base: parameters.base,
+            commitments,
+            // NOTE there is exactly one member for one signer
+            member: members.pop().unwrap(),
+        })
+    }
+
+    /// Signs `message_hash` as the member of `ring` holding `signing_key`.
+    pub fn sign<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        signing_key: &SigningKey,
         ring: &Ring,
         message_hash: &[u8],
-        mut privkey: Scalar, // mutable for zeroize at the end
+        scope: &[u8],
         parameters: &Parameters,
     ) -> Result<Self, String> {
-        // include the msg hash in the challenge
-        let mut hasher = Keccak256::new();
-        hasher.update(message_hash);
-        // pad ring and hash pubkeys
+        let index = ring
+            .position(&signing_key.public_key())
+            .ok_or_else(|| "signing key not in ring".to_owned())?;
+        Self::new(
+            rng,
+            index,
+            ring,
+            message_hash,
+            scope,
+            signing_key,
+            parameters,
+        )
+    }
+
+    pub fn verify(
+        &self,
+        ring: &[AffinePoint],
+        message_hash: &[u8],
+        scope: &[u8],
+        parameters: &Parameters,
+    ) -> Result<(), String> {
+        Self::verify_batch(&mut OsRng, &[(self, message_hash)], ring, scope, parameters)
+    }
+
+    /// Verifies signatures of (possibly different) messages over the same
+    /// ring and scope.
+    ///
+    /// The verification equations of every signature are weighted by random
+    /// scalars and checked by a single multiscalar multiplication, in which
+    /// the ring, the generators and the base points appear only once. The
+    /// batch is rejected as a whole if any signature in it is invalid.
+    pub fn verify_batch<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        batch: &[(&Self, &[u8])],
+        ring: &[AffinePoint],
+        scope: &[u8],
+        parameters: &Parameters,
+    ) -> Result<(), String> {
         let mut ring = ring.to_owned();
-        let m = pad_ring_to_2n(&mut ring)?;
-        for pk in ring.iter() {
-            hasher.update(pk.to_bytes());
+        let m = pad_ring_to_nm(&mut ring, parameters.base)?;
+        let u_point = u_point(scope);
+        let mut equation = BatchEquation::new(ring.len(), parameters, m)?;
+        for (signature, message_hash) in batch {
+            let members = std::slice::from_ref(&signature.member);
+            check_size(signature.base, members, parameters.base, m)?;
+            let xi = challenge(
+                &ring,
+                message_hash,
+                scope,
+                &u_point,
+                &signature.commitments,
+                members,
+            );
+            equation.add(rng, &signature.commitments, members, xi);
         }
+        equation.check(&ring, parameters, &u_point)
+    }
 
-        // generate signature tag
-        let u_point = AffinePoint::GENERATOR * Scalar::from_uint_reduced(U_SCALAR_U256);
-        if privkey == Scalar::ZERO {
-            return Err("invalid private key".to_owned());
+    /// Encodes the signature as
+    /// `version || n || m || A || B || C || D || X_j || Y_j || f_j,i || z_A || z_C || z || J`
+    /// with compressed SEC1 points and big-endian scalars.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let commitments = &self.commitments;
+        let member = &self.member;
+        let m = member.f_scalars.len();
+        let mut bytes = Vec::with_capacity(
+            3 + (5 + 2 * m) * POINT_SIZE + (3 + m * (self.base - 1)) * SCALAR_SIZE,
+        );
+        bytes.push(ENCODING_VERSION);
+        // NOTE the base is at most MAX_BASE and m is at most the number of
+        // generators, both fit in a byte
+        bytes.push(self.base as u8);
+        bytes.push(m as u8);
+        for point in [
+            &commitments.a_commitment,
+            &commitments.b_commitment,
+            &commitments.c_commitment,
+            &commitments.d_commitment,
+        ]
+        .into_iter()
+        .chain(&member.x_points)
+        .chain(&member.y_points)
+        {
+            bytes.extend_from_slice(&point.to_bytes());
         }
-        // NOTE privkey is always invertible due to the above check
-        let j_point = u_point * privkey.invert().unwrap();
-
-        let a_vec = (0..m)
-            .map(|_| {
-                let i_1 = Scalar::random(OsRng);
-                VecElem { i_0: -i_1, i_1 }
-            })
-            .collect::<Vec<VecElem<Scalar>>>();
-        let b_vec = deltas(index, m); // sigma vec
-        let c_vec = a_vec
-            .iter()
-            .zip(b_vec.iter())
-            .map(|(a, b)| {
-                let i_0 = a.i_0 * (Scalar::ONE - (b.i_0 + b.i_0));
-                let i_1 = a.i_1 * (Scalar::ONE - (b.i_1 + b.i_1));
-                VecElem { i_0, i_1 }
-            })
-            .collect::<Vec<VecElem<Scalar>>>();
-        let d_vec = a_vec
-            .iter()
-            .map(|a| {
-                let i_0 = -(a.i_0 * a.i_0);
-                let i_1 = -(a.i_1 * a.i_1);
-                VecElem { i_0, i_1 }
-            })
-            .collect::<Vec<VecElem<Scalar>>>();
-
-        let mut omegas = Vec::<Scalar>::with_capacity(m);
-        let mut rho_vec = Vec::<Scalar>::with_capacity(m);
-        let mut a_com_gen = ProjectivePoint::IDENTITY;
-        let mut b_com_gen = ProjectivePoint::IDENTITY;
-        let mut c_com_gen = ProjectivePoint::IDENTITY;
-        let mut d_com_gen = ProjectivePoint::IDENTITY;
-        for (i, gen) in parameters.generators.iter().take(m).enumerate() {
-            a_com_gen += gen.i_0 * a_vec[i].i_0;
-            a_com_gen += gen.i_1 * a_vec[i].i_1;
-            b_com_gen += gen.i_0 * b_vec[i].i_0;
-            b_com_gen += gen.i_1 * b_vec[i].i_1;
-            c_com_gen += gen.i_0 * c_vec[i].i_0;
-            c_com_gen += gen.i_1 * c_vec[i].i_1;
-            d_com_gen += gen.i_0 * d_vec[i].i_0;
-            d_com_gen += gen.i_1 * d_vec[i].i_1;
-            omegas.push(Scalar::from((i + 2) as u32)); // x points for polynomial interpolation
-            rho_vec.push(Scalar::random(OsRng));
-        }
-
-        let r_a = Scalar::random(OsRng);
-        let r_b = Scalar::random(OsRng);
-        let r_c = Scalar::random(OsRng);
-        let r_d = Scalar::random(OsRng);
-
-        let a_com = (parameters.h_point * r_a + a_com_gen).to_affine();
-        let b_com = (parameters.h_point * r_b + b_com_gen).to_affine();
-        let c_com = (parameters.h_point * r_c + c_com_gen).to_affine();
-        let d_com = (parameters.h_point * r_d + d_com_gen).to_affine();
-
-        hasher.update(a_com.to_bytes());
-        hasher.update(b_com.to_bytes());
-        hasher.update(c_com.to_bytes());
-        hasher.update(d_com.to_bytes());
-
-        let coeff_vecs = get_coeffs(index, ring.len(), m, &a_vec, &b_vec, &omegas)?;
-
-        let mut x_points = Vec::<AffinePoint>::with_capacity(m);
-        let mut y_points = Vec::<AffinePoint>::with_capacity(m);
-
-        for (j, rho) in rho_vec.iter().enumerate() {
-            let mut sum = ProjectivePoint::IDENTITY;
-            for k in 0..ring.len() {
-                sum += ring[k] * coeff_vecs[k][j];
-            }
-            let x = (sum + ProjectivePoint::GENERATOR * rho).to_affine();
-            let y = (j_point * rho).to_affine();
-            hasher.update(x.to_bytes());
-            hasher.update(y.to_bytes());
+        for scalar in member.f_scalars.iter().flatten().chain([
+            &commitments.z_a_scalar,
+            &commitments.z_c_scalar,
+            &member.z_scalar,
+        ]) {
+            bytes.extend_from_slice(&scalar.to_bytes());
+        }
+        bytes.extend_from_slice(&member.tag.to_bytes());
+        bytes
+    }
 
-            x_points.push(x);
-            y_points.push(y);
+    /// Decodes a signature encoded by [`to_bytes`](Self::to_bytes),
+    /// rejecting unknown versions, trailing bytes, the point at infinity,
+    /// and coordinates or scalars that are not reduced.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader(bytes);
+        let version = reader.byte()?;
+        if version != ENCODING_VERSION {
+            return Err(format!("unsupported signature version {}", version));
+        }
+        let base = reader.byte()? as usize;
+        check_base(base)?;
+        let m = reader.byte()? as usize;
+        if m > crate::MAX_2_EXPONENT {
+            return Err("too many signature rows".to_owned());
+        }
+        let a_commitment = reader.point()?;
+        let b_commitment = reader.point()?;
+        let c_commitment = reader.point()?;
+        let d_commitment = reader.point()?;
+        let x_points = (0..m).map(|_| reader.point()).collect::<Result<_, _>>()?;
+        let y_points = (0..m).map(|_| reader.point()).collect::<Result<_, _>>()?;
+        let f_scalars = (0..m)
+            .map(|_| (1..base).map(|_| reader.scalar()).collect())
+            .collect::<Result<_, _>>()?;
+        let commitments = DigitCommitments {
+            a_commitment,
+            b_commitment,
+            c_commitment,
+            d_commitment,
+            z_a_scalar: reader.scalar()?,
+            z_c_scalar: reader.scalar()?,
+        };
+        let member = MemberProof {
+            x_points,
+            y_points,
+            f_scalars,
+            z_scalar: reader.scalar()?,
+            tag: reader.point()?,
+        };
+        if !reader.0.is_empty() {
+            return Err("trailing bytes after signature".to_owned());
         }
+        Ok(Self {
+            base,
+            commitments,
+            member,
+        })
+    }
 
-        // NOTE unwrap is fine here as the hasher will always
-        // provide a hash with proper size from which the
-        // scalar is generated
-        let xi = Scalar::from_repr(hasher.finalize()).unwrap();
+    /// Base of the decomposition of the signer's index.
+    pub fn base(&self) -> usize {
+        self.base
+    }
 
-        let mut f_vec = Vec::<Scalar>::with_capacity(m);
-        for (a, b) in a_vec.iter().zip(b_vec.iter()) {
-            f_vec.push(b.i_1 * xi + a.i_1);
-        }
+    /// Linking tag of the signature, the same for every signature of the
+    /// key in the scope it was generated in.
+    pub fn tag(&self) -> AffinePoint {
+        self.member.tag
+    }
 
-        let z_a = r_b * xi + r_a;
-        let z_c = r_c * xi + r_d;
+    /// Verifies the signature and records its tag in `store`, rejecting the
+    /// signature if its key has already signed in `scope`.
+    pub fn verify_and_record<S: TagStore + ?Sized>(
+        &self,
+        ring: &[AffinePoint],
+        message_hash: &[u8],
+        scope: &[u8],
+        parameters: &Parameters,
+        store: &mut S,
+    ) -> Result<(), String> {
+        self.verify(ring, message_hash, scope, parameters)?;
+        if !store.insert(scope, &self.member.tag)? {
+            return Err("tag already recorded in scope".to_owned());
+        }
+        Ok(())
+    }
+}
 
-        let mut xi_pow = Scalar::ONE;
-        let z_sum = rho_vec.iter().fold(Scalar::ZERO, |acc, &rho| {
-            let next = rho * xi_pow;
-            xi_pow *= xi;
-            acc + next
-        });
+/// Proves that `signers` know the keys at their indices in `ring`.
+///
+/// The digits of all indices are committed to in the same digit
+/// commitments, the rows of the `u`-th signer being `u * m..(u + 1) * m`,
+/// and the members share the challenge, so none of them can be replaced
+/// without invalidating the rest.
+pub(crate) fn prove<R: RngCore + CryptoRng>(
+    rng: &mut R,
+    signers: &[(usize, &SigningKey)],
+    ring: &[AffinePoint],
+    message_hash: &[u8],
+    scope: &[u8],
+    parameters: &Parameters,
+) -> Result<(DigitCommitments, Vec<MemberProof>), String> {
+    // pad ring
+    let base = parameters.base;
+    let mut ring = ring.to_owned();
+    let m = pad_ring_to_nm(&mut ring, base)?;
+    let rows = signers.len() * m;
+    if rows > parameters.generators.len() {
+        return Err("not enough generators".to_owned());
+    }
 
-        // note x_pow here should be xi^m due to stuff in fold
-        let z_scalar = privkey * xi_pow - z_sum;
-        privkey.zeroize();
+    // generate signature tags
+    let u_point = u_point(scope);
+    let mut j_points = Vec::<ProjectivePoint>::with_capacity(signers.len());
+    for (_, signing_key) in signers.iter() {
+        // NOTE signing keys are never zero, thus always invertible
+        j_points.push(u_point * signing_key.secret().invert().unwrap());
+    }
 
-        Ok(Self {
-            a_commitment: a_com,
-            b_commitment: b_com,
-            c_commitment: c_com,
-            d_commitment: d_com,
+    let a_vec = (0..rows)
+        .map(|_| {
+            let mut row = (0..base)
+                .map(|_| Scalar::random(&mut *rng))
+                .collect::<Vec<Scalar>>();
+            row[0] = -row[1..].iter().fold(Scalar::ZERO, |acc, a| acc + a);
+            row
+        })
+        .collect::<Vec<Vec<Scalar>>>();
+    let b_vec = signers
+        .iter()
+        .flat_map(|(index, _)| deltas(*index, base, m)) // sigma vec
+        .collect::<Vec<Vec<Scalar>>>();
+    let c_vec = a_vec
+        .iter()
+        .zip(b_vec.iter())
+        .map(|(a_row, b_row)| {
+            a_row
+                .iter()
+                .zip(b_row)
+                .map(|(a, b)| a * &(Scalar::ONE - (b + b)))
+                .collect()
+        })
+        .collect::<Vec<Vec<Scalar>>>();
+    let d_vec = a_vec
+        .iter()
+        .map(|a_row| a_row.iter().map(|a| -(a * a)).collect())
+        .collect::<Vec<Vec<Scalar>>>();
+
+    let rho_vec = (0..rows)
+        .map(|_| Scalar::random(&mut *rng))
+        .collect::<Vec<Scalar>>();
+
+    let r_a = Scalar::random(&mut *rng);
+    let r_b = Scalar::random(&mut *rng);
+    let r_c = Scalar::random(&mut *rng);
+    let r_d = Scalar::random(&mut *rng);
+
+    let a_com = parameters.commit(&a_vec, r_a);
+    let b_com = parameters.commit(&b_vec, r_b);
+    let c_com = parameters.commit(&c_vec, r_c);
+    let d_com = parameters.commit(&d_vec, r_d);
+
+    // X_j = sum_k pk_k * p_k,j + G * rho_j with a multiscalar
+    // multiplication over the ring per row
+    let coeff_vecs = (0..signers.len())
+        .flat_map(|u| get_coeffs(&a_vec[u * m..(u + 1) * m], &b_vec[u * m..(u + 1) * m]))
+        .collect::<Vec<Vec<Scalar>>>();
+    let ring_points = ring
+        .iter()
+        .map(ProjectivePoint::from)
+        .collect::<Vec<ProjectivePoint>>();
+    #[cfg(feature = "parallel")]
+    let coeff_rows = coeff_vecs.par_iter();
+    #[cfg(not(feature = "parallel"))]
+    let coeff_rows = coeff_vecs.iter();
+    let sums = coeff_rows
+        .map(|coeffs| multiscalar_mul(coeffs, &ring_points))
+        .collect::<Vec<ProjectivePoint>>();
+
+    let mut members = Vec::<MemberProof>::with_capacity(signers.len());
+    for (u, j_point) in j_points.iter().enumerate() {
+        let (x_points, y_points) = sums[u * m..(u + 1) * m]
+            .iter()
+            .zip(&rho_vec[u * m..(u + 1) * m])
+            .map(|(sum, rho)| {
+                (
+                    (sum + &(ProjectivePoint::GENERATOR * rho)).to_affine(),
+                    (j_point * rho).to_affine(),
+                )
+            })
+            .unzip();
+        members.push(MemberProof {
             x_points,
             y_points,
-            f_scalars: f_vec,
-            z_a_scalar: z_a,
-            z_c_scalar: z_c,
-            z_scalar,
+            // NOTE filled in after the challenge is computed
+            f_scalars: Vec::new(),
+            z_scalar: Scalar::ZERO,
             tag: j_point.to_affine(),
+        });
+    }
+
+    let commitments = DigitCommitments {
+        a_commitment: a_com,
+        b_commitment: b_com,
+        c_commitment: c_com,
+        d_commitment: d_com,
+        z_a_scalar: Scalar::ZERO,
+        z_c_scalar: Scalar::ZERO,
+    };
+    let xi = challenge(&ring, message_hash, scope, &u_point, &commitments, &members);
+
+    let commitments = DigitCommitments {
+        z_a_scalar: r_b * xi + r_a,
+        z_c_scalar: r_c * xi + r_d,
+        ..commitments
+    };
+
+    let f_vec = a_vec
+        .iter()
+        .zip(b_vec.iter())
+        .map(|(a_row, b_row)| {
+            a_row
+                .iter()
+                .zip(b_row)
+                .skip(1)
+                .map(|(a, b)| b * &xi + a)
+                .collect()
         })
+        .collect::<Vec<Vec<Scalar>>>();
+
+    let xi_pow_m = xi_powers(xi, m + 1).last().unwrap_or(Scalar::ONE);
+    for (u, (member, (_, signing_key))) in members.iter_mut().zip(signers.iter()).enumerate() {
+        let z_sum = rho_vec[u * m..(u + 1) * m]
+            .iter()
+            .zip(xi_powers(xi, m))
+            .fold(Scalar::ZERO, |acc, (rho, xi_pow)| acc + rho * &xi_pow);
+        member.f_scalars = f_vec[u * m..(u + 1) * m].to_vec();
+        member.z_scalar = signing_key.secret() * &xi_pow_m - z_sum;
     }
 
-    pub fn verify(
-        &self,
-        ring: &Ring,
-        message_hash: &[u8],
-        parameters: &Parameters,
-    ) -> Result<(), String> {
-        let mut hasher = Keccak256::new();
-        hasher.update(message_hash);
+    Ok((commitments, members))
+}
 
-        let mut ring = ring.to_owned();
-        let m = pad_ring_to_2n(&mut ring)?;
-        for pk in ring.iter() {
-            hasher.update(pk.to_bytes())
+/// Checks that the members of a proof in `signature_base` have the size of
+/// proofs over a ring of `m` digits in `base`.
+pub(crate) fn check_size(
+    signature_base: usize,
+    members: &[MemberProof],
+    base: usize,
+    m: usize,
+) -> Result<(), String> {
+    if signature_base != base {
+        return Err("signature base mismatch".to_owned());
+    }
+    for member in members {
+        if member.f_scalars.len() != m
+            || member.f_scalars.iter().any(|row| row.len() != base - 1)
+            || member.x_points.len() != m
+            || member.y_points.len() != m
+        {
+            return Err("signature size mismatch".to_owned());
         }
+    }
+    Ok(())
+}
 
-        let u_point = AffinePoint::GENERATOR * Scalar::from_uint_reduced(U_SCALAR_U256);
+/// Computes the Fiat-Shamir challenge of a proof on the padded ring.
+///
+/// `u_point` is the base point of the tags in `scope`.
+pub(crate) fn challenge(
+    ring: &[AffinePoint],
+    message_hash: &[u8],
+    scope: &[u8],
+    u_point: &AffinePoint,
+    commitments: &DigitCommitments,
+    members: &[MemberProof],
+) -> Scalar {
+    // include the msg hash and the scope in the challenge
+    let mut hasher = Keccak256::new();
+    hasher.update(message_hash);
+    hasher.update((scope.len() as u64).to_be_bytes());
+    hasher.update(scope);
+    hasher.update(u_point.to_bytes());
+    for pk in ring.iter() {
+        hasher.update(pk.to_bytes())
+    }
 
-        hasher.update(self.a_commitment.to_bytes());
-        hasher.update(self.b_commitment.to_bytes());
-        hasher.update(self.c_commitment.to_bytes());
-        hasher.update(self.d_commitment.to_bytes());
+    hasher.update(commitments.a_commitment.to_bytes());
+    hasher.update(commitments.b_commitment.to_bytes());
+    hasher.update(commitments.c_commitment.to_bytes());
+    hasher.update(commitments.d_commitment.to_bytes());
 
-        for (x, y) in self.x_points.iter().zip(self.y_points.iter()) {
+    // NOTE the tags are fixed before the challenge, otherwise a tag could be
+    // solved for afterwards without being the tag of the signing key
+    for member in members {
+        hasher.update(member.tag.to_bytes());
+    }
+    for member in members {
+        for (x, y) in member.x_points.iter().zip(member.y_points.iter()) {
             hasher.update(x.to_bytes());
             hasher.update(y.to_bytes());
         }
+    }
 
-        // NOTE unwrap is fine here as the hasher will always
-        // provide a hash with proper size from which the
-        // scalar is generated
-        let xi = Scalar::from_repr(hasher.finalize()).unwrap();
-
-        let f_scalars = self
-            .f_scalars
-            .iter()
-            .map(|&elem| VecElem {
-                i_0: xi - elem,
-                i_1: elem,
-            })
-            .collect::<Vec<VecElem<Scalar>>>();
+    // NOTE unwrap is fine here as the hasher will always
+    // provide a hash with proper size from which the
+    // scalar is generated
+    Scalar::from_repr(hasher.finalize()).unwrap()
+}
 
-        // check commitments
-        let mut gen_0_f = ProjectivePoint::IDENTITY;
-        let mut gen_1_f = ProjectivePoint::IDENTITY;
-        let mut gen_0_f_xi = ProjectivePoint::IDENTITY;
-        let mut gen_1_f_xi = ProjectivePoint::IDENTITY;
+/// Random linear combination of the verification equations
+///
+/// - `A + xi * B - sum_j,i g_ji * f_ji - h * z_A = 0`
+/// - `D + xi * C - sum_j,i g_ji * f_ji * (xi - f_ji) - h * z_C = 0`
+///
+/// of the digit commitments, and
+///
+/// - `sum_k pk_k * prod_f_k - sum_j xi^j * X_j - G * z = 0`
+/// - `U * sum_k prod_f_k - sum_j xi^j * Y_j - J * z = 0`
+///
+/// of every member of proofs, where `f_j0 = xi - sum_i>0 f_ji`. The scalars
+/// of the shared points are accumulated, the points of the proofs themselves
+/// are collected.
+pub(crate) struct BatchEquation {
+    m: usize,
+    ring: Vec<Scalar>,
+    generators: Vec<Vec<Scalar>>,
+    g_point: Scalar,
+    h_point: Scalar,
+    u_point: Scalar,
+    scalars: Vec<Scalar>,
+    points: Vec<ProjectivePoint>,
+}
 
-        for (g, f) in parameters.generators.iter().take(m).zip(f_scalars.iter()) {
-            gen_0_f += g.i_0 * f.i_0;
-            gen_1_f += g.i_1 * f.i_1;
-            gen_0_f_xi += g.i_0 * (f.i_0 * (xi - f.i_0));
-            gen_1_f_xi += g.i_1 * (f.i_1 * (xi - f.i_1));
+impl BatchEquation {
+    /// Equation of proofs over a padded ring of `ring_size` keys, with `m`
+    /// digits per member.
+    pub(crate) fn new(ring_size: usize, parameters: &Parameters, m: usize) -> Result<Self, String> {
+        if m > parameters.generators.len() {
+            return Err("not enough generators".to_owned());
         }
+        Ok(Self {
+            m,
+            ring: vec![Scalar::ZERO; ring_size],
+            generators: Vec::new(),
+            g_point: Scalar::ZERO,
+            h_point: Scalar::ZERO,
+            u_point: Scalar::ZERO,
+            scalars: Vec::new(),
+            points: Vec::new(),
+        })
+    }
 
-        let com_f = (gen_0_f + gen_1_f + parameters.h_point * self.z_a_scalar).to_affine();
-        let com_f_xi = (gen_0_f_xi + gen_1_f_xi + parameters.h_point * self.z_c_scalar).to_affine();
+    fn push(&mut self, scalar: Scalar, point: &AffinePoint) {
+        self.scalars.push(scalar);
+        self.points.push(ProjectivePoint::from(*point));
+    }
 
-        if (ProjectivePoint::from(self.a_commitment) + self.b_commitment * xi).to_affine() != com_f
-        {
-            return Err("ab commitment mismatch".to_owned());
+    /// Adds the equations of a proof with challenge `xi`, whose size has
+    /// been checked by [`check_size`].
+    pub(crate) fn add<R: RngCore + CryptoRng>(
+        &mut self,
+        rng: &mut R,
+        commitments: &DigitCommitments,
+        members: &[MemberProof],
+        xi: Scalar,
+    ) {
+        let base = members
+            .first()
+            .and_then(|member| member.f_scalars.first())
+            .map_or(0, |row| row.len() + 1);
+        let rows = members.len() * self.m;
+        if self.generators.len() < rows {
+            self.generators.resize(rows, vec![Scalar::ZERO; base]);
         }
 
-        if (ProjectivePoint::from(self.d_commitment) + self.c_commitment * xi).to_affine()
-            != com_f_xi
-        {
-            return Err("cd commitment mismatch".to_owned());
+        let w = [(); 2].map(|_| Scalar::random(&mut *rng));
+        // commitments to the digits of the indices
+        self.push(w[0], &commitments.a_commitment);
+        self.push(w[0] * xi, &commitments.b_commitment);
+        self.push(w[1], &commitments.d_commitment);
+        self.push(w[1] * xi, &commitments.c_commitment);
+        let f_scalars = members
+            .iter()
+            .flat_map(|member| member.f_scalars.iter())
+            .map(|row| {
+                let f_0 = xi - row.iter().fold(Scalar::ZERO, |acc, f| acc + f);
+                std::iter::once(f_0).chain(row.iter().copied()).collect()
+            })
+            .collect::<Vec<Vec<Scalar>>>();
+        for (gens, f_row) in self.generators.iter_mut().zip(f_scalars.iter()) {
+            for (g, f) in gens.iter_mut().zip(f_row) {
+                *g -= w[0] * f + w[1] * f * (xi - f);
+            }
         }
+        self.h_point -= w[0] * commitments.z_a_scalar + w[1] * commitments.z_c_scalar;
+
+        // knowledge of the keys at the committed indices and of their tags
+        for (u, member) in members.iter().enumerate() {
+            let w = [(); 2].map(|_| Scalar::random(&mut *rng));
+            let prod_f = prod_f_scalars(&f_scalars[u * self.m..(u + 1) * self.m], self.ring.len());
+            for (acc, prod) in self.ring.iter_mut().zip(prod_f.iter()) {
+                *acc += w[0] * prod;
+            }
+            self.u_point += w[1] * prod_f.iter().fold(Scalar::ZERO, |acc, prod| acc + prod);
+            for ((x, y), xi_pow) in member
+                .x_points
+                .iter()
+                .zip(member.y_points.iter())
+                .zip(xi_powers(xi, self.m))
+            {
+                self.push(-w[0] * xi_pow, x);
+                self.push(-w[1] * xi_pow, y);
+            }
+            self.g_point -= w[0] * member.z_scalar;
+            self.push(-w[1] * member.z_scalar, &member.tag);
+        }
+    }
 
-        let (sum_pk_prod_f, sum_prod_f) = sum_f_scalars(&f_scalars, &ring);
-
-        let (x_sum, y_sum) = self.sum_points_with_challenge(xi);
+    /// Checks the equations with a single multiscalar multiplication.
+    pub(crate) fn check(
+        mut self,
+        ring: &[AffinePoint],
+        parameters: &Parameters,
+        u_point: &AffinePoint,
+    ) -> Result<(), String> {
+        if self.generators.len() > parameters.generators.len() {
+            return Err("not enough generators".to_owned());
+        }
+        self.push(self.g_point, &AffinePoint::GENERATOR);
+        self.push(self.h_point, &parameters.h_point);
+        self.push(self.u_point, u_point);
+        for (scalar, point) in self.ring.iter().zip(ring.iter()) {
+            self.scalars.push(*scalar);
+            self.points.push(ProjectivePoint::from(*point));
+        }
+        for (scalars, gens) in self.generators.iter().zip(parameters.generators.iter()) {
+            self.scalars.extend(scalars);
+            self.points.extend(gens.iter().map(ProjectivePoint::from));
+        }
+        if multiscalar_mul(&self.scalars, &self.points) != ProjectivePoint::IDENTITY {
+            return Err("invalid signature".to_owned());
+        }
+        Ok(())
+    }
+}
 
-        let first_zero = sum_pk_prod_f - x_sum - AffinePoint::GENERATOR * self.z_scalar;
-        let second_zero = u_point * sum_prod_f - y_sum - self.tag * self.z_scalar;
+struct Reader<'a>(&'a [u8]);
 
-        if first_zero.to_affine() != AffinePoint::IDENTITY {
-            return Err("first constraint is nonzero".to_owned());
+impl<'a> Reader<'a> {
+    fn take(&mut self, size: usize) -> Result<&'a [u8], String> {
+        if self.0.len() < size {
+            return Err("signature too short".to_owned());
         }
+        let (head, tail) = self.0.split_at(size);
+        self.0 = tail;
+        Ok(head)
+    }
 
-        if second_zero.to_affine() != AffinePoint::IDENTITY {
-            return Err("second constraint is nonzero".to_owned());
-        }
+    fn byte(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
 
-        Ok(())
+    fn point(&mut self) -> Result<AffinePoint, String> {
+        let bytes = self.take(POINT_SIZE)?;
+        // NOTE only the compressed encodings of finite points are accepted,
+        // which are unique for every point
+        if bytes[0] != 0x02 && bytes[0] != 0x03 {
+            return Err("invalid point encoding".to_owned());
+        }
+        let point = AffinePoint::from_bytes(CompressedPoint::from_slice(bytes));
+        if point.is_none().into() {
+            return Err("invalid point encoding".to_owned());
+        }
+        Ok(point.unwrap())
     }
 
-    fn sum_points_with_challenge(&self, xi: Scalar) -> (ProjectivePoint, ProjectivePoint) {
-        let mut xi_pow = Scalar::ONE; // xi^0
-        self.x_points.iter().zip(self.y_points.iter()).fold(
-            (ProjectivePoint::IDENTITY, ProjectivePoint::IDENTITY),
-            |(acc_x, acc_y), (&x, &y)| {
-                let next_x = acc_x + x * xi_pow;
-                let next_y = acc_y + y * xi_pow;
-                xi_pow *= xi;
-                (next_x, next_y)
-            },
-        )
+    fn scalar(&mut self) -> Result<Scalar, String> {
+        let bytes = self.take(SCALAR_SIZE)?;
+        let scalar = Scalar::from_repr(*k256::FieldBytes::from_slice(bytes));
+        if scalar.is_none().into() {
+            return Err("non-canonical scalar".to_owned());
+        }
+        Ok(scalar.unwrap())
     }
 }
 
-fn deltas(num: usize, n: usize) -> Vec<VecElem<Scalar>> {
-    (0..n)
-        .map(|j| {
-            if num & 1 << j == 0 {
-                VecElem {
-                    i_0: Scalar::ONE,
-                    i_1: Scalar::ZERO,
-                }
-            } else {
-                VecElem {
-                    i_0: Scalar::ZERO,
-                    i_1: Scalar::ONE,
-                }
-            }
+// Base `base` digits of `num` as rows of `m` Kronecker deltas.
+fn deltas(num: usize, base: usize, m: usize) -> Vec<Vec<Scalar>> {
+    let mut rest = num;
+    (0..m)
+        .map(|_| {
+            let digit = rest % base;
+            rest /= base;
+            (0..base)
+                .map(|i| {
+                    if i == digit {
+                        Scalar::ONE
+                    } else {
+                        Scalar::ZERO
+                    }
+                })
+                .collect()
         })
-        .collect::<Vec<VecElem<Scalar>>>()
+        .collect::<Vec<Vec<Scalar>>>()
 }
 
-fn get_coeffs(
-    index: usize,
-    n: usize,
-    m: usize,
-    a_vec: &[VecElem<Scalar>],
-    b_vec: &[VecElem<Scalar>],
-    omegas: &[Scalar],
-) -> Result<Vec<Vec<Scalar>>, String> {
-    let mut coeff_vecs = Vec::<Vec<Scalar>>::new();
-    for k in 0..n {
-        let mut evals = vec![Scalar::ONE; m];
-        for (omega, eval) in omegas.iter().zip(evals.iter_mut()) {
-            let mut highest_order_omega = Scalar::ONE;
-            for j in 0..m {
-                if k & (1 << j) == 0 {
-                    *eval *= b_vec[j].i_0 * omega + a_vec[j].i_0;
-                } else {
-                    *eval *= b_vec[j].i_1 * omega + a_vec[j].i_1;
+// Coefficients of the polynomials
+//
+//   p_k(x) = prod_j (b_j,k_j * x + a_j,k_j) - delta(k, index) * x^m
+//
+// for every index `k` of the padded ring, where `k_j` is the j-th digit of
+// `k`, transposed so that `coeffs[d][k]` is the coefficient of `x^d` in
+// `p_k`. The products are expanded one digit at a time, reusing the
+// polynomials of the lower digits, like in `prod_f_scalars`. Only the
+// polynomial of the signer has a nonzero coefficient of `x^m`, which is 1, so
+// subtracting `x^m` is the same as dropping the highest coefficients.
+fn get_coeffs(a_vec: &[Vec<Scalar>], b_vec: &[Vec<Scalar>]) -> Vec<Vec<Scalar>> {
+    let mut coeffs = vec![vec![Scalar::ONE]];
+    for (a_row, b_row) in a_vec.iter().zip(b_vec) {
+        let low = coeffs[0].len();
+        let mut next = vec![Vec::with_capacity(low * a_row.len()); coeffs.len() + 1];
+        for (a, b) in a_row.iter().zip(b_row) {
+            for (d, column) in next.iter_mut().enumerate() {
+                for k in 0..low {
+                    let mut coeff = Scalar::ZERO;
+                    if let Some(lower) = coeffs.get(d) {
+                        coeff += a * &lower[k];
+                    }
+                    if d > 0 {
+                        coeff += b * &coeffs[d - 1][k];
+                    }
+                    column.push(coeff);
                 }
-                highest_order_omega *= omega;
-            }
-            if k == index {
-                *eval -= highest_order_omega;
             }
         }
-
-        let poly = Polynomial::interpolate(omegas, &evals).map_err(|e| e.to_string())?;
-        coeff_vecs.push(poly.into_coeffs());
+        coeffs = next;
     }
-    Ok(coeff_vecs)
+    coeffs.pop();
+    coeffs
 }
 
-fn sum_f_scalars(f_scalars: &[VecElem<Scalar>], ring: &Ring) -> (ProjectivePoint, Scalar) {
-    let mut sum_pk_prod_f = ProjectivePoint::IDENTITY;
-    let mut sum_prod_f = Scalar::ZERO;
-    for (k, &pk) in ring.iter().enumerate() {
-        let prod_f = f_scalars
-            .iter()
-            .enumerate()
-            .fold(Scalar::ONE, |acc, (j, f)| {
-                if k & (1 << j) == 0 {
-                    acc * f.i_0
-                } else {
-                    acc * f.i_1
-                }
-            });
+fn xi_powers(xi: Scalar, m: usize) -> impl Iterator<Item = Scalar> {
+    std::iter::successors(Some(Scalar::ONE), move |pow| Some(pow * &xi)).take(m)
+}
 
-        sum_pk_prod_f += pk * prod_f;
-        sum_prod_f += prod_f;
+// Products `prod_j f_j,k_j` for every index `k` of the padded ring, where
+// `k_j` is the j-th digit of `k`. They are built up one digit at a time,
+// reusing the products of the lower digits, instead of being computed index
+// by index.
+fn prod_f_scalars(f_scalars: &[Vec<Scalar>], ring_size: usize) -> Vec<Scalar> {
+    let mut prod_f = Vec::with_capacity(ring_size);
+    prod_f.push(Scalar::ONE);
+    for f_row in f_scalars {
+        let low = prod_f.len();
+        for f in &f_row[1..] {
+            for k in 0..low {
+                prod_f.push(prod_f[k] * f);
+            }
+        }
+        for prod in prod_f[..low].iter_mut() {
+            *prod *= f_row[0];
+        }
     }
-
-    (sum_pk_prod_f, sum_prod_f)
+    prod_f.truncate(ring_size);
+    prod_f
 }
 
 #[cfg(test)]
-mod test {
+pub(crate) mod test {
     use super::*;
     use crate::keypair::Keypair;
+    use crate::tag_store::MemoryTagStore;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    const SCOPE: &[u8] = b"guild";
+
+    /// Proves like [`prove`], but with tags that do not belong to the keys:
+    /// the `Y_j` are chosen as `U * r_j` and `J` is solved from the equation
+    /// of `U` once the challenge is known, which is only possible if the
+    /// challenge does not depend on `J`.
+    pub(crate) fn forge_tags(
+        signers: &[(usize, &SigningKey)],
+        ring: &[AffinePoint],
+        message_hash: &[u8],
+        scope: &[u8],
+        parameters: &Parameters,
+    ) -> (DigitCommitments, Vec<MemberProof>) {
+        let base = parameters.base;
+        let mut ring = ring.to_owned();
+        let m = pad_ring_to_nm(&mut ring, base).unwrap();
+        let rows = signers.len() * m;
+        let u_point = u_point(scope);
 
-    fn test_signature() -> Signature {
-        Signature {
-            a_commitment: AffinePoint::IDENTITY,
-            b_commitment: AffinePoint::IDENTITY,
-            c_commitment: AffinePoint::IDENTITY,
-            d_commitment: AffinePoint::IDENTITY,
-            x_points: vec![AffinePoint::GENERATOR; 3],
-            y_points: vec![AffinePoint::GENERATOR; 3],
-            f_scalars: Vec::new(),
+        let a_vec = (0..rows)
+            .map(|_| {
+                let mut row = (0..base)
+                    .map(|_| Scalar::random(OsRng))
+                    .collect::<Vec<Scalar>>();
+                row[0] = -row[1..].iter().fold(Scalar::ZERO, |acc, a| acc + a);
+                row
+            })
+            .collect::<Vec<Vec<Scalar>>>();
+        let b_vec = signers
+            .iter()
+            .flat_map(|(index, _)| deltas(*index, base, m))
+            .collect::<Vec<Vec<Scalar>>>();
+        let c_vec = a_vec
+            .iter()
+            .zip(b_vec.iter())
+            .map(|(a_row, b_row)| {
+                a_row
+                    .iter()
+                    .zip(b_row)
+                    .map(|(a, b)| a * &(Scalar::ONE - (b + b)))
+                    .collect()
+            })
+            .collect::<Vec<Vec<Scalar>>>();
+        let d_vec = a_vec
+            .iter()
+            .map(|a_row| a_row.iter().map(|a| -(a * a)).collect())
+            .collect::<Vec<Vec<Scalar>>>();
+        let rho_vec = (0..rows)
+            .map(|_| Scalar::random(OsRng))
+            .collect::<Vec<Scalar>>();
+        let r_vec = (0..rows)
+            .map(|_| Scalar::random(OsRng))
+            .collect::<Vec<Scalar>>();
+        let [r_a, r_b, r_c, r_d] = [(); 4].map(|_| Scalar::random(OsRng));
+
+        let ring_points = ring
+            .iter()
+            .map(ProjectivePoint::from)
+            .collect::<Vec<ProjectivePoint>>();
+        let mut members = Vec::<MemberProof>::with_capacity(signers.len());
+        for u in 0..signers.len() {
+            let coeffs = get_coeffs(&a_vec[u * m..(u + 1) * m], &b_vec[u * m..(u + 1) * m]);
+            members.push(MemberProof {
+                x_points: coeffs
+                    .iter()
+                    .zip(&rho_vec[u * m..(u + 1) * m])
+                    .map(|(coeffs, rho)| {
+                        (multiscalar_mul(coeffs, &ring_points) + ProjectivePoint::GENERATOR * rho)
+                            .to_affine()
+                    })
+                    .collect(),
+                y_points: r_vec[u * m..(u + 1) * m]
+                    .iter()
+                    .map(|r| (u_point * r).to_affine())
+                    .collect(),
+                f_scalars: Vec::new(),
+                z_scalar: Scalar::ZERO,
+                // NOTE replaced by the forged tag after the challenge
+                tag: u_point,
+            });
+        }
+
+        let commitments = DigitCommitments {
+            a_commitment: parameters.commit(&a_vec, r_a),
+            b_commitment: parameters.commit(&b_vec, r_b),
+            c_commitment: parameters.commit(&c_vec, r_c),
+            d_commitment: parameters.commit(&d_vec, r_d),
             z_a_scalar: Scalar::ZERO,
             z_c_scalar: Scalar::ZERO,
-            z_scalar: Scalar::ZERO,
-            tag: AffinePoint::GENERATOR,
+        };
+        let xi = challenge(&ring, message_hash, scope, &u_point, &commitments, &members);
+        let commitments = DigitCommitments {
+            z_a_scalar: r_b * xi + r_a,
+            z_c_scalar: r_c * xi + r_d,
+            ..commitments
+        };
+
+        let xi_pow_m = xi_powers(xi, m + 1).last().unwrap();
+        for (u, (member, (_, signing_key))) in members.iter_mut().zip(signers).enumerate() {
+            let digits = u * m..(u + 1) * m;
+            member.f_scalars = a_vec[digits.clone()]
+                .iter()
+                .zip(&b_vec[digits.clone()])
+                .map(|(a_row, b_row)| {
+                    a_row
+                        .iter()
+                        .zip(b_row)
+                        .skip(1)
+                        .map(|(a, b)| b * &xi + a)
+                        .collect()
+                })
+                .collect();
+            let (rho_sum, r_sum) = rho_vec[digits.clone()]
+                .iter()
+                .zip(&r_vec[digits])
+                .zip(xi_powers(xi, m))
+                .fold(
+                    (Scalar::ZERO, Scalar::ZERO),
+                    |(rho_acc, r_acc), ((rho, r), xi_pow)| {
+                        (rho_acc + rho * &xi_pow, r_acc + r * &xi_pow)
+                    },
+                );
+            member.z_scalar = signing_key.secret() * &xi_pow_m - rho_sum;
+            // U * sum_k prod_f_k = U * xi^m = sum_j xi^j * Y_j + J * z
+            let j_scalar = (xi_pow_m - r_sum) * member.z_scalar.invert().unwrap();
+            member.tag = (u_point * j_scalar).to_affine();
         }
+
+        (commitments, members)
     }
 
-    fn test_ring(size: usize) -> Ring {
-        let mut ring = Ring::with_capacity(size);
+    fn test_ring(size: usize) -> Vec<AffinePoint> {
+        let mut ring = Vec::with_capacity(size);
         for _ in 0..size {
             ring.push((AffinePoint::GENERATOR * Scalar::random(OsRng)).to_affine());
         }
@@ -407,98 +975,101 @@ mod test {
     }
 
     #[test]
-    #[rustfmt::skip]
     fn kronecker_delta() {
+        let zero = Scalar::ZERO;
+        let one = Scalar::ONE;
         let index = 1234_usize; //0b0000010011010010
         let m = 11; // N = 2^11
-        let d = deltas(index, m);
+        let d = deltas(index, 2, m);
 
         assert_eq!(d.len(), 11);
-        assert_eq!(d[0], VecElem { i_0: Scalar::ONE, i_1: Scalar::ZERO }); // 0
-        assert_eq!(d[1], VecElem { i_0: Scalar::ZERO, i_1: Scalar::ONE }); // 1
-        assert_eq!(d[2], VecElem { i_0: Scalar::ONE, i_1: Scalar::ZERO }); // 0
-        assert_eq!(d[3], VecElem { i_0: Scalar::ONE, i_1: Scalar::ZERO }); // 0
-        assert_eq!(d[4], VecElem { i_0: Scalar::ZERO, i_1: Scalar::ONE }); // 1
-        assert_eq!(d[5], VecElem { i_0: Scalar::ONE, i_1: Scalar::ZERO }); // 0
-        assert_eq!(d[6], VecElem { i_0: Scalar::ZERO, i_1: Scalar::ONE }); // 1
-        assert_eq!(d[7], VecElem { i_0: Scalar::ZERO, i_1: Scalar::ONE }); // 1
-        assert_eq!(d[8], VecElem { i_0: Scalar::ONE, i_1: Scalar::ZERO }); // 0
-        assert_eq!(d[9], VecElem { i_0: Scalar::ONE, i_1: Scalar::ZERO }); // 0
-        assert_eq!(d[10], VecElem { i_0: Scalar::ZERO, i_1: Scalar::ONE }); // 1
+        for (row, bit) in d.iter().zip([0, 1, 0, 0, 1, 0, 1, 1, 0, 0, 1]) {
+            let expected = if bit == 0 { [one, zero] } else { [zero, one] };
+            assert_eq!(row, &expected);
+        }
+
+        // 1234 = 1200201 in base 3, least significant digit first
+        let d = deltas(index, 3, 7);
+        for (row, digit) in d.iter().zip([1, 0, 2, 0, 0, 2, 1]) {
+            let mut expected = [zero; 3];
+            expected[digit] = one;
+            assert_eq!(row, &expected);
+        }
     }
 
     #[test]
-    #[rustfmt::skip]
-    fn coeff_interpolation() {
-        let m = 2; // exponent
-        let n = 4; // 2^exponent
+    fn coeff_expansion() {
         let index = 1; // we are second in the ring
-        let omegas = vec![Scalar::from(2u32), Scalar::from(3u32)];
         let a_vec = vec![
-            VecElem { i_0: Scalar::ONE, i_1: Scalar::from(2u32) },
-            VecElem { i_0: Scalar::from(3u32), i_1: Scalar::from(4u32) },
+            vec![Scalar::ONE, Scalar::from(2u32)],
+            vec![Scalar::from(3u32), Scalar::from(4u32)],
         ];
-        let b_vec = vec![
-            VecElem { i_0: Scalar::from(5u32), i_1: Scalar::from(6u32) },
-            VecElem { i_0: Scalar::from(7u32), i_1: Scalar::from(8u32) },
-        ];
-
-        let coeffs = get_coeffs(index, n, m, &a_vec, &b_vec, &omegas).unwrap();
-        assert_eq!(-coeffs[0][0], Scalar::from(207u32));
-        assert_eq!(-coeffs[1][0], Scalar::from(240u32));
-        assert_eq!(-coeffs[2][0], Scalar::from(236u32));
-        assert_eq!(-coeffs[3][0], Scalar::from(280u32));
-        assert_eq!(coeffs[0][1], Scalar::from(197u32));
-        assert_eq!(coeffs[1][1], Scalar::from(237u32));
-        assert_eq!(coeffs[2][1], Scalar::from(228u32));
-        assert_eq!(coeffs[3][1], Scalar::from(280u32));
-
-        assert_eq!(coeffs[0][0] + omegas[0] * coeffs[0][1], Scalar::from(187u32));
-        assert_eq!(coeffs[0][0] + omegas[1] * coeffs[0][1], Scalar::from(384u32));
-
-        assert_eq!(coeffs[1][0] + omegas[0] * coeffs[1][1], Scalar::from(234u32));
-        assert_eq!(coeffs[1][0] + omegas[1] * coeffs[1][1], Scalar::from(471u32));
+        let b_vec = deltas(index, 2, 2);
 
-        assert_eq!(coeffs[2][0] + omegas[0] * coeffs[2][1], Scalar::from(220u32));
-        assert_eq!(coeffs[2][0] + omegas[1] * coeffs[2][1], Scalar::from(448u32));
+        // p_0 = 1 * (x + 3), p_1 = (x + 2) * (x + 3) - x^2, p_2 = 1 * 4, p_3 = (x + 2) * 4
+        let coeffs = get_coeffs(&a_vec, &b_vec);
+        assert_eq!(coeffs.len(), 2);
+        assert_eq!(coeffs[0], [3u32, 6, 4, 8].map(Scalar::from));
+        assert_eq!(coeffs[1], [1u32, 5, 0, 4].map(Scalar::from));
 
-        assert_eq!(coeffs[3][0] + omegas[0] * coeffs[3][1], Scalar::from(280u32));
-        assert_eq!(coeffs[3][0] + omegas[1] * coeffs[3][1], Scalar::from(560u32));
+        // the coefficients evaluate to the products of the rows
+        let (base, m, index) = (3, 4, 47);
+        let a_vec = (0..m)
+            .map(|_| (0..base).map(|_| Scalar::random(OsRng)).collect())
+            .collect::<Vec<Vec<Scalar>>>();
+        let b_vec = deltas(index, base, m);
+        let coeffs = get_coeffs(&a_vec, &b_vec);
+        let x = Scalar::random(OsRng);
+        for k in 0..81 {
+            let digits = deltas(k, base, m);
+            let mut expected = Scalar::ONE;
+            for (j, digit_row) in digits.iter().enumerate() {
+                let digit = digit_row.iter().position(|d| *d == Scalar::ONE).unwrap();
+                expected *= b_vec[j][digit] * x + a_vec[j][digit];
+            }
+            if k == index {
+                expected -= xi_powers(x, m + 1).last().unwrap();
+            }
+            let eval = coeffs
+                .iter()
+                .zip(xi_powers(x, m))
+                .fold(Scalar::ZERO, |acc, (column, pow)| acc + column[k] * pow);
+            assert_eq!(eval, expected);
+        }
     }
 
     #[test]
-    fn sum_xy_points() {
-        let signature = test_signature();
-        let (x_sum, y_sum) = signature.sum_points_with_challenge(Scalar::ONE);
-        assert_eq!(x_sum, AffinePoint::GENERATOR * Scalar::from(3u32));
-        assert_eq!(y_sum, AffinePoint::GENERATOR * Scalar::from(3u32));
-        let (x_sum, y_sum) = signature.sum_points_with_challenge(Scalar::from(3u32));
-        assert_eq!(x_sum, AffinePoint::GENERATOR * Scalar::from(13u32));
-        assert_eq!(y_sum, AffinePoint::GENERATOR * Scalar::from(13u32));
+    fn challenge_powers() {
+        let powers = xi_powers(Scalar::from(3u32), 4).collect::<Vec<_>>();
+        assert_eq!(powers, [1u32, 3, 9, 27].map(Scalar::from));
+        assert_eq!(xi_powers(Scalar::ONE, 0).count(), 0);
     }
 
     #[test]
     #[rustfmt::skip]
-    fn f_scalars_summed() {
-        let ring = vec![
-            AffinePoint::GENERATOR,
-            (AffinePoint::GENERATOR * Scalar::from(2u32)).to_affine(),
+    fn f_scalars_multiplied() {
+        let f_scalars = vec![
+            vec![Scalar::ONE, Scalar::from(2u32)],
+            vec![Scalar::from(3u32), Scalar::from(4u32)],
+            vec![Scalar::from(5u32), Scalar::from(6u32)],
         ];
 
+        let prod_f = prod_f_scalars(&f_scalars, 2);
+        assert_eq!(prod_f, [15u32, 30].map(Scalar::from));
+        let prod_f = prod_f_scalars(&f_scalars, 8);
+        assert_eq!(prod_f, [15u32, 30, 20, 40, 18, 36, 24, 48].map(Scalar::from));
+
         let f_scalars = vec![
-            VecElem { i_0: Scalar::ONE, i_1: Scalar::from(2u32) },
-            VecElem { i_0: Scalar::from(3u32), i_1: Scalar::from(4u32) },
-            VecElem { i_0: Scalar::from(5u32), i_1: Scalar::from(6u32) },
+            vec![Scalar::ONE, Scalar::from(2u32), Scalar::from(3u32)],
+            vec![Scalar::from(4u32), Scalar::from(5u32), Scalar::from(6u32)],
         ];
-
-        let (pk_sum, sum) = sum_f_scalars(&f_scalars, &ring);
-        assert_eq!(sum, Scalar::from(45u32));
-        assert_eq!(pk_sum, AffinePoint::GENERATOR * Scalar::from(75u32));
+        let prod_f = prod_f_scalars(&f_scalars, 9);
+        assert_eq!(prod_f, [4u32, 8, 12, 5, 10, 15, 6, 12, 18].map(Scalar::from));
     }
 
     #[test]
     fn valid_signature() {
-        let parameters = Parameters::new(10);
+        let parameters = Parameters::new(2, 10).unwrap();
         let keypair = Keypair::random();
         let mut ring = test_ring(5);
         let index = 2_usize;
@@ -509,14 +1080,108 @@ mod test {
         hasher.update(msg);
         let msg_hash = hasher.finalize();
 
-        let signature =
-            Signature::new(index, &ring, &msg_hash, keypair.private, &parameters).unwrap();
-        assert!(signature.verify(&ring, &msg_hash, &parameters).is_ok());
+        let signature = Signature::new(
+            &mut OsRng,
+            index,
+            &ring,
+            &msg_hash,
+            SCOPE,
+            &keypair.private,
+            &parameters,
+        )
+        .unwrap();
+        assert!(signature
+            .verify(&ring, &msg_hash, SCOPE, &parameters)
+            .is_ok());
+
+        // a ring of a single key has no digits, but the tag is still checked
+        let ring = vec![keypair.public];
+        let signature = Signature::new(
+            &mut OsRng,
+            0,
+            &ring,
+            &msg_hash,
+            SCOPE,
+            &keypair.private,
+            &parameters,
+        )
+        .unwrap();
+        assert!(signature
+            .verify(&ring, &msg_hash, SCOPE, &parameters)
+            .is_ok());
+        let mut forged = signature.clone();
+        forged.member.tag = keypair.public;
+        assert!(forged.verify(&ring, &msg_hash, SCOPE, &parameters).is_err());
+    }
+
+    #[test]
+    fn n_ary_signatures() {
+        let keypair = Keypair::random();
+        let mut ring = test_ring(20);
+        let index = 17_usize;
+        ring[index] = keypair.public;
+        let msg_hash = Keccak256::digest(b"n-ary");
+
+        for (base, m) in [(3, 3), (4, 3), (8, 2), (16, 2)] {
+            let parameters = Parameters::new(base, m).unwrap();
+            let signature = Signature::new(
+                &mut OsRng,
+                index,
+                &ring,
+                &msg_hash,
+                SCOPE,
+                &keypair.private,
+                &parameters,
+            )
+            .unwrap();
+            assert_eq!(signature.base(), base);
+            assert_eq!(signature.member.f_scalars.len(), m);
+            assert!(signature
+                .verify(&ring, &msg_hash, SCOPE, &parameters)
+                .is_ok());
+            assert!(signature
+                .verify(&ring, &Keccak256::digest(b"other"), SCOPE, &parameters)
+                .is_err());
+
+            let decoded = Signature::from_bytes(&signature.to_bytes()).unwrap();
+            assert!(decoded.verify(&ring, &msg_hash, SCOPE, &parameters).is_ok());
+            // the tag does not depend on the base
+            assert_eq!(signature.tag(), decoded.tag());
+        }
+
+        // too few rows of generators for the ring
+        let parameters = Parameters::new(4, 2).unwrap();
+        assert!(Signature::new(
+            &mut OsRng,
+            index,
+            &ring,
+            &msg_hash,
+            SCOPE,
+            &keypair.private,
+            &parameters
+        )
+        .is_err());
+        // verifying in another base
+        let parameters = Parameters::new(4, 3).unwrap();
+        let signature = Signature::new(
+            &mut OsRng,
+            index,
+            &ring,
+            &msg_hash,
+            SCOPE,
+            &keypair.private,
+            &parameters,
+        )
+        .unwrap();
+        assert_eq!(
+            signature.verify(&ring, &msg_hash, SCOPE, &Parameters::new(2, 10).unwrap()),
+            Err("signature base mismatch".to_owned())
+        );
     }
 
     #[test]
     fn invalid_not_in_ring() {
-        let parameters = Parameters::new(10);
+        let parameters = Parameters::new(2, 10).unwrap();
         let keypair = Keypair::random();
         let ring = test_ring(30);
         let index = 2_usize;
@@ -525,14 +1190,24 @@ mod test {
         hasher.update(msg);
         let msg_hash = hasher.finalize();
 
-        let signature =
-            Signature::new(index, &ring, &msg_hash, keypair.private, &parameters).unwrap();
-        assert!(signature.verify(&ring, &msg_hash, &parameters).is_err());
+        let signature = Signature::new(
+            &mut OsRng,
+            index,
+            &ring,
+            &msg_hash,
+            SCOPE,
+            &keypair.private,
+            &parameters,
+        )
+        .unwrap();
+        assert!(signature
+            .verify(&ring, &msg_hash, SCOPE, &parameters)
+            .is_err());
     }
 
     #[test]
     fn invalid_index() {
-        let parameters = Parameters::new(10);
+        let parameters = Parameters::new(2, 10).unwrap();
         let keypair = Keypair::random();
         let mut ring = test_ring(10);
         ring[7] = keypair.public;
@@ -542,14 +1217,24 @@ mod test {
         hasher.update(msg);
         let msg_hash = hasher.finalize();
 
-        let signature =
-            Signature::new(index, &ring, &msg_hash, keypair.private, &parameters).unwrap();
-        assert!(signature.verify(&ring, &msg_hash, &parameters).is_err());
+        let signature = Signature::new(
+            &mut OsRng,
+            index,
+            &ring,
+            &msg_hash,
+            SCOPE,
+            &keypair.private,
+            &parameters,
+        )
+        .unwrap();
+        assert!(signature
+            .verify(&ring, &msg_hash, SCOPE, &parameters)
+            .is_err());
     }
 
     #[test]
     fn invalid_msg_hash() {
-        let parameters = Parameters::new(10);
+        let parameters = Parameters::new(2, 10).unwrap();
         let keypair = Keypair::random();
         let mut ring = test_ring(10);
         let index = 3_usize;
@@ -559,21 +1244,29 @@ mod test {
         hasher.update(msg);
         let msg_hash = hasher.finalize();
 
-        let signature =
-            Signature::new(index, &ring, &msg_hash, keypair.private, &parameters).unwrap();
+        let signature = Signature::new(
+            &mut OsRng,
+            index,
+            &ring,
+            &msg_hash,
+            SCOPE,
+            &keypair.private,
+            &parameters,
+        )
+        .unwrap();
 
         let wrong_msg = b"Some other message";
         let mut hasher = Keccak256::new();
         hasher.update(wrong_msg);
         let wrong_msg_hash = hasher.finalize();
         assert!(signature
-            .verify(&ring, &wrong_msg_hash, &parameters)
+            .verify(&ring, &wrong_msg_hash, SCOPE, &parameters)
             .is_err());
     }
 
     #[test]
     fn invalid_different_ring() {
-        let parameters = Parameters::new(10);
+        let parameters = Parameters::new(2, 10).unwrap();
         let keypair = Keypair::random();
         let mut ring = test_ring(8);
         let index = 5_usize;
@@ -583,15 +1276,52 @@ mod test {
         hasher.update(msg);
         let msg_hash = hasher.finalize();
 
-        let signature =
-            Signature::new(index, &ring, &msg_hash, keypair.private, &parameters).unwrap();
+        let signature = Signature::new(
+            &mut OsRng,
+            index,
+            &ring,
+            &msg_hash,
+            SCOPE,
+            &keypair.private,
+            &parameters,
+        )
+        .unwrap();
         ring.push(AffinePoint::GENERATOR);
-        assert!(signature.verify(&ring, &msg_hash, &parameters).is_err());
+        assert!(signature
+            .verify(&ring, &msg_hash, SCOPE, &parameters)
+            .is_err());
+    }
+
+    #[test]
+    fn hash_derived_parameters() {
+        let parameters = Parameters::new(2, 10).unwrap();
+        assert_eq!(parameters, Parameters::new(2, 10).unwrap());
+        // the generators do not depend on how many are derived
+        assert_eq!(
+            parameters.generators[..4],
+            Parameters::new(2, 4).unwrap().generators
+        );
+        // nor on the base, apart from the length of the rows
+        let base_4 = Parameters::new(4, 10).unwrap();
+        for (row, base_4_row) in parameters.generators.iter().zip(&base_4.generators) {
+            assert_eq!(row[..], base_4_row[..2]);
+        }
+        assert!(Parameters::new(1, 10).is_err());
+        assert!(Parameters::new(MAX_BASE + 1, 10).is_err());
+
+        let mut points = vec![AffinePoint::GENERATOR, base_4.h_point];
+        for gens in base_4.generators.iter() {
+            points.extend(gens);
+        }
+        for (i, point) in points.iter().enumerate() {
+            assert_ne!(*point, AffinePoint::IDENTITY);
+            assert!(!points[i + 1..].contains(point));
+        }
     }
 
     #[test]
     fn invalid_parameters() {
-        let parameters = Parameters::new(10);
+        let parameters = Parameters::new(2, 10).unwrap();
         let keypair = Keypair::random();
         let mut ring = test_ring(8);
         let index = 5_usize;
@@ -601,15 +1331,36 @@ mod test {
         hasher.update(msg);
         let msg_hash = hasher.finalize();
 
-        let signature =
-            Signature::new(index, &ring, &msg_hash, keypair.private, &parameters).unwrap();
-        let parameters = Parameters::new(10);
-        assert!(signature.verify(&ring, &msg_hash, &parameters).is_err());
+        let signature = Signature::new(
+            &mut OsRng,
+            index,
+            &ring,
+            &msg_hash,
+            SCOPE,
+            &keypair.private,
+            &parameters,
+        )
+        .unwrap();
+        // generators of known discrete logarithm
+        let parameters = Parameters {
+            base: 2,
+            generators: (0..10)
+                .map(|_| {
+                    (0..2)
+                        .map(|_| (AffinePoint::GENERATOR * Scalar::random(OsRng)).to_affine())
+                        .collect()
+                })
+                .collect(),
+            h_point: (AffinePoint::GENERATOR * Scalar::random(OsRng)).to_affine(),
+        };
+        assert!(signature
+            .verify(&ring, &msg_hash, SCOPE, &parameters)
+            .is_err());
     }
 
     #[test]
     fn linkability() {
-        let parameters = Parameters::new(10);
+        let parameters = Parameters::new(2, 10).unwrap();
         let keypair = Keypair::random();
         let mut ring = test_ring(8);
         let index = 5_usize;
@@ -620,14 +1371,34 @@ mod test {
         let msg_hash = hasher.finalize();
 
         // sign exactly the same ring with same message
-        let signature =
-            Signature::new(index, &ring, &msg_hash, keypair.private, &parameters).unwrap();
-        assert!(signature.verify(&ring, &msg_hash, &parameters).is_ok());
-
-        let same_signature =
-            Signature::new(index, &ring, &msg_hash, keypair.private, &parameters).unwrap();
-        assert!(same_signature.verify(&ring, &msg_hash, &parameters).is_ok());
-        assert_eq!(same_signature.tag, signature.tag);
+        let signature = Signature::new(
+            &mut OsRng,
+            index,
+            &ring,
+            &msg_hash,
+            SCOPE,
+            &keypair.private,
+            &parameters,
+        )
+        .unwrap();
+        assert!(signature
+            .verify(&ring, &msg_hash, SCOPE, &parameters)
+            .is_ok());
+
+        let same_signature = Signature::new(
+            &mut OsRng,
+            index,
+            &ring,
+            &msg_hash,
+            SCOPE,
+            &keypair.private,
+            &parameters,
+        )
+        .unwrap();
+        assert!(same_signature
+            .verify(&ring, &msg_hash, SCOPE, &parameters)
+            .is_ok());
+        assert_eq!(same_signature.tag(), signature.tag());
 
         // change message
         let msg = b"asdf";
@@ -635,16 +1406,303 @@ mod test {
         hasher.update(msg);
         let msg_hash = hasher.finalize();
 
-        let diff_msg_sig =
-            Signature::new(index, &ring, &msg_hash, keypair.private, &parameters).unwrap();
-        assert!(diff_msg_sig.verify(&ring, &msg_hash, &parameters).is_ok());
-        assert_eq!(diff_msg_sig.tag, signature.tag);
-        // change ring and even the parameters
-        let parameters = Parameters::new(10);
+        let diff_msg_sig = Signature::new(
+            &mut OsRng,
+            index,
+            &ring,
+            &msg_hash,
+            SCOPE,
+            &keypair.private,
+            &parameters,
+        )
+        .unwrap();
+        assert!(diff_msg_sig
+            .verify(&ring, &msg_hash, SCOPE, &parameters)
+            .is_ok());
+        assert_eq!(diff_msg_sig.tag(), signature.tag());
+        // change ring
         ring.push(AffinePoint::GENERATOR);
-        let different_sig =
-            Signature::new(index, &ring, &msg_hash, keypair.private, &parameters).unwrap();
-        assert!(different_sig.verify(&ring, &msg_hash, &parameters).is_ok());
-        assert_eq!(different_sig.tag, signature.tag);
+        let different_sig = Signature::new(
+            &mut OsRng,
+            index,
+            &ring,
+            &msg_hash,
+            SCOPE,
+            &keypair.private,
+            &parameters,
+        )
+        .unwrap();
+        assert!(different_sig
+            .verify(&ring, &msg_hash, SCOPE, &parameters)
+            .is_ok());
+        assert_eq!(different_sig.tag(), signature.tag());
+
+        // signatures in another scope are not linkable to the above
+        let other_scope = b"other guild";
+        let scoped_sig = Signature::new(
+            &mut OsRng,
+            index,
+            &ring,
+            &msg_hash,
+            other_scope,
+            &keypair.private,
+            &parameters,
+        )
+        .unwrap();
+        assert!(scoped_sig
+            .verify(&ring, &msg_hash, other_scope, &parameters)
+            .is_ok());
+        assert_ne!(scoped_sig.tag(), signature.tag());
+        // and are only valid in their own scope
+        assert!(scoped_sig
+            .verify(&ring, &msg_hash, SCOPE, &parameters)
+            .is_err());
+    }
+
+    #[test]
+    fn forged_tag() {
+        let parameters = Parameters::new(2, 10).unwrap();
+        let keypair = Keypair::random();
+        let mut ring = test_ring(4);
+        let index = 1_usize;
+        ring[index] = keypair.public;
+        let msg_hash = Keccak256::digest(b"join again");
+
+        let (commitments, mut members) = forge_tags(
+            &[(index, &keypair.private)],
+            &ring,
+            &msg_hash,
+            SCOPE,
+            &parameters,
+        );
+        let signature = Signature {
+            base: parameters.base,
+            commitments,
+            member: members.pop().unwrap(),
+        };
+        // a fresh tag, not the one of the key in the scope
+        let honest = Signature::new(
+            &mut OsRng,
+            index,
+            &ring,
+            &msg_hash,
+            SCOPE,
+            &keypair.private,
+            &parameters,
+        )
+        .unwrap();
+        assert_ne!(signature.tag(), honest.tag());
+        assert_eq!(
+            signature.verify(&ring, &msg_hash, SCOPE, &parameters),
+            Err("invalid signature".to_owned())
+        );
+    }
+
+    #[test]
+    fn double_join() {
+        let parameters = Parameters::new(2, 10).unwrap();
+        let keypair = Keypair::random();
+        let mut ring = test_ring(4);
+        let index = 1_usize;
+        ring[index] = keypair.public;
+        let mut store = MemoryTagStore::new();
+
+        let sign = |msg: &[u8], scope: &[u8]| {
+            let msg_hash = Keccak256::digest(msg);
+            let signature = Signature::new(
+                &mut OsRng,
+                index,
+                &ring,
+                &msg_hash,
+                scope,
+                &keypair.private,
+                &parameters,
+            )
+            .unwrap();
+            (msg_hash, signature)
+        };
+
+        let (msg_hash, signature) = sign(b"join", SCOPE);
+        assert!(signature
+            .verify_and_record(&ring, &msg_hash, SCOPE, &parameters, &mut store)
+            .is_ok());
+        // the same key joining again with another signature
+        let (msg_hash, signature) = sign(b"join again", SCOPE);
+        assert_eq!(
+            signature.verify_and_record(&ring, &msg_hash, SCOPE, &parameters, &mut store),
+            Err("tag already recorded in scope".to_owned())
+        );
+        // but it may join another scope
+        let (msg_hash, signature) = sign(b"join", b"other guild");
+        assert!(signature
+            .verify_and_record(&ring, &msg_hash, b"other guild", &parameters, &mut store)
+            .is_ok());
+
+        // invalid signatures are not recorded
+        let mut store = MemoryTagStore::new();
+        let (msg_hash, signature) = sign(b"join", SCOPE);
+        let wrong_msg_hash = Keccak256::digest(b"another message");
+        assert!(signature
+            .verify_and_record(&ring, &wrong_msg_hash, SCOPE, &parameters, &mut store)
+            .is_err());
+        assert!(signature
+            .verify_and_record(&ring, &msg_hash, SCOPE, &parameters, &mut store)
+            .is_ok());
+    }
+
+    #[test]
+    fn batch_verification() {
+        let parameters = Parameters::new(2, 10).unwrap();
+        let keypairs = (0..5).map(|_| Keypair::random()).collect::<Vec<_>>();
+        let mut ring = test_ring(11);
+        for (i, keypair) in keypairs.iter().enumerate() {
+            ring[2 * i] = keypair.public;
+        }
+
+        let msg_hashes = (0..keypairs.len() as u8)
+            .map(|i| Keccak256::digest([i]))
+            .collect::<Vec<_>>();
+        let signatures = keypairs
+            .iter()
+            .zip(&msg_hashes)
+            .enumerate()
+            .map(|(i, (keypair, msg_hash))| {
+                Signature::new(
+                    &mut OsRng,
+                    2 * i,
+                    &ring,
+                    msg_hash,
+                    SCOPE,
+                    &keypair.private,
+                    &parameters,
+                )
+                .unwrap()
+            })
+            .collect::<Vec<_>>();
+        let mut batch = signatures
+            .iter()
+            .zip(&msg_hashes)
+            .map(|(signature, msg_hash)| (signature, msg_hash.as_slice()))
+            .collect::<Vec<_>>();
+
+        assert!(Signature::verify_batch(&mut OsRng, &batch, &ring, SCOPE, &parameters).is_ok());
+        assert!(Signature::verify_batch(&mut OsRng, &[], &ring, SCOPE, &parameters).is_ok());
+        assert!(
+            Signature::verify_batch(&mut OsRng, &batch, &ring, b"other guild", &parameters)
+                .is_err()
+        );
+
+        // a single invalid signature fails the batch
+        let wrong_msg_hash = Keccak256::digest(b"another message");
+        batch[3].1 = &wrong_msg_hash;
+        assert_eq!(
+            Signature::verify_batch(&mut OsRng, &batch, &ring, SCOPE, &parameters),
+            Err("invalid signature".to_owned())
+        );
+        assert!(signatures[3]
+            .verify(&ring, &wrong_msg_hash, SCOPE, &parameters)
+            .is_err());
+
+        // signatures over a ring of another size
+        let small_ring = ring[..4].to_vec();
+        assert_eq!(
+            Signature::verify_batch(&mut OsRng, &batch, &small_ring, SCOPE, &parameters),
+            Err("signature size mismatch".to_owned())
+        );
+    }
+
+    #[test]
+    fn binary_encoding() {
+        let parameters = Parameters::new(2, 10).unwrap();
+        let keypair = Keypair::random();
+        let mut ring = test_ring(5);
+        let index = 4_usize;
+        ring[index] = keypair.public;
+        let msg_hash = Keccak256::digest(b"compact");
+        let signature = Signature::new(
+            &mut OsRng,
+            index,
+            &ring,
+            &msg_hash,
+            SCOPE,
+            &keypair.private,
+            &parameters,
+        )
+        .unwrap();
+
+        let bytes = signature.to_bytes();
+        // m = 3 for a ring of 5 keys
+        assert_eq!(bytes.len(), 3 + 11 * POINT_SIZE + 6 * SCALAR_SIZE);
+        let decoded = Signature::from_bytes(&bytes).unwrap();
+        assert_eq!(decoded.to_bytes(), bytes);
+        assert!(decoded.verify(&ring, &msg_hash, SCOPE, &parameters).is_ok());
+
+        let mut unknown_version = bytes.clone();
+        unknown_version[0] = 1;
+        assert!(Signature::from_bytes(&unknown_version).is_err());
+        let mut invalid_base = bytes.clone();
+        invalid_base[1] = 1;
+        assert!(Signature::from_bytes(&invalid_base).is_err());
+        assert!(Signature::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Signature::from_bytes(&[bytes.as_slice(), &[0]].concat()).is_err());
+
+        // uncompressed prefix of the A commitment
+        let mut invalid_point = bytes.clone();
+        invalid_point[3] = 0x04;
+        assert!(Signature::from_bytes(&invalid_point).is_err());
+        // x-coordinate of the A commitment not reduced modulo p
+        let mut unreduced_point = bytes.clone();
+        unreduced_point[4..4 + 32].copy_from_slice(&[0xff; 32]);
+        assert!(Signature::from_bytes(&unreduced_point).is_err());
+        // z scalar not reduced modulo n
+        let z_offset = 3 + 10 * POINT_SIZE + 5 * SCALAR_SIZE;
+        let mut unreduced_scalar = bytes;
+        unreduced_scalar[z_offset..z_offset + SCALAR_SIZE].copy_from_slice(&[0xff; 32]);
+        assert!(Signature::from_bytes(&unreduced_scalar).is_err());
+    }
+
+    #[test]
+    fn typed_api() {
+        let parameters = Parameters::new(3, 2).unwrap();
+        let signing_key = SigningKey::random(&mut OsRng);
+        let mut keys = test_ring(7);
+        keys[5] = signing_key.public_key();
+        let ring = Ring::new(keys).unwrap();
+        let msg_hash = Keccak256::digest(b"typed");
+
+        let signature = Signature::sign(
+            &mut OsRng,
+            &signing_key,
+            &ring,
+            &msg_hash,
+            SCOPE,
+            &parameters,
+        )
+        .unwrap();
+        assert!(signature
+            .verify(&ring, &msg_hash, SCOPE, &parameters)
+            .is_ok());
+        assert_eq!(
+            Signature::sign(
+                &mut OsRng,
+                &SigningKey::random(&mut OsRng),
+                &ring,
+                &msg_hash,
+                SCOPE,
+                &parameters
+            )
+            .err(),
+            Some("signing key not in ring".to_owned())
+        );
+
+        // the same seed gives the same signature
+        let sign_seeded = |seed| {
+            let mut rng = StdRng::seed_from_u64(seed);
+            Signature::sign(&mut rng, &signing_key, &ring, &msg_hash, SCOPE, &parameters)
+                .unwrap()
+                .to_bytes()
+        };
+        assert_eq!(sign_seeded(7), sign_seeded(7));
+        assert_ne!(sign_seeded(7), sign_seeded(8));
     }
 }
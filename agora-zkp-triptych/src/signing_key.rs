@@ -0,0 +1,84 @@
+use k256::elliptic_curve::{NonZeroScalar, PrimeField};
+use k256::{AffinePoint, FieldBytes, Scalar, Secp256k1};
+use rand_core::{CryptoRng, RngCore};
+use zeroize::Zeroize;
+
+/// Private key of a ring member, zeroized when dropped.
+#[derive(Clone)]
+pub struct SigningKey {
+    secret: Scalar,
+}
+
+impl SigningKey {
+    pub fn random<R: RngCore + CryptoRng>(rng: &mut R) -> Self {
+        Self {
+            secret: *NonZeroScalar::<Secp256k1>::random(rng),
+        }
+    }
+
+    /// Parses a big-endian scalar, rejecting zero and values that are not
+    /// reduced modulo the group order.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() != 32 {
+            return Err("invalid private key length".to_owned());
+        }
+        let secret = Scalar::from_repr(*FieldBytes::from_slice(bytes));
+        if secret.is_none().into() {
+            return Err("failed to parse privkey".to_owned());
+        }
+        let secret = secret.unwrap();
+        if secret == Scalar::ZERO {
+            return Err("invalid private key".to_owned());
+        }
+        Ok(Self { secret })
+    }
+
+    /// Parses a hex encoded private key with an optional `0x` prefix.
+    pub fn from_hex(privkey: &str) -> Result<Self, String> {
+        let mut bytes = hex::decode(privkey.trim_start_matches("0x")).map_err(|e| e.to_string())?;
+        let key = Self::from_bytes(&bytes);
+        bytes.zeroize();
+        key
+    }
+
+    pub fn to_bytes(&self) -> FieldBytes {
+        self.secret.to_bytes()
+    }
+
+    pub fn public_key(&self) -> AffinePoint {
+        (AffinePoint::GENERATOR * self.secret).to_affine()
+    }
+
+    pub(crate) fn secret(&self) -> &Scalar {
+        &self.secret
+    }
+}
+
+impl Drop for SigningKey {
+    fn drop(&mut self) {
+        self.secret.zeroize();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand_core::OsRng;
+
+    #[test]
+    fn parse_signing_key() {
+        for _ in 0..10 {
+            let key = SigningKey::random(&mut OsRng);
+            let hex_string = hex::encode(key.to_bytes());
+            let parsed = SigningKey::from_hex(&hex_string).unwrap();
+            assert_eq!(key.secret, parsed.secret);
+            let parsed = SigningKey::from_hex(&format!("0x{}", hex_string)).unwrap();
+            assert_eq!(key.public_key(), parsed.public_key());
+        }
+
+        assert!(SigningKey::from_bytes(&[0; 32]).is_err());
+        assert!(SigningKey::from_bytes(&[0xff; 32]).is_err());
+        assert!(SigningKey::from_bytes(&[1; 31]).is_err());
+        assert!(SigningKey::from_hex("0xnothex").is_err());
+    }
+}
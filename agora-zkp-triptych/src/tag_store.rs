@@ -0,0 +1,156 @@
+//! Stores of the linking tags already seen, giving one join per key and
+//! scope.
+use k256::elliptic_curve::group::GroupEncoding;
+use k256::AffinePoint;
+
+use std::collections::HashSet;
+
+type Entry = (Vec<u8>, Vec<u8>);
+
+fn entry(scope: &[u8], tag: &AffinePoint) -> Entry {
+    (scope.to_vec(), tag.to_bytes().to_vec())
+}
+
+pub trait TagStore {
+    /// Whether `tag` was already recorded in `scope`.
+    fn contains(&self, scope: &[u8], tag: &AffinePoint) -> Result<bool, String>;
+
+    /// Records `tag` in `scope`, returning `false` if it was already
+    /// recorded.
+    fn insert(&mut self, scope: &[u8], tag: &AffinePoint) -> Result<bool, String>;
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MemoryTagStore {
+    tags: HashSet<Entry>,
+}
+
+impl MemoryTagStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl TagStore for MemoryTagStore {
+    fn contains(&self, scope: &[u8], tag: &AffinePoint) -> Result<bool, String> {
+        Ok(self.tags.contains(&entry(scope, tag)))
+    }
+
+    fn insert(&mut self, scope: &[u8], tag: &AffinePoint) -> Result<bool, String> {
+        Ok(self.tags.insert(entry(scope, tag)))
+    }
+}
+
+/// Tag store persisted in an SQLite database, so several processes can share
+/// it.
+///
+/// Tags are unique per scope in the database itself, an insertion is a single
+/// statement that either records the tag or leaves the table as it is.
+#[cfg(not(target_arch = "wasm32"))]
+pub struct SqliteTagStore {
+    connection: rusqlite::Connection,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl SqliteTagStore {
+    // NOTE writers of other processes hold the lock for a single insertion
+    const BUSY_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+    /// Opens the database at `path`, creating it if it does not exist.
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Self, String> {
+        let connection = rusqlite::Connection::open(path).map_err(|e| e.to_string())?;
+        connection
+            .busy_timeout(Self::BUSY_TIMEOUT)
+            .and_then(|_| {
+                connection.execute(
+                    "CREATE TABLE IF NOT EXISTS tags (
+                        scope BLOB NOT NULL,
+                        tag BLOB NOT NULL,
+                        PRIMARY KEY (scope, tag)
+                    ) WITHOUT ROWID",
+                    (),
+                )
+            })
+            .map_err(|e| e.to_string())?;
+        Ok(Self { connection })
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl TagStore for SqliteTagStore {
+    fn contains(&self, scope: &[u8], tag: &AffinePoint) -> Result<bool, String> {
+        let (scope, tag) = entry(scope, tag);
+        self.connection
+            .query_row(
+                "SELECT EXISTS (SELECT 1 FROM tags WHERE scope = ?1 AND tag = ?2)",
+                (scope, tag),
+                |row| row.get(0),
+            )
+            .map_err(|e| e.to_string())
+    }
+
+    fn insert(&mut self, scope: &[u8], tag: &AffinePoint) -> Result<bool, String> {
+        let (scope, tag) = entry(scope, tag);
+        let inserted = self
+            .connection
+            .execute(
+                "INSERT OR IGNORE INTO tags (scope, tag) VALUES (?1, ?2)",
+                (scope, tag),
+            )
+            .map_err(|e| e.to_string())?;
+        Ok(inserted == 1)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::keypair::Keypair;
+
+    fn check_store<S: TagStore>(store: &mut S) {
+        let tag = Keypair::random().public;
+        assert!(!store.contains(b"guild", &tag).unwrap());
+        assert!(store.insert(b"guild", &tag).unwrap());
+        assert!(store.contains(b"guild", &tag).unwrap());
+        assert!(!store.insert(b"guild", &tag).unwrap());
+        // tags are recorded per scope
+        assert!(store.insert(b"other guild", &tag).unwrap());
+    }
+
+    #[test]
+    fn memory_tag_store() {
+        check_store(&mut MemoryTagStore::new());
+    }
+
+    #[test]
+    fn sqlite_tag_store() {
+        let path = std::env::temp_dir().join(format!("triptych-tags-{}", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+
+        let mut store = SqliteTagStore::open(&path).unwrap();
+        check_store(&mut store);
+        let tag = Keypair::random().public;
+        assert!(store.insert(b"guild", &tag).unwrap());
+
+        // another connection, e.g. of another process, sees the same tags
+        let mut other = SqliteTagStore::open(&path).unwrap();
+        assert!(other.contains(b"guild", &tag).unwrap());
+        assert!(!other.insert(b"guild", &tag).unwrap());
+        let other_tag = Keypair::random().public;
+        assert!(other.insert(b"guild", &other_tag).unwrap());
+        assert!(!store.insert(b"guild", &other_tag).unwrap());
+        drop(store);
+        drop(other);
+
+        // the tags survive reopening the store
+        let mut store = SqliteTagStore::open(&path).unwrap();
+        assert!(store.contains(b"guild", &tag).unwrap());
+        assert!(!store.insert(b"guild", &tag).unwrap());
+        assert!(!store.contains(b"another guild", &tag).unwrap());
+        drop(store);
+
+        std::fs::write(&path, "not a database\n").unwrap();
+        assert!(SqliteTagStore::open(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
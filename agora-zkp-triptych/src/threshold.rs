@@ -0,0 +1,287 @@
+//! k-out-of-N Triptych signatures, proving that k distinct members of a ring
+//! signed a message, e.g. a quorum of moderators approving a guild action.
+//!
+//! Following the multi-input extension of Arcturus, the indices of all
+//! signers are committed to in the same digit commitments and the signers
+//! share the challenge, while every signer proves the knowledge of its key
+//! and its tag separately. A key has a single tag in a scope, so distinct
+//! tags mean distinct signers.
+//!
+//! The signature is generated by a single prover holding all k signing keys,
+//! there is no protocol in which the signers contribute their parts without
+//! revealing their keys to each other. Use it for keys controlled by one
+//! party, e.g. a service acting for several accounts, and collect ordinary
+//! [`Signature`](crate::Signature)s from independent signers instead.
+use crate::ring::pad_ring_to_nm;
+use crate::signature::{
+    challenge, check_size, prove, u_point, BatchEquation, DigitCommitments, MemberProof, Parameters,
+};
+use crate::signing_key::SigningKey;
+use k256::AffinePoint;
+use rand_core::{CryptoRng, RngCore};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ThresholdSignature {
+    base: usize,
+    commitments: DigitCommitments,
+    members: Vec<MemberProof>,
+}
+
+impl ThresholdSignature {
+    /// Signs `message_hash` with the keys of `signers`, given by their index
+    /// in `ring` and their signing key. The caller holds every key, see the
+    /// module documentation.
+    ///
+    /// Signing requires `signers.len() * m` rows of generators for a ring
+    /// of up to `base^m` keys.
+    pub fn new<R: RngCore + CryptoRng>(
+        rng: &mut R,
+        signers: &[(usize, &SigningKey)],
+        ring: &[AffinePoint],
+        message_hash: &[u8],
+        scope: &[u8],
+        parameters: &Parameters,
+    ) -> Result<Self, String> {
+        if signers.is_empty() {
+            return Err("no signers".to_owned());
+        }
+        let mut indices = signers.iter().map(|(index, _)| *index).collect::<Vec<_>>();
+        indices.sort_unstable();
+        indices.dedup();
+        if indices.len() != signers.len() {
+            return Err("signer indices must be distinct".to_owned());
+        }
+
+        let (commitments, members) = prove(rng, signers, ring, message_hash, scope, parameters)?;
+        Ok(Self {
+            base: parameters.base(),
+            commitments,
+            members,
+        })
+    }
+
+    pub fn signer_count(&self) -> usize {
+        self.members.len()
+    }
+
+    /// Linking tags of the signers, the same as the tags of their ordinary
+    /// signatures in the scope.
+    pub fn tags(&self) -> Vec<AffinePoint> {
+        self.members.iter().map(MemberProof::tag).collect()
+    }
+
+    /// Verifies that at least `threshold` distinct members of `ring` signed
+    /// `message_hash`, checking every signer with a single multiscalar
+    /// multiplication weighted by scalars drawn from `rng`.
+    pub fn verify<R: RngCore + CryptoRng>(
+        &self,
+        rng: &mut R,
+        threshold: usize,
+        ring: &[AffinePoint],
+        message_hash: &[u8],
+        scope: &[u8],
+        parameters: &Parameters,
+    ) -> Result<(), String> {
+        if self.members.is_empty() || self.members.len() < threshold {
+            return Err("not enough signers".to_owned());
+        }
+        let tags = self.tags();
+        for (i, tag) in tags.iter().enumerate() {
+            if tags[i + 1..].contains(tag) {
+                return Err("duplicate signer tag".to_owned());
+            }
+        }
+
+        let mut ring = ring.to_owned();
+        let m = pad_ring_to_nm(&mut ring, parameters.base())?;
+        check_size(self.base, &self.members, parameters.base(), m)?;
+        let u_point = u_point(scope);
+        let xi = challenge(
+            &ring,
+            message_hash,
+            scope,
+            &u_point,
+            &self.commitments,
+            &self.members,
+        );
+        let mut equation = BatchEquation::new(ring.len(), parameters, m)?;
+        equation.add(rng, &self.commitments, &self.members, xi);
+        equation.check(&ring, parameters, &u_point)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::keypair::Keypair;
+    use crate::signature::test::forge_tags;
+    use crate::signature::Signature;
+    use rand_core::OsRng;
+    use sha3::{Digest, Keccak256};
+
+    const SCOPE: &[u8] = b"guild";
+
+    fn test_ring(size: usize) -> (Vec<AffinePoint>, Vec<Keypair>) {
+        let keypairs = (0..size).map(|_| Keypair::random()).collect::<Vec<_>>();
+        let ring = keypairs.iter().map(|keypair| keypair.public).collect();
+        (ring, keypairs)
+    }
+
+    #[test]
+    fn k_out_of_n() {
+        let (ring, keypairs) = test_ring(20);
+        let msg_hash = Keccak256::digest(b"ban spammer");
+        let signers = [3, 11, 19]
+            .map(|index| (index, &keypairs[index].private))
+            .to_vec();
+
+        for (base, m) in [(2, 5), (4, 3)] {
+            let parameters = Parameters::new(base, 3 * m).unwrap();
+            let signature =
+                ThresholdSignature::new(&mut OsRng, &signers, &ring, &msg_hash, SCOPE, &parameters)
+                    .unwrap();
+            assert_eq!(signature.signer_count(), 3);
+            assert!(signature
+                .verify(&mut OsRng, 3, &ring, &msg_hash, SCOPE, &parameters)
+                .is_ok());
+            assert!(signature
+                .verify(&mut OsRng, 2, &ring, &msg_hash, SCOPE, &parameters)
+                .is_ok());
+            assert_eq!(
+                signature.verify(&mut OsRng, 4, &ring, &msg_hash, SCOPE, &parameters),
+                Err("not enough signers".to_owned())
+            );
+            assert!(signature
+                .verify(
+                    &mut OsRng,
+                    3,
+                    &ring,
+                    &Keccak256::digest(b"other"),
+                    SCOPE,
+                    &parameters
+                )
+                .is_err());
+            assert!(signature
+                .verify(&mut OsRng, 3, &ring, &msg_hash, b"other guild", &parameters)
+                .is_err());
+            let mut other_ring = ring.clone();
+            other_ring.swap(3, 4);
+            assert!(signature
+                .verify(&mut OsRng, 3, &other_ring, &msg_hash, SCOPE, &parameters)
+                .is_err());
+
+            // the tags are the ones of ordinary signatures in the scope
+            for (tag, (index, privkey)) in signature.tags().iter().zip(&signers) {
+                let single = Signature::new(
+                    &mut OsRng,
+                    *index,
+                    &ring,
+                    &msg_hash,
+                    SCOPE,
+                    privkey,
+                    &parameters,
+                )
+                .unwrap();
+                assert_eq!(*tag, single.tag());
+            }
+        }
+
+        // a single signer is a 1-out-of-N signature
+        let parameters = Parameters::new(2, 5).unwrap();
+        let signature = ThresholdSignature::new(
+            &mut OsRng,
+            &signers[..1],
+            &ring,
+            &msg_hash,
+            SCOPE,
+            &parameters,
+        )
+        .unwrap();
+        assert!(signature
+            .verify(&mut OsRng, 1, &ring, &msg_hash, SCOPE, &parameters)
+            .is_ok());
+        // but more signers need more generators
+        assert_eq!(
+            ThresholdSignature::new(&mut OsRng, &signers, &ring, &msg_hash, SCOPE, &parameters)
+                .err(),
+            Some("not enough generators".to_owned())
+        );
+    }
+
+    #[test]
+    fn invalid_threshold_signatures() {
+        let (ring, keypairs) = test_ring(8);
+        let msg_hash = Keccak256::digest(b"approve");
+        let parameters = Parameters::new(2, 9).unwrap();
+        let sign = |indices: &[usize]| {
+            let signers = indices
+                .iter()
+                .map(|&index| (index, &keypairs[index].private))
+                .collect::<Vec<_>>();
+            ThresholdSignature::new(&mut OsRng, &signers, &ring, &msg_hash, SCOPE, &parameters)
+        };
+
+        assert!(sign(&[]).is_err());
+        assert_eq!(
+            sign(&[1, 5, 1]).err(),
+            Some("signer indices must be distinct".to_owned())
+        );
+
+        // the same signer counted twice
+        let signature = sign(&[1, 5]).unwrap();
+        let mut duplicated = signature.clone();
+        duplicated.members[1] = duplicated.members[0].clone();
+        assert_eq!(
+            duplicated.verify(&mut OsRng, 2, &ring, &msg_hash, SCOPE, &parameters),
+            Err("duplicate signer tag".to_owned())
+        );
+
+        // members do not carry over to another signature
+        let other = sign(&[2, 6]).unwrap();
+        let mut mixed = signature.clone();
+        mixed.members[1] = other.members[1].clone();
+        assert!(mixed
+            .verify(&mut OsRng, 2, &ring, &msg_hash, SCOPE, &parameters)
+            .is_err());
+
+        // a signer outside of the ring
+        let outsider_key = SigningKey::random(&mut OsRng);
+        let mut signers = vec![(1, &keypairs[1].private), (4, &outsider_key)];
+        let outsider =
+            ThresholdSignature::new(&mut OsRng, &signers, &ring, &msg_hash, SCOPE, &parameters)
+                .unwrap();
+        assert!(outsider
+            .verify(&mut OsRng, 2, &ring, &msg_hash, SCOPE, &parameters)
+            .is_err());
+        signers[1].1 = &keypairs[4].private;
+        let insider =
+            ThresholdSignature::new(&mut OsRng, &signers, &ring, &msg_hash, SCOPE, &parameters)
+                .unwrap();
+        assert!(insider
+            .verify(&mut OsRng, 2, &ring, &msg_hash, SCOPE, &parameters)
+            .is_ok());
+    }
+
+    #[test]
+    fn forged_tags_of_one_signer() {
+        let (ring, keypairs) = test_ring(8);
+        let msg_hash = Keccak256::digest(b"approve");
+        let parameters = Parameters::new(2, 9).unwrap();
+
+        // a single key holder filling every slot with a fresh tag
+        let signers = [(5, &keypairs[5].private); 3];
+        let (commitments, members) = forge_tags(&signers, &ring, &msg_hash, SCOPE, &parameters);
+        let forged = ThresholdSignature {
+            base: parameters.base(),
+            commitments,
+            members,
+        };
+        let tags = forged.tags();
+        assert!(tags[0] != tags[1] && tags[0] != tags[2] && tags[1] != tags[2]);
+        assert_eq!(
+            forged.verify(&mut OsRng, 3, &ring, &msg_hash, SCOPE, &parameters),
+            Err("invalid signature".to_owned())
+        );
+    }
+}
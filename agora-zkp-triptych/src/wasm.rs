@@ -0,0 +1,102 @@
+//! Thin wasm-bindgen wrapper around the typed API, taking hex strings and
+//! JS values.
+use crate::ring::{check_base, digit_count, parse_ring, FrontendRing, Ring};
+use crate::signature::{Parameters, Signature};
+use crate::signing_key::SigningKey;
+
+use rand_core::OsRng;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+// NOTE the parameters are derived by the verifier, see `Parameters`
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Proof {
+    pub signature: Signature,
+}
+
+/// Signs `msg_hash` as a member of `ring`. The linking tag of the signature
+/// is the same for every signature of the key in `scope`, e.g. a guild id.
+///
+/// The index of the signer is decomposed in `base`, larger bases mean less
+/// padding and shorter signatures for large rings.
+#[wasm_bindgen]
+pub fn sign(
+    msg_hash: String,
+    privkey: String,
+    index: u32,
+    ring: JsValue,
+    scope: String,
+    base: u32,
+) -> Result<JsValue, JsValue> {
+    let frontend_ring: FrontendRing =
+        serde_wasm_bindgen::from_value(ring).map_err(|e| e.to_string())?;
+    let ring = parse_ring(frontend_ring)?;
+    let parameters = derive_parameters(base as usize, &ring)?;
+    let parsed_msg_hash = parse_msg_hash(&msg_hash)?;
+    let signing_key = SigningKey::from_hex(&privkey)?;
+
+    let signature = Signature::new(
+        &mut OsRng,
+        index as usize,
+        &ring,
+        &parsed_msg_hash,
+        scope.as_bytes(),
+        &signing_key,
+        &parameters,
+    )?;
+
+    let proof = Proof { signature };
+    serde_wasm_bindgen::to_value(&proof).map_err(|e| e.to_string().into())
+}
+
+/// Encodes the proof returned by `sign` into the compact binary format of
+/// `Signature::to_bytes`.
+#[wasm_bindgen(js_name = "encodeProof")]
+pub fn encode_proof(proof: JsValue) -> Result<Vec<u8>, JsValue> {
+    let proof: Proof = serde_wasm_bindgen::from_value(proof).map_err(|e| e.to_string())?;
+    Ok(proof.signature.to_bytes())
+}
+
+/// Decodes a proof encoded by `encodeProof`.
+#[wasm_bindgen(js_name = "decodeProof")]
+pub fn decode_proof(bytes: Vec<u8>) -> Result<JsValue, JsValue> {
+    let proof = Proof {
+        signature: Signature::from_bytes(&bytes)?,
+    };
+    serde_wasm_bindgen::to_value(&proof).map_err(|e| e.to_string().into())
+}
+
+#[wasm_bindgen]
+pub fn verify(
+    msg_hash: String,
+    proof: JsValue,
+    ring: JsValue,
+    scope: String,
+) -> Result<JsValue, JsValue> {
+    let proof: Proof = serde_wasm_bindgen::from_value(proof).map_err(|e| e.to_string())?;
+    let frontend_ring: FrontendRing =
+        serde_wasm_bindgen::from_value(ring).map_err(|e| e.to_string())?;
+    let ring = parse_ring(frontend_ring)?;
+    let parsed_msg_hash = parse_msg_hash(&msg_hash)?;
+
+    proof.signature.verify(
+        &ring,
+        &parsed_msg_hash,
+        scope.as_bytes(),
+        &derive_parameters(proof.signature.base(), &ring)?,
+    )?;
+    Ok(JsValue::from("Proof OK"))
+}
+
+// Derives as many rows of generators as the ring needs in `base`.
+fn derive_parameters(base: usize, ring: &Ring) -> Result<Parameters, String> {
+    check_base(base)?;
+    Parameters::new(base, digit_count(ring.len(), base))
+}
+
+fn parse_msg_hash(msg_hash: &str) -> Result<[u8; 32], String> {
+    let mut bytes = [0u8; 32];
+    hex::decode_to_slice(msg_hash.trim_start_matches("0x"), &mut bytes)
+        .map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
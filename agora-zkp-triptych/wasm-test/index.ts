@@ -4,6 +4,8 @@
 	const privkey = "0xc1e0a5f33f8551ca8725f0b20cfe7a033ff863809100c3301009f2efac3810d6";
 	const msgHash = "0xaaaaaaaabbbbbbbbccccccccddddddddeeeeeeeeffffffff0000000011111111";
 	const index = 4;
+	const scope = "guild";
+	const base = 3;
 	const ring = [
             "0x04b9bc295e1319841c63cbd252e6431ad46611d1b9f5538732cfc61e3fcd9cd8a8ffe625780401f028e7060e978ff755e423cad1fed4a27938545a76ffd427cc78",
     	    "0x043c19ca75eed7cad6146b9f69d62b5da48c9b5757b518c008b5927e7ba59c68cd13d0c11c0927cacc9903c1de89f51448b6fa5636b073c50533f7a9aa08d28351",
@@ -18,8 +20,8 @@
 	];
 
 	try {
-		let proof = sign(msgHash, privkey, index, ring);
-		let result = verify(msgHash, proof, ring);
+		let proof = sign(msgHash, privkey, index, ring, scope, base);
+		let result = verify(msgHash, proof, ring, scope);
 		console.log(result)
 	} catch(error) {
 		throw Error(error)